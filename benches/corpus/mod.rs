@@ -0,0 +1,94 @@
+// 基准测试使用的语料. 为了不在仓库中保存很大的文件, 语料由固定的伪随机序列生成, 每次运行的内容完全相同.
+
+// 与单元测试共用同一个随机数生成器.
+#[allow(dead_code)]
+#[path = "../../src/test_util.rs"]
+mod test_util;
+
+use test_util::Rng;
+
+const WORDS: [&str; 12] = [
+    "panda",
+    "china",
+    "hello",
+    "world",
+    "json",
+    "map",
+    "value",
+    "中文",
+    "naïve",
+    "tab\\t",
+    "quote\\\"d",
+    "line\\nfeed",
+];
+
+/// 以对象为主的文档, 类似于接口返回的记录列表.
+pub fn records(count: usize) -> String {
+    let mut rng = Rng(1);
+    let mut json = String::from("{\"records\":[");
+    for i in 0..count {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"id\":{},\"name\":\"{} {}\",\"active\":{},\"score\":{}.{},\"tags\":[\"{}\",\"{}\"],\"owner\":{{\"id\":{},\"email\":\"user{}@example.com\"}},\"parent\":null}}",
+            i,
+            WORDS[rng.below(12) as usize],
+            WORDS[rng.below(12) as usize],
+            rng.below(2) == 0,
+            rng.below(1000),
+            rng.below(100),
+            WORDS[rng.below(12) as usize],
+            WORDS[rng.below(12) as usize],
+            rng.below(100000),
+            rng.below(100000),
+        ));
+    }
+    json.push_str("]}");
+
+    json
+}
+
+/// 以数字为主的文档, 类似于地理坐标数据.
+pub fn numbers(count: usize) -> String {
+    let mut rng = Rng(2);
+    let mut json = String::from("{\"type\":\"Polygon\",\"coordinates\":[");
+    for i in 0..count {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "[-{}.{:014},{}.{:014}]",
+            rng.below(180),
+            rng.below(100_000_000_000_000),
+            rng.below(90),
+            rng.below(100_000_000_000_000),
+        ));
+    }
+    json.push_str("]}");
+
+    json
+}
+
+/// 以长字符串为主的文档.
+pub fn strings(count: usize) -> String {
+    let mut rng = Rng(3);
+    let mut json = String::from("{\"documents\":[");
+    for i in 0..count {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push('"');
+        for _ in 0..40 {
+            json.push_str(WORDS[rng.below(7) as usize]);
+            json.push(' ');
+        }
+        if rng.below(4) == 0 {
+            json.push_str("\\u00e9\\ud83d\\ude00");
+        }
+        json.push('"');
+    }
+    json.push_str("]}");
+
+    json
+}
@@ -0,0 +1,58 @@
+//! 解析吞吐量的基准测试.
+//!
+//! 运行方式: `cargo bench --bench parse`.
+
+mod corpus;
+
+use mapjson::{Map, MapRef};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let documents = [
+        ("records", corpus::records(20_000)),
+        ("numbers", corpus::numbers(100_000)),
+        ("strings", corpus::strings(10_000)),
+    ];
+
+    for (name, json) in documents.iter() {
+        let size = json.len() as f64 / (1024.0 * 1024.0);
+
+        let elapsed = measure(|| {
+            let mut map = Map::new();
+            map.merge(json).unwrap();
+            black_box(map);
+        });
+        report(name, "Map::merge", size, elapsed);
+
+        let elapsed = measure(|| {
+            black_box(MapRef::parse(json).unwrap());
+        });
+        report(name, "MapRef::parse", size, elapsed);
+    }
+}
+
+// 重复运行直到累计至少一秒, 返回单次运行的平均时间.
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    f();
+
+    let start = Instant::now();
+    let mut iterations = 0;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        iterations += 1;
+    }
+
+    start.elapsed() / iterations
+}
+
+fn report(document: &str, method: &str, size: f64, elapsed: Duration) {
+    println!(
+        "{:<8} {:<14} {:>7.2} MiB {:>10.2?} {:>8.1} MiB/s",
+        document,
+        method,
+        size,
+        elapsed,
+        size / elapsed.as_secs_f64()
+    );
+}
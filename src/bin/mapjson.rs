@@ -0,0 +1,265 @@
+use mapjson::{
+    CodegenSettings, JsonReaderSettings, JsonWriterSettings, Map, Shape, Value, reformat,
+};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+  mapjson fmt [--indent N] [--sort-keys] [FILE]   Pretty-print JSON (default indent: 2)
+  mapjson minify [FILE]                          Remove all insignificant whitespace
+  mapjson validate [FILE...]                     Check that each input loads as a JSON object
+  mapjson get POINTER [FILE]                     Print the value at a JSON Pointer, e.g. /a/0/b
+  mapjson ndjson [FILE]                          Validate and minify newline-delimited JSON
+  mapjson schema [FILE]                          Infer a JSON Schema from newline-delimited JSON
+  mapjson codegen [--name NAME] [FILE]           Generate Rust types from newline-delimited JSON objects
+
+FILE defaults to standard input; `-` also means standard input.
+`fmt`, `minify`, `validate` and `get` accept exactly what the library reader loads into a map:
+the top level must be an object and the reader's default limits apply.
+`fmt` and `minify` keep key order, duplicate keys and number text as written, unless `--sort-keys` is given.";
+
+// 命令行的失败原因, 对应不同的退出码.
+enum Failure {
+    Usage(String),   // 参数错误, 退出码为2.
+    Invalid(String), // 输入不是合法的Json, 找不到值或读写失败, 退出码为1.
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("mapjson: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(Failure::Invalid(message)) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Failure> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(Failure::Usage(String::from("missing command")));
+    };
+
+    let mut indent = 2;
+    let mut sort_keys = false;
+    let mut root_name = None;
+    let mut operands = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--sort-keys" if command == "fmt" => sort_keys = true,
+            "--indent" if command == "fmt" => {
+                let value = iter
+                    .next()
+                    .ok_or(Failure::Usage(String::from("--indent requires a value")))?;
+                indent = value.parse().map_err(|_| {
+                    Failure::Usage(format!("invalid value for --indent: {}", value))
+                })?;
+            }
+            "--name" if command == "codegen" => {
+                let value = iter
+                    .next()
+                    .ok_or(Failure::Usage(String::from("--name requires a value")))?;
+                root_name = Some(value.clone());
+            }
+            "-" => operands.push(arg.as_str()),
+            _ if arg.starts_with('-') => {
+                return Err(Failure::Usage(format!("unknown option: {}", arg)));
+            }
+            _ => operands.push(arg.as_str()),
+        }
+    }
+
+    match command.as_str() {
+        "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        "fmt" => {
+            let file = single_file(&operands)?;
+            let (json, map) = read_json(file)?;
+            let settings = JsonWriterSettings {
+                indentation: " ".repeat(indent),
+                trailing_newline: true,
+                sort_keys,
+                ..Default::default()
+            };
+            if sort_keys {
+                write_stdout(map.to_json_with_settings(settings).as_bytes())
+            } else {
+                write_reformatted(file, &json, &settings)
+            }
+        }
+        "minify" => {
+            let file = single_file(&operands)?;
+            let (json, _) = read_json(file)?;
+            let settings = JsonWriterSettings {
+                trailing_newline: true,
+                ..Default::default()
+            };
+            write_reformatted(file, &json, &settings)
+        }
+        "validate" => {
+            let files = if operands.is_empty() {
+                vec!["-"]
+            } else {
+                operands
+            };
+
+            // 检查所有文件后再报告失败, 这样一次就能看到所有出错的文件.
+            let mut failed = Vec::new();
+            for file in files {
+                if let Err(Failure::Invalid(e) | Failure::Usage(e)) = read_json(file) {
+                    failed.push(e);
+                }
+            }
+
+            match failed.is_empty() {
+                true => Ok(()),
+                false => Err(Failure::Invalid(failed.join("\n"))),
+            }
+        }
+        "get" => {
+            let Some((pointer, operands)) = operands.split_first() else {
+                return Err(Failure::Usage(String::from("missing JSON Pointer")));
+            };
+            let file = single_file(operands)?;
+            let (_, map) = read_json(file)?;
+            let settings = JsonWriterSettings {
+                indentation: "  ".to_string(),
+                trailing_newline: true,
+                ..Default::default()
+            };
+
+            let json = if pointer.is_empty() {
+                map.to_json_with_settings(settings)
+            } else {
+                map.pointer(pointer)
+                    .ok_or_else(|| Failure::Invalid(format!("No value at {}", pointer)))?
+                    .to_json_with_settings(settings)
+            };
+            write_stdout(json.as_bytes())
+        }
+        "ndjson" => {
+            let file = single_file(&operands)?;
+            let input = BufReader::new(open(file)?);
+            let mut output = stdout();
+            let settings = JsonWriterSettings::default();
+
+            for (i, line) in input.lines().enumerate() {
+                let line = line.map_err(|e| Failure::Invalid(e.to_string()))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                // 与`validate`, `fmt`和`minify`使用相同的读取器和限制检查, 再保留原文的顺序写出.
+                let json = Value::from_json_with_settings(&line, JsonReaderSettings::default())
+                    .and_then(|_| reformat(&line, &settings))
+                    .map_err(|e| {
+                        Failure::Invalid(format!("{}: line {}: {}", name(file), i + 1, e))
+                    })?;
+                writeln!(output, "{}", json).map_err(|e| Failure::Invalid(e.to_string()))?;
+            }
+            output.flush().map_err(|e| Failure::Invalid(e.to_string()))
+        }
+        "schema" => {
+            let file = single_file(&operands)?;
+            let mut shape = Shape::new();
+            shape
+                .add_json_lines(BufReader::new(open(file)?))
+                .map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+            let settings = JsonWriterSettings {
+                indentation: "  ".to_string(),
+                trailing_newline: true,
+                sort_keys: true,
+                ..Default::default()
+            };
+            write_stdout(
+                shape
+                    .to_json_schema()
+                    .to_json_with_settings(settings)
+                    .as_bytes(),
+            )
+        }
+        "codegen" => {
+            let file = single_file(&operands)?;
+            let mut shape = Shape::new();
+            shape
+                .add_json_lines(BufReader::new(open(file)?))
+                .map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+            let mut settings = CodegenSettings::default();
+            if let Some(root_name) = root_name {
+                settings.root_name = root_name;
+            }
+            let code = shape
+                .to_rust(&settings)
+                .map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+            write_stdout(code.as_bytes())
+        }
+        _ => Err(Failure::Usage(format!("unknown command: {}", command))),
+    }
+}
+
+fn single_file<'a>(operands: &[&'a str]) -> Result<&'a str, Failure> {
+    match *operands {
+        [] => Ok("-"),
+        [file] => Ok(file),
+        _ => Err(Failure::Usage(String::from("too many arguments"))),
+    }
+}
+
+fn name(file: &str) -> &str {
+    if file == "-" { "<stdin>" } else { file }
+}
+
+fn open(file: &str) -> Result<Box<dyn Read>, Failure> {
+    if file == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+
+    File::open(file)
+        .map(|file| Box::new(file) as Box<dyn Read>)
+        .map_err(|e| Failure::Invalid(format!("{}: {}", file, e)))
+}
+
+// 读取整个输入, 并用与`Map::merge`相同的`JsonReader`和设置解析. 返回原文和解析出的`Map`.
+fn read_json(file: &str) -> Result<(String, Map), Failure> {
+    let mut json = String::new();
+    open(file)?
+        .read_to_string(&mut json)
+        .map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+
+    let mut map = Map::new();
+    map.merge_with_settings(json.as_str(), JsonReaderSettings::default())
+        .map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+    Ok((json, map))
+}
+
+// 保留原文中成员的顺序, 重复的名称和数字的文本, 重新排版后写入标准输出.
+fn write_reformatted(file: &str, json: &str, settings: &JsonWriterSettings) -> Result<(), Failure> {
+    let json =
+        reformat(json, settings).map_err(|e| Failure::Invalid(format!("{}: {}", name(file), e)))?;
+    write_stdout(json.as_bytes())
+}
+
+fn stdout() -> BufWriter<io::StdoutLock<'static>> {
+    BufWriter::new(io::stdout().lock())
+}
+
+fn write_stdout(bytes: &[u8]) -> Result<(), Failure> {
+    let mut output = stdout();
+    output
+        .write_all(bytes)
+        .and_then(|_| output.flush())
+        .map_err(|e| Failure::Invalid(e.to_string()))
+}
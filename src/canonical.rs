@@ -0,0 +1,265 @@
+use crate::{Map, Number, Value};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// 为`Value`提供`Eq`, `Hash`和全序`Ord`的包装, 可以用作`HashSet`的元素, `HashMap`的键或用于排序.
+///
+/// 比较规则如下:
+///
+/// - 先按类型排序: `Null` < `Bool` < 数字 < `String` < `Vec` < `Object`.
+/// - `I64`与`F64`同属数字, 按数学值比较, 因此`I64(1) == F64(1.0)`, `-0.0 == 0.0`;
+///   所有`NaN`彼此相等, 并且大于其他任何数字.
+/// - 字符串按字节比较, 数组按元素逐个比较.
+/// - 对象按名称排序后逐个比较成员(先比较名称, 再比较值), 哈希值与成员的存储顺序无关.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{CanonicalValue, Value};
+/// use std::collections::HashSet;
+///
+/// let values = vec![Value::I64(1), Value::F64(1.0), Value::Null, Value::F64(-0.0), Value::I64(0)];
+/// let set: HashSet<CanonicalValue> = values.into_iter().map(CanonicalValue::from).collect();
+/// assert_eq!(set.len(), 3);
+///
+/// let mut vec = vec![Value::String("a".to_string()), Value::F64(2.5), Value::Bool(true)];
+/// vec.sort_by(Value::canonical_cmp);
+/// assert_eq!(Value::Vec(vec).to_json(), r#"[true,2.5,"a"]"#);
+/// ```
+#[derive(Clone)]
+pub struct CanonicalValue(pub Value);
+
+impl CanonicalValue {
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl From<Value> for CanonicalValue {
+    fn from(value: Value) -> Self {
+        CanonicalValue(value)
+    }
+}
+
+impl Deref for CanonicalValue {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl PartialEq for CanonicalValue {
+    fn eq(&self, other: &Self) -> bool {
+        compare(&self.0, &other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for CanonicalValue {}
+
+impl PartialOrd for CanonicalValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CanonicalValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.0, &other.0)
+    }
+}
+
+impl Hash for CanonicalValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash(&self.0, state);
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::F64(_) | Value::I64(_) => 2,
+        Value::String(_) => 3,
+        Value::Vec(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+fn is_nan(n: Number) -> bool {
+    matches!(n, Number::F64(f) if f.is_nan())
+}
+
+fn compare_numbers(a: Number, b: Number) -> Ordering {
+    a.partial_cmp(&b)
+        .unwrap_or_else(|| is_nan(a).cmp(&is_nan(b)))
+}
+
+// 按名称排序的成员, 使比较和哈希与存储顺序无关.
+fn sorted_entries(map: &Map) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+pub(crate) fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Vec(x), Value::Vec(y)) => {
+            for (x, y) in x.iter().zip(y) {
+                match compare(x, y) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            let (x, y) = (sorted_entries(x), sorted_entries(y));
+            for ((xk, xv), (yk, yv)) in x.iter().zip(&y) {
+                match xk.cmp(yk).then_with(|| compare(xv, yv)) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+            }
+            x.len().cmp(&y.len())
+        }
+        _ => match (a.as_number(), b.as_number()) {
+            (Some(x), Some(y)) => compare_numbers(x, y),
+            _ => type_rank(a).cmp(&type_rank(b)),
+        },
+    }
+}
+
+pub(crate) fn hash<H: Hasher>(value: &Value, state: &mut H) {
+    type_rank(value).hash(state);
+    match value {
+        Value::Null => {}
+        Value::Bool(b) => b.hash(state),
+        Value::F64(_) | Value::I64(_) => {
+            // 与某个`i64`相等的数字都按`i64`计算, 保证相等的数字哈希值也相等.
+            let n = value.as_number().unwrap();
+            match (n.to_i64(), n) {
+                (Ok(i), _) => i.hash(state),
+                (Err(_), Number::F64(f)) if f.is_nan() => f64::NAN.to_bits().hash(state),
+                (Err(_), Number::F64(f)) => f.to_bits().hash(state),
+                (Err(_), Number::I64(_)) => unreachable!(),
+            }
+        }
+        Value::String(s) => s.hash(state),
+        Value::Vec(vec) => {
+            vec.len().hash(state);
+            for v in vec {
+                hash(v, state);
+            }
+        }
+        Value::Object(map) => {
+            map.len().hash(state);
+            for (k, v) in sorted_entries(map) {
+                k.hash(state);
+                hash(v, state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash_of(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        CanonicalValue(value.clone()).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn parse(json: &str) -> Value {
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        Value::Object(map)
+    }
+
+    #[test]
+    fn numbers() {
+        let equal = [
+            (Value::I64(1), Value::F64(1.0)),
+            (Value::F64(-0.0), Value::F64(0.0)),
+            (Value::F64(-0.0), Value::I64(0)),
+            (Value::F64(f64::NAN), Value::F64(-f64::NAN)),
+            (Value::I64(i64::MIN), Value::F64(-9223372036854775808.0)),
+        ];
+        for (a, b) in equal {
+            assert_eq!(compare(&a, &b), Ordering::Equal);
+            assert_eq!(hash_of(&a), hash_of(&b));
+        }
+
+        let ascending = [
+            Value::F64(f64::NEG_INFINITY),
+            Value::I64(i64::MIN),
+            Value::F64(-0.5),
+            Value::I64(0),
+            Value::F64(0.5),
+            Value::I64(9007199254740992),
+            Value::I64(9007199254740993),
+            Value::F64(9007199254740994.0),
+            Value::I64(i64::MAX),
+            Value::F64(9223372036854775808.0),
+            Value::F64(f64::INFINITY),
+            Value::F64(f64::NAN),
+        ];
+        for pair in ascending.windows(2) {
+            assert_eq!(compare(&pair[0], &pair[1]), Ordering::Less);
+            assert_eq!(compare(&pair[1], &pair[0]), Ordering::Greater);
+        }
+    }
+
+    #[test]
+    fn type_order() {
+        let ascending = [
+            Value::Null,
+            Value::Bool(false),
+            Value::Bool(true),
+            Value::F64(f64::NAN),
+            Value::String(String::new()),
+            Value::String("a".to_string()),
+            Value::Vec(Vec::new()),
+            Value::Vec(vec![Value::I64(1)]),
+            Value::Vec(vec![Value::I64(1), Value::Null]),
+            Value::Vec(vec![Value::I64(2)]),
+            parse("{}"),
+            parse(r#"{"a":1}"#),
+            parse(r#"{"a":1,"b":0}"#),
+            parse(r#"{"a":2}"#),
+            parse(r#"{"b":0}"#),
+        ];
+        let mut sorted: Vec<CanonicalValue> = ascending
+            .iter()
+            .rev()
+            .cloned()
+            .map(CanonicalValue)
+            .collect();
+        sorted.sort();
+        for (a, b) in sorted.iter().zip(&ascending) {
+            assert_eq!(compare(a, b), Ordering::Equal);
+        }
+    }
+
+    #[test]
+    fn maps() {
+        let a = parse(r#"{"x":1,"y":[1.0,{"z":null}],"w":"s"}"#);
+        let b = parse(r#"{"w":"s","y":[1,{"z":null}],"x":1.0}"#);
+        assert!(a != b);
+        assert_eq!(compare(&a, &b), Ordering::Equal);
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        let mut set = HashSet::new();
+        assert!(set.insert(CanonicalValue(a)));
+        assert!(!set.insert(CanonicalValue(b)));
+        assert!(set.insert(CanonicalValue(parse(r#"{"x":1}"#))));
+    }
+}
@@ -0,0 +1,972 @@
+use crate::{Map, Value};
+use std::collections::hash_map;
+use std::{mem, slice, vec};
+
+/// 将`Value`转换成CBOR (RFC 8949)的转换器.
+pub struct CborWriter {
+    settings: CborWriterSettings,
+}
+
+impl CborWriter {
+    pub fn new(settings: CborWriterSettings) -> Self {
+        CborWriter { settings }
+    }
+
+    // 嵌套的对象和数组通过显式的栈写入, 而不是递归, 因此写入深度不受线程栈大小约束.
+    pub fn format(&self, value: &Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut stack: Vec<Entries> = Vec::new();
+        let mut next = Some(value);
+
+        loop {
+            if let Some(value) = next.take()
+                && let Some(entries) = self.write_value(&mut out, value)
+            {
+                stack.push(entries);
+            }
+
+            let Some(entries) = stack.last_mut() else {
+                break;
+            };
+            match entries.next() {
+                Some((key, val)) => {
+                    if let Some(key) = key {
+                        write_head(&mut out, MAJOR_TEXT, key.len() as u64);
+                        out.extend_from_slice(key.as_bytes());
+                    }
+                    next = Some(val);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        out
+    }
+
+    // 写入一个值. 对于对象和数组, 只写入头部, 并返回需要继续写入的成员.
+    fn write_value<'a>(&self, out: &mut Vec<u8>, value: &'a Value) -> Option<Entries<'a>> {
+        match *value {
+            Value::Null => out.push(SIMPLE_NULL),
+            Value::Bool(false) => out.push(SIMPLE_FALSE),
+            Value::Bool(true) => out.push(SIMPLE_TRUE),
+            Value::I64(n) if n >= 0 => write_head(out, MAJOR_UNSIGNED, n as u64),
+            Value::I64(n) => write_head(out, MAJOR_NEGATIVE, !n as u64),
+            Value::F64(n) => self.write_f64(out, n),
+            Value::String(ref s) => {
+                write_head(out, MAJOR_TEXT, s.len() as u64);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Value::Vec(ref vec) => {
+                write_head(out, MAJOR_ARRAY, vec.len() as u64);
+                return Some(Entries::Vec(vec.iter()));
+            }
+            Value::Object(ref obj) => {
+                write_head(out, MAJOR_MAP, obj.len() as u64);
+                if !self.settings.deterministic {
+                    return Some(Entries::Struct(obj.iter()));
+                }
+
+                // 确定性编码要求按名称编码后的字节排序. 名称都是文本串, 头部按长度单调递增,
+                // 所以等价于先按长度, 再按字节排序.
+                let mut entries: Vec<_> = obj.iter().collect();
+                entries.sort_unstable_by(|a, b| {
+                    (a.0.len(), a.0.as_bytes()).cmp(&(b.0.len(), b.0.as_bytes()))
+                });
+                return Some(Entries::Sorted(entries.into_iter()));
+            }
+        }
+
+        None
+    }
+
+    // 确定性编码时, 使用能精确表示该值的最短浮点格式, NaN统一写为半精度的0x7e00; 否则总是写为双精度.
+    fn write_f64(&self, out: &mut Vec<u8>, n: f64) {
+        if self.settings.deterministic {
+            if n.is_nan() {
+                out.extend_from_slice(&[MAJOR_SIMPLE << 5 | 25, 0x7e, 0x00]);
+                return;
+            }
+
+            let single = n as f32;
+            if single as f64 == n {
+                match f32_to_f16(single) {
+                    Some(half) => {
+                        out.push(MAJOR_SIMPLE << 5 | 25);
+                        out.extend_from_slice(&half.to_be_bytes());
+                    }
+                    None => {
+                        out.push(MAJOR_SIMPLE << 5 | 26);
+                        out.extend_from_slice(&single.to_be_bytes());
+                    }
+                }
+                return;
+            }
+        }
+
+        out.push(MAJOR_SIMPLE << 5 | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+// 写入栈中尚未写完的对象或数组的成员.
+enum Entries<'a> {
+    Struct(hash_map::Iter<'a, String, Value>),
+    Sorted(vec::IntoIter<(&'a String, &'a Value)>),
+    Vec(slice::Iter<'a, Value>),
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (Option<&'a String>, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Entries::Struct(ref mut iter) => iter.next().map(|(k, v)| (Some(k), v)),
+            Entries::Sorted(ref mut iter) => iter.next().map(|(k, v)| (Some(k), v)),
+            Entries::Vec(ref mut iter) => iter.next().map(|v| (None, v)),
+        }
+    }
+}
+
+// 写入数据项的头部, 总是使用能容纳`arg`的最短形式.
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..24 => out.push(major | arg as u8),
+        24..0x100 => out.extend_from_slice(&[major | 24, arg as u8]),
+        0x100..0x1_0000 => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..0x1_0000_0000 => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+// 如果`n`可以精确地表示为半精度浮点数, 返回其位模式. 不处理NaN.
+fn f32_to_f16(n: f32) -> Option<u16> {
+    let bits = n.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exp == 0xff {
+        return Some(sign | 0x7c00); // 无穷大
+    }
+    if exp == 0 {
+        // 零, 单精度的非规格化数都太小, 无法表示
+        return (mantissa == 0).then_some(sign);
+    }
+
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+        return None;
+    }
+    if half_exp >= 1 {
+        return (mantissa & 0x1fff == 0)
+            .then_some(sign | (half_exp as u16) << 10 | (mantissa >> 13) as u16);
+    }
+
+    // 半精度的非规格化数: 值为 m * 2^-24
+    let shift = 126 - exp;
+    if shift >= 25 {
+        return None;
+    }
+    let full = mantissa | 0x80_0000;
+    (full & ((1 << shift) - 1) == 0).then_some(sign | (full >> shift) as u16)
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f64;
+
+    sign * match exp {
+        0 => mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exp - 25),
+    }
+}
+
+/// 将CBOR (RFC 8949)转换成`Value`的转换器.
+///
+/// 解析过程使用显式的栈而不是递归, 因此嵌套深度只受`CborReaderSettings`中的限制约束.
+pub struct CborReader {
+    settings: CborReaderSettings,
+}
+
+// 解析栈中尚未结束的容器. `remaining`为剩余的成员数, None表示不定长, 以break结束.
+enum Frame {
+    Array {
+        vec: Vec<Value>,
+        remaining: Option<usize>,
+    },
+    Map {
+        map: Map,
+        remaining: Option<usize>,
+        key: Option<String>, // 已读取名称, 正在等待其值的成员.
+    },
+}
+
+// 正在读取的输入.
+struct Input<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl CborReader {
+    pub fn new(settings: CborReaderSettings) -> Self {
+        CborReader { settings }
+    }
+
+    pub fn parse(&self, bytes: &[u8]) -> Result<Value, String> {
+        if bytes.len() > self.settings.max_input_length {
+            return Err(format!(
+                "The set input length limit is exceeded: {}",
+                self.settings.max_input_length
+            ));
+        }
+
+        let mut input = Input { bytes, pos: 0 };
+        let mut stack = Vec::new();
+        let mut nodes = 0;
+        let mut tagged = false;
+
+        loop {
+            let expecting_key = matches!(stack.last(), Some(Frame::Map { key: None, .. }));
+            let (major, info) = input.read_initial()?;
+            let after_tag = mem::replace(&mut tagged, major == MAJOR_TAG);
+
+            if major == MAJOR_SIMPLE && info == INDEFINITE {
+                // 标签之后必须是被标记的值
+                if after_tag {
+                    return Err(String::from("Unexpected CBOR break after a tag"));
+                }
+                let value = match stack.pop() {
+                    Some(Frame::Array {
+                        vec,
+                        remaining: None,
+                    }) => Value::Vec(vec),
+                    Some(Frame::Map {
+                        map,
+                        remaining: None,
+                        key: None,
+                    }) => Value::Object(map),
+                    _ => return Err(String::from("Unexpected CBOR break")),
+                };
+                match self.complete(&mut stack, value)? {
+                    Some(root) => return input.finish(root),
+                    None => continue,
+                }
+            }
+
+            if major == MAJOR_TAG {
+                let tag = input.read_argument(info)?;
+                match self.settings.tags {
+                    CborTagPolicy::Reject => return Err(format!("Unsupported CBOR tag: {}", tag)),
+                    CborTagPolicy::Ignore => continue,
+                }
+            }
+
+            if expecting_key
+                && major != MAJOR_TEXT
+                && self.settings.map_keys == MapKeyPolicy::Reject
+            {
+                return Err(format!("Unsupported CBOR map key of major type {}", major));
+            }
+
+            nodes += 1;
+            if nodes > self.settings.max_nodes {
+                return Err(format!(
+                    "The set node limit is exceeded: {}",
+                    self.settings.max_nodes
+                ));
+            }
+
+            let value = match major {
+                MAJOR_UNSIGNED => {
+                    let n = input.read_argument(info)?;
+                    Value::I64(
+                        i64::try_from(n)
+                            .map_err(|_| format!("CBOR integer out of range for I64: {}", n))?,
+                    )
+                }
+                MAJOR_NEGATIVE => {
+                    let n = input.read_argument(info)?;
+                    match i64::try_from(n) {
+                        Ok(n) => Value::I64(-1 - n),
+                        Err(_) => {
+                            return Err(format!("CBOR integer out of range for I64: -1-{}", n));
+                        }
+                    }
+                }
+                MAJOR_BYTES => {
+                    let bytes = self.read_string(&mut input, MAJOR_BYTES, info)?;
+                    match self.settings.byte_strings {
+                        ByteStringPolicy::Reject => {
+                            return Err(String::from("Unsupported CBOR byte string"));
+                        }
+                        ByteStringPolicy::Base64 => Value::String(encode_base64(&bytes)),
+                        // 展开后与数组相同, 受相同的长度限制.
+                        ByteStringPolicy::Array if bytes.len() > self.settings.max_array_length => {
+                            return Err(limit_error(MAJOR_ARRAY, self.settings.max_array_length));
+                        }
+                        ByteStringPolicy::Array => {
+                            Value::Vec(bytes.iter().map(|&b| Value::I64(b as i64)).collect())
+                        }
+                    }
+                }
+                MAJOR_TEXT => {
+                    let bytes = self.read_string(&mut input, MAJOR_TEXT, info)?;
+                    Value::String(
+                        String::from_utf8(bytes)
+                            .map_err(|_| String::from("Invalid UTF-8 in CBOR text string"))?,
+                    )
+                }
+                MAJOR_ARRAY | MAJOR_MAP => {
+                    let len = input.read_length(info, if major == MAJOR_MAP { 2 } else { 1 })?;
+                    let limit = if major == MAJOR_MAP {
+                        self.settings.max_object_members
+                    } else {
+                        self.settings.max_array_length
+                    };
+                    if let Some(len) = len
+                        && len > limit
+                    {
+                        return Err(limit_error(major, limit));
+                    }
+
+                    match (major, len) {
+                        (MAJOR_ARRAY, Some(0)) => Value::Vec(Vec::new()),
+                        (_, Some(0)) => Value::Object(Map::new()),
+                        _ => {
+                            if stack.len() >= self.settings.depth_limit {
+                                return Err(format!(
+                                    "The set depth limit is exceeded: {}",
+                                    self.settings.depth_limit
+                                ));
+                            }
+                            stack.push(if major == MAJOR_ARRAY {
+                                Frame::Array {
+                                    vec: Vec::with_capacity(len.unwrap_or(0)),
+                                    remaining: len,
+                                }
+                            } else {
+                                Frame::Map {
+                                    map: Map::new(),
+                                    remaining: len,
+                                    key: None,
+                                }
+                            });
+                            continue;
+                        }
+                    }
+                }
+                _ => match input.read_simple(info)? {
+                    Value::F64(n) if !n.is_finite() => match self.settings.non_finite {
+                        NonFinitePolicy::Reject => {
+                            return Err(format!("Unsupported non-finite CBOR float: {}", n));
+                        }
+                        NonFinitePolicy::Null => Value::Null,
+                    },
+                    value => value,
+                },
+            };
+
+            if let Some(root) = self.complete(&mut stack, value)? {
+                return input.finish(root);
+            }
+        }
+    }
+
+    // 将读取完的值加入栈顶的容器. 容器因此结束时, 继续将它加入上一层容器. 栈为空时返回最外层的值.
+    fn complete(&self, stack: &mut Vec<Frame>, mut value: Value) -> Result<Option<Value>, String> {
+        loop {
+            let done = match stack.last_mut() {
+                None => return Ok(Some(value)),
+                Some(Frame::Array { vec, remaining }) => {
+                    if vec.len() >= self.settings.max_array_length {
+                        return Err(limit_error(MAJOR_ARRAY, self.settings.max_array_length));
+                    }
+                    vec.push(value);
+                    count_down(remaining)
+                }
+                Some(Frame::Map {
+                    map,
+                    remaining,
+                    key,
+                }) => match key.take() {
+                    None => {
+                        *key = Some(self.map_key(value)?);
+                        return Ok(None);
+                    }
+                    Some(name) => {
+                        if map.len() >= self.settings.max_object_members {
+                            return Err(limit_error(MAJOR_MAP, self.settings.max_object_members));
+                        }
+                        map.insert(name, value);
+                        count_down(remaining)
+                    }
+                },
+            };
+            if !done {
+                return Ok(None);
+            }
+
+            value = match stack.pop() {
+                Some(Frame::Array { vec, .. }) => Value::Vec(vec),
+                Some(Frame::Map { map, .. }) => Value::Object(map),
+                None => return Ok(None),
+            };
+        }
+    }
+
+    // 将读取的值转换为成员名称. 不是文本串的名称只有在`MapKeyPolicy::Stringify`时才会读到这里.
+    fn map_key(&self, mut value: Value) -> Result<String, String> {
+        if let Value::String(ref mut s) = value {
+            return Ok(mem::take(s));
+        }
+
+        match self.settings.map_keys {
+            MapKeyPolicy::Reject => Err(String::from("Unsupported CBOR map key")),
+            MapKeyPolicy::Stringify => Ok(match value {
+                Value::I64(n) => n.to_string(),
+                _ => value.to_json(),
+            }),
+        }
+    }
+
+    // 读取字节串或文本串的内容. 不定长的串由若干个同类型的定长串组成, 以break结束.
+    fn read_string(&self, input: &mut Input, major: u8, info: u8) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        if info != INDEFINITE {
+            let len = input.read_length(info, 1)?.unwrap_or(0);
+            self.check_string_length(len)?;
+            bytes.extend_from_slice(input.take(len)?);
+            return Ok(bytes);
+        }
+
+        loop {
+            let (chunk_major, chunk_info) = input.read_initial()?;
+            if chunk_major == MAJOR_SIMPLE && chunk_info == INDEFINITE {
+                return Ok(bytes);
+            }
+            if chunk_major != major || chunk_info == INDEFINITE {
+                return Err(String::from(
+                    "Invalid chunk in indefinite-length CBOR string",
+                ));
+            }
+
+            let len = input.read_length(chunk_info, 1)?.unwrap_or(0);
+            self.check_string_length(bytes.len().saturating_add(len))?;
+            bytes.extend_from_slice(input.take(len)?);
+        }
+    }
+
+    fn check_string_length(&self, len: usize) -> Result<(), String> {
+        if len > self.settings.max_string_length {
+            Err(format!(
+                "The set string length limit is exceeded: {}",
+                self.settings.max_string_length
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn count_down(remaining: &mut Option<usize>) -> bool {
+    match remaining {
+        Some(n) => {
+            *n -= 1;
+            *n == 0
+        }
+        None => false,
+    }
+}
+
+fn limit_error(major: u8, limit: usize) -> String {
+    if major == MAJOR_MAP {
+        format!("The set object member limit is exceeded: {}", limit)
+    } else {
+        format!("The set array length limit is exceeded: {}", limit)
+    }
+}
+
+impl<'a> Input<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.pos < len {
+            return Err(String::from("Unexpected end of CBOR input"));
+        }
+
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    // 读取初始字节, 返回主类型和附加信息.
+    fn read_initial(&mut self) -> Result<(u8, u8), String> {
+        let [initial] = self.take_array::<1>()?;
+        Ok((initial >> 5, initial & 0x1f))
+    }
+
+    fn read_argument(&mut self, info: u8) -> Result<u64, String> {
+        match info {
+            0..24 => Ok(info as u64),
+            24 => Ok(u8::from_be_bytes(self.take_array()?) as u64),
+            25 => Ok(u16::from_be_bytes(self.take_array()?) as u64),
+            26 => Ok(u32::from_be_bytes(self.take_array()?) as u64),
+            27 => Ok(u64::from_be_bytes(self.take_array()?)),
+            _ => Err(format!("Invalid CBOR additional information: {}", info)),
+        }
+    }
+
+    // 读取长度, None表示不定长. 每个成员至少占用`min_item_size`个字节, 因此声明的长度超过剩余输入时
+    // 直接报错, 不会按照不可信的长度分配内存.
+    fn read_length(&mut self, info: u8, min_item_size: usize) -> Result<Option<usize>, String> {
+        if info == INDEFINITE {
+            return Ok(None);
+        }
+
+        let len = self.read_argument(info)?;
+        let remaining = (self.bytes.len() - self.pos) / min_item_size;
+        match usize::try_from(len) {
+            Ok(len) if len <= remaining => Ok(Some(len)),
+            _ => Err(String::from("Unexpected end of CBOR input")),
+        }
+    }
+
+    // 读取主类型7中的简单值和浮点数.
+    fn read_simple(&mut self, info: u8) -> Result<Value, String> {
+        match info {
+            20 => Ok(Value::Bool(false)),
+            21 => Ok(Value::Bool(true)),
+            22 | 23 => Ok(Value::Null), // null和undefined
+            25 => Ok(Value::F64(f16_to_f64(u16::from_be_bytes(
+                self.take_array()?,
+            )))),
+            26 => Ok(Value::F64(f32::from_be_bytes(self.take_array()?) as f64)),
+            27 => Ok(Value::F64(f64::from_be_bytes(self.take_array()?))),
+            24 => {
+                let [simple] = self.take_array::<1>()?;
+                Err(format!("Unsupported CBOR simple value: {}", simple))
+            }
+            0..20 => Err(format!("Unsupported CBOR simple value: {}", info)),
+            _ => Err(format!("Invalid CBOR additional information: {}", info)),
+        }
+    }
+
+    fn finish(&self, root: Value) -> Result<Value, String> {
+        if self.pos != self.bytes.len() {
+            return Err(String::from("Unexpected data after the end of CBOR item"));
+        }
+
+        Ok(root)
+    }
+}
+
+// CBOR的主类型.
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const INDEFINITE: u8 = 31; // 附加信息31: 不定长, 在主类型7中表示break.
+const SIMPLE_FALSE: u8 = 0xf4;
+const SIMPLE_TRUE: u8 = 0xf5;
+const SIMPLE_NULL: u8 = 0xf6;
+
+// 使用标准字母表和填充的Base64编码.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+
+    text
+}
+
+/// 写入CBOR时使用的设置.
+#[derive(Default)]
+pub struct CborWriterSettings {
+    pub deterministic: bool, // 是否使用RFC 8949 §4.2.1的确定性编码: 最短的浮点格式, 按编码后的名称排序对象成员.
+}
+
+/// 读取CBOR时使用的设置. 各项限制与`JsonReaderSettings`中的同名限制含义相同.
+pub struct CborReaderSettings {
+    pub depth_limit: usize,             // 对象与数组的最大嵌套深度.
+    pub max_input_length: usize,        // 输入的最大长度(字节).
+    pub max_string_length: usize,       // 单个文本串或字节串(包括成员名称)的最大长度(字节).
+    pub max_array_length: usize,        // 单个数组(包括展开为数组的字节串)的最大元素个数.
+    pub max_object_members: usize,      // 单个对象的最大成员个数.
+    pub max_nodes: usize,               // 整个文档中值(包括对象与数组本身)的最大个数.
+    pub tags: CborTagPolicy,            // 遇到标签时的处理方式.
+    pub byte_strings: ByteStringPolicy, // 遇到字节串时的处理方式.
+    pub map_keys: MapKeyPolicy,         // 遇到不是文本串的成员名称时的处理方式.
+    pub non_finite: NonFinitePolicy,    // 遇到Json不能表示的无穷大和NaN时的处理方式.
+}
+
+impl Default for CborReaderSettings {
+    fn default() -> Self {
+        CborReaderSettings {
+            depth_limit: 128,
+            max_input_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_members: usize::MAX,
+            max_nodes: usize::MAX,
+            tags: CborTagPolicy::Reject,
+            byte_strings: ByteStringPolicy::Reject,
+            map_keys: MapKeyPolicy::Reject,
+            non_finite: NonFinitePolicy::Reject,
+        }
+    }
+}
+
+/// 读取CBOR时遇到标签(主类型6)的处理方式.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CborTagPolicy {
+    /// 返回错误.
+    Reject,
+    /// 忽略标签, 只保留被标记的值. 例如日期时间字符串(标签0)读取为普通字符串.
+    Ignore,
+}
+
+/// 读取二进制数据时的处理方式, `Value`中没有对应的类型.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteStringPolicy {
+    /// 返回错误.
+    Reject,
+    /// 转换为Base64编码(标准字母表, 带填充)的字符串.
+    Base64,
+    /// 转换为每个元素是一个字节的`I64`数组.
+    Array,
+}
+
+/// 读取不是字符串的对象成员名称时的处理方式, `Map`的名称只能是字符串.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapKeyPolicy {
+    /// 返回错误.
+    Reject,
+    /// 转换为字符串: 整数转换为十进制文本, 其他值转换为紧凑格式的Json.
+    Stringify,
+}
+
+/// 读取Json不能表示的浮点数(无穷大和NaN)时的处理方式.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    /// 返回错误.
+    Reject,
+    /// 转换为`Null`.
+    Null,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cbor::{encode_base64, f16_to_f64, f32_to_f16};
+    use crate::test_util::{hex, unhex};
+    use crate::{
+        ByteStringPolicy, CborReaderSettings, CborTagPolicy, CborWriterSettings, Map, MapKeyPolicy,
+        NonFinitePolicy, Value,
+    };
+
+    #[test]
+    fn rfc_examples() {
+        // RFC 8949 附录A中的例子
+        let cases: &[(Value, &str)] = &[
+            (Value::I64(0), "00"),
+            (Value::I64(23), "17"),
+            (Value::I64(24), "1818"),
+            (Value::I64(1000), "1903e8"),
+            (Value::I64(1000000), "1a000f4240"),
+            (Value::I64(1000000000000), "1b000000e8d4a51000"),
+            (Value::I64(-1), "20"),
+            (Value::I64(-1000), "3903e7"),
+            (Value::I64(i64::MIN), "3b7fffffffffffffff"),
+            (Value::F64(0.0), "f90000"),
+            (Value::F64(-0.0), "f98000"),
+            (Value::F64(1.5), "f93e00"),
+            (Value::F64(65504.0), "f97bff"),
+            (Value::F64(100000.0), "fa47c35000"),
+            (Value::F64(5.960464477539063e-8), "f90001"),
+            (Value::F64(1.1), "fb3ff199999999999a"),
+            (Value::Bool(false), "f4"),
+            (Value::Null, "f6"),
+            (Value::String(String::new()), "60"),
+            (Value::String("\u{6c34}".to_string()), "63e6b0b4"),
+            (
+                Value::Vec(vec![Value::I64(1), Value::Vec(vec![Value::I64(2)])]),
+                "82018102",
+            ),
+        ];
+
+        let settings = || CborWriterSettings {
+            deterministic: true,
+        };
+        for (value, expected) in cases {
+            let bytes = value.to_cbor_with_settings(settings());
+            assert_eq!(hex(&bytes), *expected);
+            assert!(Value::from_cbor(&bytes).unwrap() == *value, "{}", expected);
+        }
+
+        // 无穷大和NaN可以写入, 但默认读取时返回错误.
+        let cases = [
+            (f64::INFINITY, "f97c00"),
+            (f64::NEG_INFINITY, "f9fc00"),
+            (f64::NAN, "f97e00"),
+        ];
+        for (n, expected) in cases {
+            let bytes = Value::F64(n).to_cbor_with_settings(settings());
+            assert_eq!(hex(&bytes), expected);
+            assert!(Value::from_cbor(&bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn deterministic_key_order() {
+        let mut map = Map::new();
+        for key in ["aa", "b", "a", "", "ab"] {
+            map.insert(key.to_string(), Value::Null);
+        }
+        let bytes = Value::Object(map).to_cbor_with_settings(CborWriterSettings {
+            deterministic: true,
+        });
+        assert_eq!(hex(&bytes), "a560f66161f66162f6626161f6626162f6");
+    }
+
+    #[test]
+    fn default_encoding_round_trip() {
+        let mut map = Map::new();
+        map.merge(r#"{"a":[1,-2,3.5,"x",null,true,{"b":{}}],"c":[]}"#)
+            .unwrap();
+        let value = Value::Object(map);
+        let bytes = value.to_cbor();
+        assert!(bytes.contains(&0xfb)); // 默认总是使用双精度
+        assert!(Value::from_cbor(&bytes).unwrap() == value);
+    }
+
+    #[test]
+    fn indefinite_length_items() {
+        // [_ "a", {_ "b": (_ h'01', h'02')}] 中的字节串按Array策略读取
+        let bytes = unhex("9f6161bf61625f41014102ffffff");
+        let settings = CborReaderSettings {
+            byte_strings: ByteStringPolicy::Array,
+            ..Default::default()
+        };
+        let value = Value::from_cbor_with_settings(&bytes, settings).unwrap();
+        assert_eq!(value.to_json(), r#"["a",{"b":[1,2]}]"#);
+
+        // 不定长文本串由定长文本串组成
+        let value = Value::from_cbor(&unhex("7f657374726561646d696e67ff")).unwrap();
+        assert_eq!(value.as_string(), Some("streaming"));
+    }
+
+    #[test]
+    fn unsupported_features() {
+        let cases = [
+            (
+                "c074323031332d30332d32315432303a30343a30305a",
+                "Unsupported CBOR tag: 0",
+            ),
+            ("4401020304", "Unsupported CBOR byte string"),
+            ("a10102", "Unsupported CBOR map key of major type 0"),
+            (
+                "1bffffffffffffffff",
+                "CBOR integer out of range for I64: 18446744073709551615",
+            ),
+            ("f0", "Unsupported CBOR simple value: 16"),
+            ("ff", "Unexpected CBOR break"),
+            ("f97c00", "Unsupported non-finite CBOR float: inf"),
+            (
+                "fbfff0000000000000",
+                "Unsupported non-finite CBOR float: -inf",
+            ),
+            ("fa7fc00000", "Unsupported non-finite CBOR float: NaN"),
+            ("1c", "Invalid CBOR additional information: 28"),
+            ("62c328", "Invalid UTF-8 in CBOR text string"),
+            ("8201", "Unexpected end of CBOR input"),
+            ("9bffffffffffffffff", "Unexpected end of CBOR input"),
+            ("0000", "Unexpected data after the end of CBOR item"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(parse_err(bytes, CborReaderSettings::default()), expected);
+        }
+    }
+
+    #[test]
+    fn conversion_policies() {
+        let settings = CborReaderSettings {
+            tags: CborTagPolicy::Ignore,
+            byte_strings: ByteStringPolicy::Base64,
+            map_keys: MapKeyPolicy::Stringify,
+            ..Default::default()
+        };
+        // {1: 0("2013"), [1]: h'666f6f'}
+        let value =
+            Value::from_cbor_with_settings(&unhex("a201c06432303133810143666f6f"), settings)
+                .unwrap();
+        let obj = value.as_object().unwrap();
+        assert_eq!(obj["1"].as_string(), Some("2013"));
+        assert_eq!(obj["[1]"].as_string(), Some("Zm9v"));
+
+        // [Infinity, NaN, 1.5]
+        let settings = CborReaderSettings {
+            non_finite: NonFinitePolicy::Null,
+            ..Default::default()
+        };
+        let value =
+            Value::from_cbor_with_settings(&unhex("83f97c00f97e00f93e00"), settings).unwrap();
+        assert_eq!(value.to_json(), "[null,null,1.5]");
+
+        // 被忽略的标签之后必须有值, 不能直接结束不定长数组.
+        let settings = CborReaderSettings {
+            tags: CborTagPolicy::Ignore,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_err("9fc0ff", settings),
+            "Unexpected CBOR break after a tag"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let cases = [
+            (
+                "818181818100",
+                CborReaderSettings {
+                    depth_limit: 4,
+                    ..Default::default()
+                },
+                "The set depth limit is exceeded: 4",
+            ),
+            (
+                "83010203",
+                CborReaderSettings {
+                    max_array_length: 2,
+                    ..Default::default()
+                },
+                "The set array length limit is exceeded: 2",
+            ),
+            (
+                "9f010203ff",
+                CborReaderSettings {
+                    max_array_length: 2,
+                    ..Default::default()
+                },
+                "The set array length limit is exceeded: 2",
+            ),
+            (
+                "43010203",
+                CborReaderSettings {
+                    byte_strings: ByteStringPolicy::Array,
+                    max_array_length: 2,
+                    ..Default::default()
+                },
+                "The set array length limit is exceeded: 2",
+            ),
+            (
+                "a2616100616200",
+                CborReaderSettings {
+                    max_object_members: 1,
+                    ..Default::default()
+                },
+                "The set object member limit is exceeded: 1",
+            ),
+            (
+                "63616263",
+                CborReaderSettings {
+                    max_string_length: 2,
+                    ..Default::default()
+                },
+                "The set string length limit is exceeded: 2",
+            ),
+            (
+                "820102",
+                CborReaderSettings {
+                    max_nodes: 2,
+                    ..Default::default()
+                },
+                "The set node limit is exceeded: 2",
+            ),
+            (
+                "820102",
+                CborReaderSettings {
+                    max_input_length: 2,
+                    ..Default::default()
+                },
+                "The set input length limit is exceeded: 2",
+            ),
+        ];
+        for (bytes, settings, expected) in cases {
+            assert_eq!(parse_err(bytes, settings), expected);
+        }
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = 100_000;
+        let mut bytes = vec![0x81; depth];
+        bytes.push(0xa0);
+        let settings = CborReaderSettings {
+            depth_limit: usize::MAX,
+            ..Default::default()
+        };
+        let value = Value::from_cbor_with_settings(&bytes, settings).unwrap();
+        assert_eq!(value.to_cbor(), bytes);
+    }
+
+    #[test]
+    fn half_precision() {
+        for bits in [0x0000u16, 0x0001, 0x03ff, 0x0400, 0x3c00, 0x7bff, 0xc400] {
+            let n = f16_to_f64(bits);
+            assert_eq!(f32_to_f16(n as f32), Some(bits));
+        }
+        assert_eq!(f32_to_f16(65520.0), None);
+        assert_eq!(f32_to_f16(1.0 + f32::EPSILON), None);
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    fn parse_err(bytes: &str, settings: CborReaderSettings) -> String {
+        match Value::from_cbor_with_settings(&unhex(bytes), settings) {
+            Ok(_) => panic!("Expected an Err for {}", bytes),
+            Err(e) => e,
+        }
+    }
+}
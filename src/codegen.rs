@@ -0,0 +1,451 @@
+use crate::Map;
+use crate::schema::{ObjectShape, Shape};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// 生成Rust代码时使用的设置.
+pub struct CodegenSettings {
+    pub root_name: String,    // 最外层结构体的名称.
+    pub derives: Vec<String>, // 每个生成的类型上`#[derive]`的内容, 为空时不输出.
+}
+
+impl Default for CodegenSettings {
+    fn default() -> Self {
+        CodegenSettings {
+            root_name: "Root".to_string(),
+            derives: vec![
+                "Clone".to_string(),
+                "Debug".to_string(),
+                "PartialEq".to_string(),
+            ],
+        }
+    }
+}
+
+/// 从`Map`样本推断结构并生成Rust代码, 参见`Shape::to_rust`.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{CodegenSettings, Map, generate_rust};
+///
+/// let mut map = Map::new();
+/// map.merge(r#"{"id":1,"owner":{"name":"a"}}"#).unwrap();
+/// let code = generate_rust(&[map], CodegenSettings::default());
+/// assert!(code.contains("pub struct Root {\n    pub id: i64,\n    pub owner: Owner,\n}"));
+/// assert!(code.contains("pub struct Owner {\n    pub name: String,\n}"));
+/// ```
+pub fn generate_rust(samples: &[Map], settings: CodegenSettings) -> String {
+    let mut shape = Shape::new();
+    for sample in samples {
+        shape.add_map(sample);
+    }
+    let root = ObjectShape {
+        count: shape.count,
+        fields: shape.objects.map(|o| o.fields).unwrap_or_default(),
+    };
+    generate(&root, &settings)
+}
+
+impl Shape {
+    /// 为推断出的结构生成Rust代码: 每个对象对应一个结构体, 类型不唯一的值对应一个枚举,
+    /// 它们都实现了`ToValue`和`FromValue`, 结构体还有`from_map`和`to_map`.
+    ///
+    /// 嵌套类型以成员名称命名, 数组元素的类型加上`Item`后缀. 为`Null`或者在某些对象中不存在的成员
+    /// 使用`Option<T>`, 只出现过`Null`的成员使用`Value`. 样本中有不是对象的值时返回错误.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{CodegenSettings, Shape};
+    /// use std::io::Cursor;
+    ///
+    /// let mut shape = Shape::new();
+    /// shape.add_json_lines(Cursor::new("{\"id\":1,\"v\":\"a\"}\n{\"v\":2}")).unwrap();
+    /// let code = shape.to_rust(&CodegenSettings::default()).unwrap();
+    /// assert!(code.contains("    pub id: Option<i64>,\n    pub v: V,\n"));
+    /// assert!(code.contains("pub enum V {\n    I64(i64),\n    String(String),\n}"));
+    ///
+    /// shape.add(&mapjson::Value::I64(1));
+    /// assert!(shape.to_rust(&CodegenSettings::default()).is_err());
+    /// ```
+    pub fn to_rust(&self, settings: &CodegenSettings) -> Result<String, String> {
+        let objects = self.objects.as_ref().map_or(0, |o| o.count);
+        if objects < self.count {
+            return Err(format!("Expected only objects, found {}", self));
+        }
+        let root = ObjectShape {
+            count: self.count,
+            fields: self
+                .objects
+                .as_ref()
+                .map(|o| o.fields.clone())
+                .unwrap_or_default(),
+        };
+        Ok(generate(&root, settings))
+    }
+}
+
+fn generate(root: &ObjectShape, settings: &CodegenSettings) -> String {
+    let mut generator = Generator {
+        settings,
+        used: RESERVED.iter().map(|s| s.to_string()).collect(),
+        items: Vec::new(),
+    };
+    let name = generator.type_name(&settings.root_name, "");
+    generator.structure(name, root);
+
+    let mut code = String::from("// Generated by mapjson codegen.\n\n");
+    code.push_str("use mapjson::{FromValue, Map, ToValue, Value};\n");
+    for item in generator.items {
+        code.push('\n');
+        code.push_str(&item);
+    }
+    code
+}
+
+// 生成的代码中已经使用的名称, 生成的类型不能与它们同名.
+const RESERVED: [&str; 10] = [
+    "Box",
+    "FromValue",
+    "Map",
+    "Option",
+    "Result",
+    "Self",
+    "String",
+    "ToValue",
+    "Value",
+    "Vec",
+];
+
+const KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+// 值可能的类型, 对应枚举的变体.
+struct Variant {
+    name: &'static str,    // 与`Value`的变体同名.
+    pattern: &'static str, // 匹配这种值的模式.
+    rust_type: String,     // 变体中保存的Rust类型.
+}
+
+struct Generator<'s> {
+    settings: &'s CodegenSettings,
+    used: HashSet<String>,
+    items: Vec<String>,
+}
+
+impl Generator<'_> {
+    // 由成员名称得到未被使用的类型名称, 重名时先加上外层类型的名称, 再加上序号.
+    fn type_name(&mut self, key: &str, parent: &str) -> String {
+        let base = pascal_case(key);
+        let mut name = base.clone();
+        if self.used.contains(&name) {
+            name = format!("{}{}", parent, base);
+        }
+        let mut n = 2;
+        while self.used.contains(&name) {
+            name = format!("{}{}{}", parent, base, n);
+            n += 1;
+        }
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn derive(&self) -> String {
+        match self.settings.derives.is_empty() {
+            true => String::new(),
+            false => format!("#[derive({})]\n", self.settings.derives.join(", ")),
+        }
+    }
+
+    fn structure(&mut self, name: String, object: &ObjectShape) {
+        // 先占位, 使外层类型排在内层类型之前.
+        let index = self.items.len();
+        self.items.push(String::new());
+
+        let mut idents = HashSet::new();
+        let mut fields = Vec::new();
+        for (key, shape) in &object.fields {
+            let mut ident = snake_case(key);
+            let mut n = 2;
+            while !idents.insert(ident.clone()) {
+                ident = format!("{}_{}", snake_case(key), n);
+                n += 1;
+            }
+            let sometimes_missing = shape.count < object.count;
+            let rust_type = self.field_type(key, &name, shape, sometimes_missing);
+            fields.push((key, ident, rust_type, sometimes_missing));
+        }
+
+        let mut code = self.derive();
+        let _ = writeln!(code, "pub struct {} {{", name);
+        for (_, ident, rust_type, _) in &fields {
+            let _ = writeln!(code, "    pub {}: {},", ident, rust_type);
+        }
+        code.push_str("}\n\n");
+
+        let _ = writeln!(code, "impl {} {{", name);
+        code.push_str("    pub fn from_map(map: &Map) -> Result<Self, String> {\n");
+        let _ = writeln!(code, "        Ok({} {{", name);
+        for (key, ident, _, _) in &fields {
+            let _ = writeln!(
+                code,
+                "            {}: FromValue::from_field(map, {:?})?,",
+                ident, key
+            );
+        }
+        code.push_str("        })\n    }\n\n");
+        code.push_str("    pub fn to_map(&self) -> Map {\n");
+        code.push_str("        let mut map = Map::new();\n");
+        for (key, ident, _, sometimes_missing) in &fields {
+            if *sometimes_missing {
+                let _ = writeln!(code, "        if let Some(value) = &self.{} {{", ident);
+                let _ = writeln!(
+                    code,
+                    "            map.insert({:?}.to_string(), value.to_value());",
+                    key
+                );
+                code.push_str("        }\n");
+            } else {
+                let _ = writeln!(
+                    code,
+                    "        map.insert({:?}.to_string(), self.{}.to_value());",
+                    key, ident
+                );
+            }
+        }
+        code.push_str("        map\n    }\n}\n\n");
+
+        let _ = writeln!(code, "impl ToValue for {} {{", name);
+        code.push_str("    fn to_value(&self) -> Value {\n");
+        code.push_str("        Value::Object(self.to_map())\n    }\n}\n\n");
+        let _ = writeln!(code, "impl FromValue for {} {{", name);
+        code.push_str("    fn from_value(value: &Value) -> Result<Self, String> {\n");
+        code.push_str("        match value.as_object() {\n");
+        code.push_str("            Some(map) => Self::from_map(map),\n");
+        code.push_str("            None => Err(mapjson::type_error(\"Object\", value)),\n");
+        code.push_str("        }\n    }\n}\n");
+
+        self.items[index] = code;
+    }
+
+    // 成员的类型. 可能为`Null`或者在某些对象中不存在时包装为`Option`.
+    fn field_type(&mut self, key: &str, parent: &str, shape: &Shape, missing: bool) -> String {
+        match self.value_type(key, parent, shape) {
+            Some(rust_type) if missing || shape.nulls > 0 => format!("Option<{}>", rust_type),
+            Some(rust_type) => rust_type,
+            None if missing => "Option<Value>".to_string(),
+            None => "Value".to_string(),
+        }
+    }
+
+    // 不是`Null`的值的类型, 没有这样的值时返回None. 有多种类型时生成枚举, 枚举使用成员的名称,
+    // 其中对象的结构体名称加上`Object`后缀.
+    fn value_type(&mut self, key: &str, parent: &str, shape: &Shape) -> Option<String> {
+        let kinds = [
+            shape.bools > 0,
+            shape.integers.is_some() || shape.floats.is_some(),
+            shape.strings.is_some(),
+            shape.arrays.is_some(),
+            shape.objects.is_some(),
+        ];
+        let enumeration = match kinds.iter().filter(|&&kind| kind).count() {
+            0 => return None,
+            1 => None,
+            _ => {
+                let name = self.type_name(key, parent);
+                self.items.push(String::new());
+                Some((name, self.items.len() - 1))
+            }
+        };
+
+        let mut variants = Vec::new();
+        if shape.bools > 0 {
+            variants.push(Variant {
+                name: "Bool",
+                pattern: "Value::Bool(_)",
+                rust_type: "bool".to_string(),
+            });
+        }
+        match (&shape.integers, &shape.floats) {
+            (Some(_), None) => variants.push(Variant {
+                name: "I64",
+                pattern: "Value::I64(_)",
+                rust_type: "i64".to_string(),
+            }),
+            (_, Some(_)) => variants.push(Variant {
+                name: "F64",
+                pattern: "Value::I64(_) | Value::F64(_)",
+                rust_type: "f64".to_string(),
+            }),
+            (None, None) => {}
+        }
+        if shape.strings.is_some() {
+            variants.push(Variant {
+                name: "String",
+                pattern: "Value::String(_)",
+                rust_type: "String".to_string(),
+            });
+        }
+        if let Some(ref array) = shape.arrays {
+            let item_key = format!("{}Item", pascal_case(key));
+            let item_type = self.field_type(&item_key, parent, &array.items, false);
+            variants.push(Variant {
+                name: "Vec",
+                pattern: "Value::Vec(_)",
+                rust_type: format!("Vec<{}>", item_type),
+            });
+        }
+        if let Some(ref object) = shape.objects {
+            let name = match enumeration {
+                Some(_) => self.type_name(&format!("{}Object", pascal_case(key)), parent),
+                None => self.type_name(key, parent),
+            };
+            self.structure(name.clone(), object);
+            variants.push(Variant {
+                name: "Object",
+                pattern: "Value::Object(_)",
+                rust_type: name,
+            });
+        }
+
+        match enumeration {
+            Some((name, index)) => {
+                self.items[index] = self.enumeration(&name, &variants);
+                Some(name)
+            }
+            None => variants.pop().map(|v| v.rust_type),
+        }
+    }
+
+    fn enumeration(&self, name: &str, variants: &[Variant]) -> String {
+        let mut code = self.derive();
+        let _ = writeln!(code, "pub enum {} {{", name);
+        for v in variants {
+            let _ = writeln!(code, "    {}({}),", v.name, v.rust_type);
+        }
+        code.push_str("}\n\n");
+
+        let _ = writeln!(code, "impl ToValue for {} {{", name);
+        code.push_str("    fn to_value(&self) -> Value {\n        match self {\n");
+        for v in variants {
+            let _ = writeln!(code, "            {}::{}(v) => v.to_value(),", name, v.name);
+        }
+        code.push_str("        }\n    }\n}\n\n");
+
+        let _ = writeln!(code, "impl FromValue for {} {{", name);
+        code.push_str("    fn from_value(value: &Value) -> Result<Self, String> {\n");
+        code.push_str("        match value {\n");
+        for v in variants {
+            let _ = writeln!(
+                code,
+                "            {} => Ok({}::{}(FromValue::from_value(value)?)),",
+                v.pattern, name, v.name
+            );
+        }
+        let expected: Vec<&str> = variants.iter().map(|v| v.name).collect();
+        let _ = writeln!(
+            code,
+            "            _ => Err(mapjson::type_error({:?}, value)),",
+            expected.join("|")
+        );
+        code.push_str("        }\n    }\n}\n");
+        code
+    }
+}
+
+// 按非字母数字字符和小写到大写的边界拆分名称.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut result = String::new();
+    for word in words(name) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            result.extend(first.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+    match result.chars().next() {
+        None => "Field".to_string(),
+        Some(c) if !c.is_alphabetic() => format!("Type{}", result),
+        Some(_) => result,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let words: Vec<String> = words(name).iter().map(|w| w.to_lowercase()).collect();
+    let result = words.join("_");
+    match result.as_str() {
+        "" => "field".to_string(),
+        "self" | "super" | "crate" => format!("{}_", result),
+        _ if result.starts_with(|c: char| c.is_numeric()) => format!("_{}", result),
+        _ if KEYWORDS.contains(&result.as_str()) => format!("r#{}", result),
+        _ => result,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(pascal_case("user_info"), "UserInfo");
+        assert_eq!(pascal_case("homeAddress"), "HomeAddress");
+        assert_eq!(pascal_case("2fa-codes"), "Type2faCodes");
+        assert_eq!(pascal_case("--"), "Field");
+        assert_eq!(snake_case("userID"), "user_id");
+        assert_eq!(snake_case("Content-Type"), "content_type");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("self"), "self_");
+        assert_eq!(snake_case("1st"), "_1st");
+        assert_eq!(snake_case("名称"), "名称");
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let mut map = Map::new();
+        map.merge(r#"{"a":{"value":{"x":1}},"b":{"value":{"y":2}},"c-d":1,"c_d":2}"#)
+            .unwrap();
+        let code = generate_rust(
+            &[map],
+            CodegenSettings {
+                root_name: "Doc".to_string(),
+                derives: Vec::new(),
+            },
+        );
+        assert!(code.contains("pub struct Doc {"));
+        assert!(code.contains("    pub c_d: i64,\n    pub c_d_2: i64,\n"));
+        assert!(code.contains("pub struct AValue {"));
+        assert!(code.contains("pub struct BValue {"));
+        assert!(!code.contains("#[derive"));
+    }
+}
@@ -0,0 +1,448 @@
+use crate::{Map, Number, PathSegment, Value, path, to_json_pointer};
+use std::borrow::Cow;
+use std::fmt;
+
+/// 语义比较时使用的设置.
+///
+/// `ignore_paths`中的每一项都是JSON Pointer, 其中的`*`段匹配任意一个成员名称或数组下标;
+/// 格式错误的JSON Pointer不会匹配任何路径. 被忽略的路径及其子节点不参与比较.
+pub struct CompareSettings {
+    pub numeric_equality: bool, // 是否按数学值比较`I64`与`F64`, 为false时类型不同即不相等.
+    pub tolerance: Tolerance,   // 至少一方为`F64`时, 两个数字允许的误差.
+    pub ignore_array_order: bool, // 是否忽略数组中元素的顺序.
+    pub ignore_paths: Vec<String>, // 不参与比较的路径.
+}
+
+impl Default for CompareSettings {
+    fn default() -> Self {
+        CompareSettings {
+            numeric_equality: true,
+            tolerance: Tolerance::Exact,
+            ignore_array_order: false,
+            ignore_paths: Vec::new(),
+        }
+    }
+}
+
+/// 浮点数比较的容差. 任何容差下, `NaN`都与`NaN`相等, 无穷大只与同号的无穷大相等.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    Exact,         // 数学值相等.
+    Absolute(f64), // 差的绝对值不超过给定值.
+    Relative(f64), // 差的绝对值不超过两者中较大绝对值的给定倍数.
+    Ulps(u64),     // 两者之间可表示的`f64`的个数不超过给定值.
+}
+
+/// 比较结果中的一处差异. `actual`或`expected`为None时表示该成员或元素只在另一方中存在.
+pub struct Mismatch<'a> {
+    pub pointer: String, // 差异所在位置的JSON Pointer, 空字符串表示最外层.
+    pub actual: Option<&'a Value>,
+    pub expected: Option<&'a Value>,
+}
+
+impl fmt::Display for Mismatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.actual, self.expected) {
+            (Some(actual), Some(expected)) => write!(
+                f,
+                "Expected {}, found {}",
+                expected.to_json(),
+                actual.to_json()
+            )?,
+            (None, Some(expected)) => write!(f, "Missing {}", expected.to_json())?,
+            (Some(actual), None) => write!(f, "Unexpected {}", actual.to_json())?,
+            (None, None) => f.write_str("No difference")?,
+        }
+        match self.pointer.as_str() {
+            "" => f.write_str(" at the root"),
+            pointer => write!(f, " at {}", pointer),
+        }
+    }
+}
+
+// 逐层比较两个值, 收集所有差异.
+struct Comparer<'s> {
+    settings: &'s CompareSettings,
+    ignored: Vec<Vec<String>>,
+}
+
+impl<'s> Comparer<'s> {
+    fn new(settings: &'s CompareSettings) -> Self {
+        let ignored = settings
+            .ignore_paths
+            .iter()
+            .filter_map(|pointer| {
+                Some(
+                    path::pointer_tokens(pointer)?
+                        .map(Cow::into_owned)
+                        .collect(),
+                )
+            })
+            .collect();
+        Comparer { settings, ignored }
+    }
+
+    fn is_ignored(&self, path: &[PathSegment]) -> bool {
+        self.ignored.iter().any(|pattern| {
+            pattern.len() == path.len()
+                && pattern.iter().zip(path).all(|(token, segment)| {
+                    token == "*"
+                        || match segment {
+                            PathSegment::Key(key) => token == key,
+                            PathSegment::Index(index) => path::parse_index(token) == Some(*index),
+                        }
+                })
+        })
+    }
+
+    fn compare<'a>(
+        &self,
+        actual: &'a Value,
+        expected: &'a Value,
+        path: &mut Vec<PathSegment<'a>>,
+        out: &mut Vec<Mismatch<'a>>,
+    ) {
+        if self.is_ignored(path) {
+            return;
+        }
+
+        match (actual, expected) {
+            (Value::Object(a), Value::Object(e)) => self.compare_maps(a, e, path, out),
+            (Value::Vec(a), Value::Vec(e)) if self.settings.ignore_array_order => {
+                self.compare_unordered(a, e, path, out)
+            }
+            (Value::Vec(a), Value::Vec(e)) => {
+                for i in 0..a.len().max(e.len()) {
+                    path.push(PathSegment::Index(i));
+                    match (a.get(i), e.get(i)) {
+                        (Some(a), Some(e)) => self.compare(a, e, path, out),
+                        (a, e) => self.mismatch(a, e, path, out),
+                    }
+                    path.pop();
+                }
+            }
+            _ => {
+                let equal = match (actual.as_number(), expected.as_number()) {
+                    (Some(a), Some(e)) => self.numbers_equal(a, e),
+                    _ => actual == expected,
+                };
+                if !equal {
+                    self.mismatch(Some(actual), Some(expected), path, out);
+                }
+            }
+        }
+    }
+
+    fn compare_maps<'a>(
+        &self,
+        actual: &'a Map,
+        expected: &'a Map,
+        path: &mut Vec<PathSegment<'a>>,
+        out: &mut Vec<Mismatch<'a>>,
+    ) {
+        // 按名称排序, 使差异的顺序稳定.
+        let mut keys: Vec<&'a String> = expected.keys().collect();
+        keys.extend(actual.keys().filter(|k| !expected.contains_key(*k)));
+        keys.sort_unstable();
+        for key in keys {
+            path.push(PathSegment::Key(Cow::Borrowed(key)));
+            match (actual.get(key), expected.get(key)) {
+                (Some(a), Some(e)) => self.compare(a, e, path, out),
+                (a, e) => self.mismatch(a, e, path, out),
+            }
+            path.pop();
+        }
+    }
+
+    // 为每个期望的元素寻找一个尚未匹配且没有差异的实际元素, 剩下的元素分别报告为缺少和多余.
+    fn compare_unordered<'a>(
+        &self,
+        actual: &'a [Value],
+        expected: &'a [Value],
+        path: &mut Vec<PathSegment<'a>>,
+        out: &mut Vec<Mismatch<'a>>,
+    ) {
+        let mut matched = vec![false; actual.len()];
+        let mut missing = Vec::new();
+        for (i, e) in expected.iter().enumerate() {
+            path.push(PathSegment::Index(i));
+            let found = (0..actual.len()).find(|&j| {
+                let mut diff = Vec::new();
+                !matched[j] && {
+                    self.compare(&actual[j], e, path, &mut diff);
+                    diff.is_empty()
+                }
+            });
+            match found {
+                Some(j) => matched[j] = true,
+                None => missing.push(i),
+            }
+            path.pop();
+        }
+
+        for i in missing {
+            path.push(PathSegment::Index(i));
+            self.mismatch(None, Some(&expected[i]), path, out);
+            path.pop();
+        }
+        for (j, a) in actual.iter().enumerate() {
+            if !matched[j] {
+                path.push(PathSegment::Index(j));
+                self.mismatch(Some(a), None, path, out);
+                path.pop();
+            }
+        }
+    }
+
+    fn mismatch<'a>(
+        &self,
+        actual: Option<&'a Value>,
+        expected: Option<&'a Value>,
+        path: &[PathSegment],
+        out: &mut Vec<Mismatch<'a>>,
+    ) {
+        if !self.is_ignored(path) {
+            out.push(Mismatch {
+                pointer: to_json_pointer(path),
+                actual,
+                expected,
+            });
+        }
+    }
+
+    fn numbers_equal(&self, a: Number, e: Number) -> bool {
+        match (a, e) {
+            (Number::I64(a), Number::I64(e)) => a == e,
+            _ if !self.settings.numeric_equality && a.is_i64() != e.is_i64() => false,
+            _ if a == e => true,
+            _ => {
+                let (a, e) = (as_f64(a), as_f64(e));
+                if a.is_nan() || e.is_nan() {
+                    return a.is_nan() && e.is_nan();
+                }
+                if a.is_infinite() || e.is_infinite() {
+                    return false;
+                }
+                let diff = (a - e).abs();
+                match self.settings.tolerance {
+                    Tolerance::Exact => false,
+                    Tolerance::Absolute(eps) => diff <= eps,
+                    Tolerance::Relative(ratio) => diff <= ratio * a.abs().max(e.abs()),
+                    Tolerance::Ulps(ulps) => ulps_between(a, e) <= ulps as u128,
+                }
+            }
+        }
+    }
+}
+
+// 不需要无损, 用于计算误差.
+fn as_f64(n: Number) -> f64 {
+    match n {
+        Number::I64(n) => n as f64,
+        Number::F64(f) => f,
+    }
+}
+
+// 将`f64`的位模式映射为单调递增的整数, `-0.0`与`0.0`映射为同一个值.
+fn ordered_bits(f: f64) -> i128 {
+    let bits = f.to_bits() as i64;
+    if bits < 0 {
+        i64::MIN as i128 - bits as i128
+    } else {
+        bits as i128
+    }
+}
+
+fn ulps_between(a: f64, b: f64) -> u128 {
+    (ordered_bits(a) - ordered_bits(b)).unsigned_abs()
+}
+
+pub(crate) fn compare_values<'a>(
+    actual: &'a Value,
+    expected: &'a Value,
+    settings: &CompareSettings,
+) -> Vec<Mismatch<'a>> {
+    let mut out = Vec::new();
+    Comparer::new(settings).compare(actual, expected, &mut Vec::new(), &mut out);
+    out
+}
+
+pub(crate) fn compare_maps<'a>(
+    actual: &'a Map,
+    expected: &'a Map,
+    settings: &CompareSettings,
+) -> Vec<Mismatch<'a>> {
+    let mut out = Vec::new();
+    let comparer = Comparer::new(settings);
+    if !comparer.is_ignored(&[]) {
+        comparer.compare_maps(actual, expected, &mut Vec::new(), &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(json: &str) -> Value {
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        Value::Object(map)
+    }
+
+    fn messages(actual: &str, expected: &str, settings: CompareSettings) -> Vec<String> {
+        let (actual, expected) = (parse(actual), parse(expected));
+        actual
+            .compare_with_settings(&expected, settings)
+            .iter()
+            .map(Mismatch::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn mismatches() {
+        let result = messages(
+            r#"{"a":1,"b":{"c":[1,2,3],"d":"x"},"e":true}"#,
+            r#"{"a":1.0,"b":{"c":[1,5],"d":"y","f":null}}"#,
+            CompareSettings::default(),
+        );
+        assert_eq!(
+            result,
+            [
+                "Expected 5, found 2 at /b/c/1",
+                "Unexpected 3 at /b/c/2",
+                r#"Expected "y", found "x" at /b/d"#,
+                "Missing null at /b/f",
+                "Unexpected true at /e",
+            ]
+        );
+
+        let result = messages(
+            r#"{"a":1}"#,
+            r#"{"a":1.0}"#,
+            CompareSettings {
+                numeric_equality: false,
+                ..Default::default()
+            },
+        );
+        assert_eq!(result, ["Expected 1, found 1 at /a"]);
+
+        let (a, e) = (Value::I64(1), Value::String("1".to_string()));
+        assert_eq!(
+            a.compare(&e)[0].to_string(),
+            r#"Expected "1", found 1 at the root"#
+        );
+        assert!(
+            Value::F64(f64::NAN)
+                .compare(&Value::F64(f64::NAN))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn tolerance() {
+        let cases = [
+            (Tolerance::Exact, 0.1 + 0.2, 0.3, false),
+            (Tolerance::Absolute(1e-9), 0.1 + 0.2, 0.3, true),
+            (Tolerance::Absolute(1e-9), 1.0, 1.1, false),
+            (Tolerance::Relative(0.01), 100.0, 100.9, true),
+            (Tolerance::Relative(0.01), 100.0, 102.0, false),
+            (Tolerance::Ulps(1), 0.1 + 0.2, 0.3, true),
+            (Tolerance::Ulps(4), 1.0, 1.0 + 5.0 * f64::EPSILON, false),
+            (Tolerance::Ulps(1), -0.0, 0.0, true),
+            (
+                Tolerance::Ulps(2),
+                -f64::MIN_POSITIVE / 2.0,
+                f64::MIN_POSITIVE / 2.0,
+                false,
+            ),
+            (Tolerance::Absolute(1.0), f64::INFINITY, f64::INFINITY, true),
+            (Tolerance::Absolute(1.0), f64::INFINITY, f64::MAX, false),
+        ];
+        for (tolerance, a, e, equal) in cases {
+            let settings = CompareSettings {
+                tolerance,
+                ..Default::default()
+            };
+            let (a, e) = (Value::F64(a), Value::F64(e));
+            let result = a.compare_with_settings(&e, settings);
+            assert_eq!(
+                result.is_empty(),
+                equal,
+                "{:?} {} {}",
+                tolerance,
+                a.to_json(),
+                e.to_json()
+            );
+        }
+
+        // 两个`I64`总是精确比较.
+        let settings = CompareSettings {
+            tolerance: Tolerance::Absolute(1.0),
+            ..Default::default()
+        };
+        assert!(
+            !Value::I64(1)
+                .compare_with_settings(&Value::I64(2), settings)
+                .is_empty()
+        );
+        let settings = CompareSettings {
+            tolerance: Tolerance::Absolute(1.0),
+            ..Default::default()
+        };
+        assert!(
+            Value::I64(1)
+                .compare_with_settings(&Value::F64(1.5), settings)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn array_order() {
+        let settings = || CompareSettings {
+            ignore_array_order: true,
+            ..Default::default()
+        };
+        assert!(
+            messages(
+                r#"{"a":[3,{"b":[2,1]},1]}"#,
+                r#"{"a":[1,3,{"b":[1,2]}]}"#,
+                settings()
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            messages(r#"{"a":[1,1,2]}"#, r#"{"a":[1,2,2]}"#, settings()),
+            ["Missing 2 at /a/2", "Unexpected 1 at /a/1"]
+        );
+    }
+
+    #[test]
+    fn ignore_paths() {
+        let settings = CompareSettings {
+            ignore_paths: vec![
+                "/id".to_string(),
+                "/items/*/updated".to_string(),
+                "/meta/a~1b".to_string(),
+                "invalid".to_string(),
+            ],
+            ..Default::default()
+        };
+        let result = messages(
+            r#"{"id":1,"items":[{"updated":1,"v":1},{"v":2}],"meta":{"a/b":1}}"#,
+            r#"{"id":2,"items":[{"updated":2,"v":1},{"updated":3,"v":3}],"meta":{}}"#,
+            settings,
+        );
+        assert_eq!(result, ["Expected 3, found 2 at /items/1/v"]);
+
+        let settings = CompareSettings {
+            ignore_paths: vec![String::new()],
+            ..Default::default()
+        };
+        let (a, e) = (Map::new(), parse(r#"{"a":1}"#));
+        assert!(
+            a.compare_with_settings(e.as_object().unwrap(), settings)
+                .is_empty()
+        );
+    }
+}
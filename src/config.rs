@@ -0,0 +1,469 @@
+use crate::path::{PathSegment, to_json_pointer};
+use crate::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io, mem};
+
+/// 分层的配置加载器.
+///
+/// 各层按添加的顺序合并, 后添加的层优先: 对象逐个成员深度合并, 其余的值(包括数组)整体替换.
+/// 环境变量和命令行参数中的值都是字符串, 若之前的层中同一位置的值是`I64`, `F64`或`Bool`, 则转换为该类型.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{ConfigBuilder, Map};
+///
+/// let mut defaults = Map::new();
+/// defaults.merge(r#"{"db":{"host":"localhost","port":5432},"debug":false}"#).unwrap();
+///
+/// let config = ConfigBuilder::new()
+///     .add_map("defaults", defaults)
+///     .add_env_vars("APP", [("APP__DB__PORT", "6432")])
+///     .add_args(["serve", "--set", "debug=true"])
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(config.pointer("/db/port").unwrap().as_i64(), Some(6432));
+/// assert_eq!(config.pointer("/debug").unwrap().as_bool(), Some(true));
+/// assert_eq!(config.source("/db/host"), Some("defaults"));
+/// assert_eq!(config.source("/db/port"), Some("env"));
+/// assert_eq!(config.source("/debug"), Some("args"));
+/// ```
+#[derive(Default)]
+pub struct ConfigBuilder {
+    layers: Vec<(String, Layer)>, // 层的名称与内容, 按优先级从低到高排列.
+}
+
+// 一层配置的来源.
+enum Layer {
+    Map(Map),
+    JsonFile { path: PathBuf, optional: bool },
+    Strings(Vec<(Vec<String>, String)>), // 路径与未转换的字符串值, 来自环境变量.
+    Args(Vec<String>),                   // 命令行参数, 在`build`时解析其中的`--set`.
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        ConfigBuilder::default()
+    }
+
+    /// 添加一个已经加载的`Map`作为一层, 例如默认值.
+    pub fn add_map(mut self, name: &str, map: Map) -> Self {
+        self.layers.push((name.to_string(), Layer::Map(map)));
+        self
+    }
+
+    /// 添加一个Json文件作为一层, 层的名称为文件路径. 文件不存在时`build`返回错误.
+    pub fn add_json_file(self, path: impl AsRef<Path>) -> Self {
+        self.add_file(path.as_ref(), false)
+    }
+
+    /// 添加一个可选的Json文件作为一层, 例如按运行环境区分的覆盖文件. 文件不存在时跳过这一层.
+    pub fn add_optional_json_file(self, path: impl AsRef<Path>) -> Self {
+        self.add_file(path.as_ref(), true)
+    }
+
+    fn add_file(mut self, path: &Path, optional: bool) -> Self {
+        let layer = Layer::JsonFile {
+            path: path.to_path_buf(),
+            optional,
+        };
+        self.layers.push((path.display().to_string(), layer));
+        self
+    }
+
+    /// 添加当前进程中以`prefix__`开头的环境变量作为一层, 层的名称为`env`.
+    ///
+    /// 变量名称的其余部分以`__`分隔并转换为小写, 例如前缀为`APP`时, `APP__DB__HOST`对应`/db/host`.
+    /// 名称或值不是合法Unicode的变量被忽略.
+    pub fn add_env(self, prefix: &str) -> Self {
+        let vars = env::vars_os().filter_map(|(name, value)| {
+            Some((OsString::into_string(name).ok()?, value.into_string().ok()?))
+        });
+        self.add_env_vars(prefix, vars)
+    }
+
+    /// 与`add_env`相同, 但从给定的名称和值中读取, 而不是当前进程的环境变量.
+    pub fn add_env_vars<K, V>(
+        mut self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let prefix = format!("{}__", prefix);
+        let mut values: Vec<(Vec<String>, String)> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let path: Vec<String> = name
+                    .as_ref()
+                    .strip_prefix(&prefix)?
+                    .split("__")
+                    .map(str::to_lowercase)
+                    .collect();
+                match path.iter().any(String::is_empty) {
+                    true => None,
+                    false => Some((path, value.into())),
+                }
+            })
+            .collect();
+        // 环境变量没有顺序, 排序使结果不依赖于读取的顺序
+        values.sort();
+
+        self.layers
+            .push((String::from("env"), Layer::Strings(values)));
+        self
+    }
+
+    /// 添加命令行参数中的`--set a.b=c`(或`--set=a.b=c`)作为一层, 层的名称为`args`. 其它参数被忽略.
+    ///
+    /// 名称以`.`分隔, 例如`db.port=6432`对应`/db/port`. 同一个名称出现多次时, 后出现的优先.
+    pub fn add_args<S: Into<String>>(mut self, args: impl IntoIterator<Item = S>) -> Self {
+        let args = args.into_iter().map(Into::into).collect();
+        self.layers.push((String::from("args"), Layer::Args(args)));
+        self
+    }
+
+    /// 加载并合并所有的层. 文件无法读取, Json格式错误, `--set`参数格式错误或字符串无法转换为已有值的类型时返回错误.
+    pub fn build(self) -> Result<Config, String> {
+        let mut config = Config {
+            map: Map::new(),
+            sources: HashMap::new(),
+            layers: Vec::new(),
+        };
+
+        for (name, layer) in self.layers {
+            let index = config.layers.len();
+            match layer {
+                Layer::Map(map) => config.merge(map, index),
+                Layer::JsonFile { path, optional } => {
+                    let text = match fs::read_to_string(&path) {
+                        Ok(text) => text,
+                        Err(e) if optional && e.kind() == io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(format!("{}: {}", name, e)),
+                    };
+                    let mut map = Map::new();
+                    map.merge(&text).map_err(|e| format!("{}: {}", name, e))?;
+                    config.merge(map, index);
+                }
+                Layer::Strings(values) => {
+                    for (path, text) in values {
+                        config.set(&path, text, index, &name)?;
+                    }
+                }
+                Layer::Args(args) => {
+                    for (path, text) in parse_set_args(&args)? {
+                        config.set(&path, text, index, &name)?;
+                    }
+                }
+            }
+            config.layers.push(name);
+        }
+
+        Ok(config)
+    }
+}
+
+// 读取参数中`--set`的值, 拆分为路径和字符串值.
+fn parse_set_args(args: &[String]) -> Result<Vec<(Vec<String>, String)>, String> {
+    let mut values = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let assignment = match arg.strip_prefix("--set") {
+            Some("") => iter.next().ok_or("--set requires a value")?,
+            Some(rest) if rest.starts_with('=') => &rest[1..],
+            _ => continue,
+        };
+
+        let invalid = || {
+            format!(
+                "Invalid --set argument, expected `name=value`: {}",
+                assignment
+            )
+        };
+        let (name, value) = assignment.split_once('=').ok_or_else(invalid)?;
+        let path: Vec<String> = name.split('.').map(str::to_string).collect();
+        if path.iter().any(String::is_empty) {
+            return Err(invalid());
+        }
+        values.push((path, value.to_string()));
+    }
+    Ok(values)
+}
+
+/// `ConfigBuilder`合并后的配置, 记录了每个值来自哪一层.
+///
+/// 通过`Deref`可以像`Map`一样读取配置.
+pub struct Config {
+    map: Map,
+    sources: HashMap<String, usize>, // 每个非对象值的JSON Pointer与其所在层的序号.
+    layers: Vec<String>,             // 各层的名称, 不包括跳过的可选文件.
+}
+
+impl Config {
+    /// 返回合并后的配置.
+    pub fn into_map(self) -> Map {
+        self.map
+    }
+
+    /// 返回JSON Pointer所指的值来自哪一层. 数组中的元素返回整个数组所在的层;
+    /// 对象可能由多层合并而成, 返回None. 值不存在时也返回None.
+    pub fn source(&self, pointer: &str) -> Option<&str> {
+        self.map.pointer(pointer)?;
+        let mut prefix = pointer;
+        loop {
+            if let Some(&index) = self.sources.get(prefix) {
+                return Some(&self.layers[index]);
+            }
+            prefix = &prefix[..prefix.rfind('/')?];
+        }
+    }
+
+    /// 返回所有非对象值的JSON Pointer及其所在层的名称, 按JSON Pointer排序, 用于诊断输出.
+    pub fn sources(&self) -> Vec<(&str, &str)> {
+        let mut sources: Vec<(&str, &str)> = self
+            .sources
+            .iter()
+            .map(|(pointer, &index)| (pointer.as_str(), self.layers[index].as_str()))
+            .collect();
+        sources.sort();
+        sources
+    }
+
+    // 将一层深度合并到配置中.
+    fn merge(&mut self, layer: Map, index: usize) {
+        // 待合并的对象: 在配置中的路径和来自这一层的成员. 使用显式的栈而不是递归.
+        let mut stack = vec![(Vec::new(), layer)];
+        while let Some((path, mut source)) = stack.pop() {
+            let target = object_mut(&mut self.map, &path);
+            for (key, mut value) in source.drain() {
+                let mut child = path.clone();
+                child.push(key.clone());
+                let pointer = to_pointer(&child);
+
+                if let Value::Object(ref mut obj) = value {
+                    let obj = mem::take(obj);
+                    if !matches!(target.get(&key), Some(Value::Object(_))) {
+                        self.sources.remove(&pointer);
+                        target.insert(key, Value::Object(Map::new()));
+                    }
+                    stack.push((child, obj));
+                    continue;
+                }
+
+                if let Some(Value::Object(_)) = target.insert(key, value) {
+                    let nested = format!("{}/", pointer);
+                    self.sources.retain(|p, _| !p.starts_with(&nested));
+                }
+                self.sources.insert(pointer, index);
+            }
+        }
+    }
+
+    // 设置一个来自字符串层的值, 按已有值的类型转换.
+    fn set(
+        &mut self,
+        path: &[String],
+        text: String,
+        index: usize,
+        layer: &str,
+    ) -> Result<(), String> {
+        let pointer = to_pointer(path);
+        let existing = self.map.pointer(&pointer);
+        let value = match existing {
+            Some(&Value::I64(_)) => text.parse().map(Value::I64).ok(),
+            Some(&Value::F64(_)) => text.parse().map(Value::F64).ok(),
+            Some(&Value::Bool(_)) => match text.to_ascii_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Some(&Value::Vec(_) | &Value::Object(_)) => None,
+            Some(&Value::Null | &Value::String(_)) | None => Some(Value::String(text.clone())),
+        };
+        let value = value.ok_or_else(|| {
+            format!(
+                "{}: cannot convert `{}` to the {} at {}",
+                layer,
+                text,
+                type_name(existing.expect("conversion fails only for existing values")),
+                pointer
+            )
+        })?;
+
+        // 路径上不是对象的值被替换为对象, 与合并对象的规则相同
+        let (last, parents) = path.split_last().expect("a path has at least one key");
+        let mut target = &mut self.map;
+        for (i, key) in parents.iter().enumerate() {
+            let entry = target
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !matches!(entry, Value::Object(_)) {
+                self.sources.remove(&to_pointer(&path[..=i]));
+                *entry = Value::Object(Map::new());
+            }
+            target = entry
+                .as_object_mut()
+                .expect("the entry was just made an object");
+        }
+        target.insert(last.clone(), value);
+        self.sources.insert(pointer, index);
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for Config {
+    type Target = Map;
+
+    fn deref(&self) -> &Map {
+        &self.map
+    }
+}
+
+// 返回路径所指的对象. 路径总是指向已经创建的对象.
+fn object_mut<'m>(mut map: &'m mut Map, path: &[String]) -> &'m mut Map {
+    for key in path {
+        map = map
+            .get_mut(key)
+            .and_then(Value::as_object_mut)
+            .expect("the path refers to an object");
+    }
+    map
+}
+
+fn to_pointer(path: &[String]) -> String {
+    let path: Vec<PathSegment> = path
+        .iter()
+        .map(|key| PathSegment::Key(Cow::Borrowed(key.as_str())))
+        .collect();
+    to_json_pointer(&path)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::F64(_) => "floating-point number",
+        Value::I64(_) => "integer",
+        Value::String(_) => "string",
+        Value::Vec(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ConfigBuilder, JsonWriterSettings, Map};
+    use std::fs;
+
+    fn map(json: &str) -> Map {
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        map
+    }
+
+    #[test]
+    fn layers() {
+        let dir = std::env::temp_dir().join(format!("mapjson-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("app.json");
+        fs::write(
+            &file,
+            r#"{"db":{"host":"db.internal","pool":{"size":10}},"tags":["a","b"]}"#,
+        )
+        .unwrap();
+
+        let config = ConfigBuilder::new()
+            .add_map(
+                "defaults",
+                map(r#"{"db":{"host":"localhost","port":5432,"pool":4},"ratio":0.5,"debug":false,"name":"app"}"#),
+            )
+            .add_json_file(&file)
+            .add_optional_json_file(dir.join("missing.json"))
+            .add_env_vars(
+                "APP",
+                [
+                    ("APP__DB__PORT", "6432"),
+                    ("APP__RATIO", "2"),
+                    ("APP__NEW__KEY", "x"),
+                    ("OTHER__DB__PORT", "1"),
+                    ("APP____BAD", "1"),
+                ],
+            )
+            .add_args(["run", "--set", "debug=TRUE", "--set=db.pool.size=20", "--verbose"])
+            .build()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let settings = JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.to_json_with_settings(settings),
+            r#"{"db":{"host":"db.internal","pool":{"size":20},"port":6432},"debug":true,"name":"app","new":{"key":"x"},"ratio":2,"tags":["a","b"]}"#
+        );
+        assert_eq!(config.pointer("/ratio").unwrap().as_f64(), Some(2.0));
+
+        let file = file.display().to_string();
+        assert_eq!(
+            config.sources(),
+            [
+                ("/db/host", file.as_str()),
+                ("/db/pool/size", "args"),
+                ("/db/port", "env"),
+                ("/debug", "args"),
+                ("/name", "defaults"),
+                ("/new/key", "env"),
+                ("/ratio", "env"),
+                ("/tags", file.as_str()),
+            ]
+        );
+        assert_eq!(config.source("/tags/1"), Some(file.as_str()));
+        assert_eq!(config.source("/db"), None);
+        assert_eq!(config.source("/missing"), None);
+    }
+
+    #[test]
+    fn errors() {
+        let defaults = || map(r#"{"port":80,"db":{"host":"h"}}"#);
+        let build_err = |builder: ConfigBuilder| match builder.build() {
+            Ok(_) => panic!("Expected an Err"),
+            Err(e) => e,
+        };
+
+        assert_eq!(
+            build_err(
+                ConfigBuilder::new()
+                    .add_map("defaults", defaults())
+                    .add_env_vars("APP", [("APP__PORT", "eighty")])
+            ),
+            "env: cannot convert `eighty` to the integer at /port"
+        );
+        assert_eq!(
+            build_err(
+                ConfigBuilder::new()
+                    .add_map("defaults", defaults())
+                    .add_args(["--set", "db=x"])
+            ),
+            "args: cannot convert `x` to the object at /db"
+        );
+        assert_eq!(
+            build_err(ConfigBuilder::new().add_args(["--set", "a..b=1"])),
+            "Invalid --set argument, expected `name=value`: a..b=1"
+        );
+        assert_eq!(
+            build_err(ConfigBuilder::new().add_args(["--set"])),
+            "--set requires a value"
+        );
+        assert!(
+            build_err(ConfigBuilder::new().add_json_file("does-not-exist.json"))
+                .starts_with("does-not-exist.json: ")
+        );
+    }
+}
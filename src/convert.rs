@@ -0,0 +1,235 @@
+use crate::{Map, Value};
+
+/// 可以转换为`Value`的类型, `codegen`生成的结构体和枚举也实现了它.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+/// 可以从`Value`转换而来的类型, `codegen`生成的结构体和枚举也实现了它.
+///
+/// 错误信息以出错位置的JSON Pointer开头, 例如`/owner/age: Expected I64, found String`.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{FromValue, Map, Value};
+///
+/// let mut map = Map::new();
+/// map.merge(r#"{"ids":[1,2.0],"name":null}"#).unwrap();
+/// let ids: Vec<i64> = FromValue::from_field(&map, "ids").unwrap();
+/// assert_eq!(ids, [1, 2]);
+/// let name: Option<String> = FromValue::from_field(&map, "name").unwrap();
+/// assert_eq!(name, None);
+///
+/// let error = <Vec<bool>>::from_field(&map, "ids").unwrap_err();
+/// assert_eq!(error, "/ids/0: Expected Bool, found I64");
+/// assert_eq!(String::from_field(&map, "x").unwrap_err(), "Missing field `x`");
+/// ```
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, String>;
+
+    /// 从`map`的成员`key`转换. 成员不存在时按`Null`转换, 因此`Option<T>`得到None,
+    /// 其他类型返回`Missing field`错误.
+    fn from_field(map: &Map, key: &str) -> Result<Self, String> {
+        match map.get(key) {
+            Some(value) => Self::from_value(value).map_err(|e| nest_error(key, e)),
+            None => Self::from_value(&Value::Null).map_err(|_| format!("Missing field `{}`", key)),
+        }
+    }
+}
+
+/// `Value`的变体名称, 用于错误信息.
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "Null",
+        Value::Bool(_) => "Bool",
+        Value::F64(_) => "F64",
+        Value::I64(_) => "I64",
+        Value::String(_) => "String",
+        Value::Vec(_) => "Vec",
+        Value::Object(_) => "Object",
+    }
+}
+
+/// 返回`Expected X, found Y`形式的错误信息.
+pub fn type_error(expected: &str, found: &Value) -> String {
+    format!("Expected {}, found {}", expected, type_name(found))
+}
+
+// 在错误信息的路径前加上一段, 使错误指向最内层的位置.
+fn nest_error(segment: &str, error: String) -> String {
+    let segment = segment.replace('~', "~0").replace('/', "~1");
+    if error.starts_with('/') {
+        format!("/{}{}", segment, error)
+    } else {
+        format!("/{}: {}", segment, error)
+    }
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl ToValue for Map {
+    fn to_value(&self) -> Value {
+        Value::Object(self.clone())
+    }
+}
+
+impl FromValue for Map {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Object(map) => Ok(map.clone()),
+            _ => Err(type_error("Object", value)),
+        }
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        value.as_bool().ok_or_else(|| type_error("Bool", value))
+    }
+}
+
+impl ToValue for i64 {
+    fn to_value(&self) -> Value {
+        Value::I64(*self)
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_number() {
+            Some(n) => n.to_i64().map_err(|e| e.to_string()),
+            None => Err(type_error("I64", value)),
+        }
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::F64(*self)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_number() {
+            Some(n) => n.to_f64().map_err(|e| e.to_string()),
+            None => Err(type_error("F64", value)),
+        }
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err(type_error("String", value)),
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        Value::Vec(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Vec(vec) => vec
+                .iter()
+                .enumerate()
+                .map(|(i, v)| T::from_value(v).map_err(|e| nest_error(&i.to_string(), e)))
+                .collect(),
+            _ => Err(type_error("Vec", value)),
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(v) => v.to_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Null => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let value = vec![Some(1i64), None].to_value();
+        assert_eq!(value.to_json(), "[1,null]");
+        assert_eq!(
+            <Vec<Option<i64>>>::from_value(&value),
+            Ok(vec![Some(1), None])
+        );
+        assert_eq!("a".to_value().as_string(), Some("a"));
+        assert_eq!(f64::from_value(&Value::I64(3)), Ok(3.0));
+        assert_eq!(
+            i64::from_value(&Value::F64(2.5)),
+            Err("Number 2.5 cannot be represented exactly as i64".to_string())
+        );
+    }
+
+    #[test]
+    fn nested_errors() {
+        let mut map = Map::new();
+        map.merge(r#"{"a/b":[{"c":"x"}]}"#).unwrap();
+        let result =
+            <Vec<Map>>::from_field(&map, "a/b").and_then(|maps| i64::from_field(&maps[0], "c"));
+        assert_eq!(result, Err("/c: Expected I64, found String".to_string()));
+        assert_eq!(
+            <Vec<Vec<bool>>>::from_field(&map, "a/b"),
+            Err("/a~1b/0: Expected Vec, found Object".to_string())
+        );
+        assert_eq!(<Option<bool>>::from_field(&map, "missing"), Ok(None));
+    }
+}
@@ -0,0 +1,527 @@
+use crate::json_reader::JsonReader;
+use crate::{JsonReaderSettings, Map, Newline, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+/// 将`Value::Vec`中的各个对象作为记录写入CSV (RFC 4180).
+///
+/// 嵌套的对象展开为以`.`连接的列名, 数组和空对象以Json写入一个单元格, `Null`和缺少的成员写为空单元格.
+pub(crate) fn write_csv<W: Write>(
+    rows: &Value,
+    mut output: W,
+    settings: &CsvWriterSettings,
+) -> Result<(), String> {
+    let Value::Vec(ref rows) = *rows else {
+        return Err(String::from("CSV output requires an array of objects"));
+    };
+
+    let mut records = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        let Value::Object(ref obj) = *row else {
+            return Err(format!("CSV row {} is not an object", i));
+        };
+        let cells = flatten(obj)
+            .map_err(|column| format!("Duplicate CSV column in row {}: {}", i, column))?;
+        records.push(cells);
+    }
+
+    let columns: Vec<&str> = match settings.columns {
+        Some(ref columns) => columns.iter().map(String::as_str).collect(),
+        None => {
+            let columns: BTreeSet<&str> = records
+                .iter()
+                .flat_map(|cells| cells.keys().map(String::as_str))
+                .collect();
+            columns.into_iter().collect()
+        }
+    };
+
+    let mut line = String::new();
+    write_record(&mut line, columns.iter().copied(), settings);
+    output
+        .write_all(line.as_bytes())
+        .map_err(|e| e.to_string())?;
+    for cells in &records {
+        line.clear();
+        let fields = columns
+            .iter()
+            .map(|&column| cells.get(column).map_or(Cow::Borrowed(""), |v| cell(v)));
+        write_record(&mut line, fields, settings);
+        output
+            .write_all(line.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    output.flush().map_err(|e| e.to_string())
+}
+
+// 展开嵌套的对象, 返回列名与值. 两个成员展开为同一个列名时, 返回该列名.
+fn flatten(obj: &Map) -> Result<HashMap<String, &Value>, String> {
+    let mut cells = HashMap::new();
+    let mut stack = vec![(String::new(), obj)];
+    while let Some((prefix, map)) = stack.pop() {
+        for (key, value) in map.iter() {
+            let column = match prefix.is_empty() {
+                true => key.clone(),
+                false => format!("{}.{}", prefix, key),
+            };
+            match *value {
+                Value::Object(ref nested) if !nested.is_empty() => stack.push((column, nested)),
+                _ => {
+                    if cells.insert(column.clone(), value).is_some() {
+                        return Err(column);
+                    }
+                }
+            }
+        }
+    }
+    Ok(cells)
+}
+
+fn cell(value: &Value) -> Cow<'_, str> {
+    match *value {
+        Value::Null => Cow::Borrowed(""),
+        Value::String(ref s) => Cow::Borrowed(s),
+        _ => Cow::Owned(value.to_json()),
+    }
+}
+
+// 写入一条记录. 包含分隔符, 引号或换行的字段写在引号中, 其中的引号写为两个引号.
+fn write_record(
+    out: &mut String,
+    fields: impl Iterator<Item = impl AsRef<str>>,
+    settings: &CsvWriterSettings,
+) {
+    let delimiter = settings.delimiter as char;
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(delimiter);
+        }
+        let field = field.as_ref();
+        if field.contains([delimiter, '"', '\r', '\n']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str(match settings.newline {
+        Newline::Lf => "\n",
+        Newline::CrLf => "\r\n",
+    });
+}
+
+/// 从`input`中逐条读取CSV记录, 第一条记录为列名. 见`read_csv`.
+pub struct CsvRecords<R: Read> {
+    input: BufReader<R>,
+    settings: CsvReaderSettings,
+    header: Option<Vec<String>>,
+    line: usize, // 当前读取位置所在的行, 从1开始.
+    done: bool,  // 已经读到输入的结尾或者出错.
+}
+
+/// 以流的方式读取CSV (RFC 4180), 第一条记录为列名, 之后的每条记录转换为一个`Map`.
+///
+/// 字段可以使用`\n`或`\r\n`换行, 引号中的字段可以包含换行. 空行被忽略. 出错后迭代结束.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{CsvReaderSettings, read_csv};
+///
+/// let csv = "id,user.name,tags\r\n1,Tom,\"[\"\"a\"\"]\"\r\n";
+/// let settings = CsvReaderSettings {
+///     infer_types: true,
+///     ..Default::default()
+/// };
+/// for record in read_csv(csv.as_bytes(), settings) {
+///     let record = record.unwrap();
+///     assert_eq!(record.pointer("/id").unwrap().as_i64(), Some(1));
+///     assert_eq!(record.pointer("/user/name").unwrap().as_string(), Some("Tom"));
+///     assert_eq!(record.pointer("/tags/0").unwrap().as_string(), Some("a"));
+/// }
+/// ```
+pub fn read_csv<R: Read>(input: R, settings: CsvReaderSettings) -> CsvRecords<R> {
+    CsvRecords {
+        input: BufReader::new(input),
+        settings,
+        header: None,
+        line: 1,
+        done: false,
+    }
+}
+
+impl<R: Read> Iterator for CsvRecords<R> {
+    type Item = Result<Map, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_map().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+impl<R: Read> CsvRecords<R> {
+    fn read_map(&mut self) -> Result<Option<Map>, String> {
+        if self.header.is_none() {
+            let Some((_, mut header)) = self.read_record()? else {
+                return Ok(None);
+            };
+            if let Some(first) = header.first_mut()
+                && let Some(name) = first.strip_prefix('\u{feff}')
+            {
+                *first = name.to_string();
+            }
+            let mut seen = HashSet::new();
+            if let Some(name) = header.iter().find(|name| !seen.insert(name.as_str())) {
+                return Err(format!("Duplicate CSV column: {}", name));
+            }
+            self.header = Some(header);
+        }
+
+        let Some((line, fields)) = self.read_record()? else {
+            return Ok(None);
+        };
+        let header = self.header.as_ref().expect("the header was read");
+        if fields.len() != header.len() {
+            return Err(format!(
+                "CSV record at line {} has {} fields, expected {}",
+                line,
+                fields.len(),
+                header.len()
+            ));
+        }
+
+        let mut map = Map::new();
+        for (column, text) in header.iter().zip(fields) {
+            let value = match self.settings.infer_types {
+                true => infer(text),
+                false => Value::String(text),
+            };
+            insert(&mut map, column, value, self.settings.unflatten)
+                .map_err(|e| format!("{} at line {}", e, line))?;
+        }
+        Ok(Some(map))
+    }
+
+    // 读取一条记录的起始行和各个字段, 跳过空行. 到达输入的结尾时返回None.
+    fn read_record(&mut self) -> Result<Option<(usize, Vec<String>)>, String> {
+        loop {
+            let line = self.line;
+            let mut fields = Vec::new();
+            let mut field = Vec::new();
+            let mut quoted = false; // 当前字段以引号开始.
+            let mut in_quotes = false; // 位于引号之内.
+
+            let end = loop {
+                let Some(b) = self.next_byte()? else {
+                    if in_quotes {
+                        return Err(format!("Unterminated quoted CSV field at line {}", line));
+                    }
+                    break fields.is_empty() && field.is_empty() && !quoted;
+                };
+
+                if in_quotes {
+                    match b {
+                        b'"' if self.peek_byte()? == Some(b'"') => {
+                            self.next_byte()?;
+                            field.push(b'"');
+                        }
+                        b'"' => in_quotes = false,
+                        _ => {
+                            if b == b'\n' {
+                                self.line += 1;
+                            }
+                            field.push(b);
+                        }
+                    }
+                    continue;
+                }
+
+                match b {
+                    _ if b == self.settings.delimiter => {
+                        fields.push(self.field(field, line)?);
+                        field = Vec::new();
+                        quoted = false;
+                    }
+                    b'\r' | b'\n' => {
+                        if b == b'\r' && self.peek_byte()? == Some(b'\n') {
+                            self.next_byte()?;
+                        }
+                        self.line += 1;
+                        break false;
+                    }
+                    b'"' if field.is_empty() && !quoted => {
+                        quoted = true;
+                        in_quotes = true;
+                    }
+                    _ if quoted => {
+                        return Err(format!(
+                            "Unexpected character after a quoted CSV field at line {}",
+                            self.line
+                        ));
+                    }
+                    _ => field.push(b),
+                }
+            };
+
+            if end {
+                return Ok(None);
+            }
+            // 空行
+            if fields.is_empty() && field.is_empty() && !quoted {
+                continue;
+            }
+            fields.push(self.field(field, line)?);
+            return Ok(Some((line, fields)));
+        }
+    }
+
+    fn field(&self, bytes: Vec<u8>, line: usize) -> Result<String, String> {
+        String::from_utf8(bytes).map_err(|_| format!("Invalid UTF-8 in CSV field at line {}", line))
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, String> {
+        loop {
+            match self.input.fill_buf() {
+                Ok(buf) => return Ok(buf.first().copied()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>, String> {
+        let b = self.peek_byte()?;
+        if b.is_some() {
+            self.input.consume(1);
+        }
+        Ok(b)
+    }
+}
+
+// 推断单元格的类型. 有前导零的数字(例如邮政编码)保留为字符串.
+fn infer(text: String) -> Value {
+    let digits = text.strip_prefix('-').unwrap_or(&text);
+    let number = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.ends_with(|c: char| c.is_ascii_digit())
+        && digits
+            .bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+        && !(digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit());
+
+    match text.as_str() {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if number => match (text.parse(), text.parse()) {
+            (Ok(n), _) => Value::I64(n),
+            (_, Ok(n)) => Value::F64(n),
+            _ => Value::String(text),
+        },
+        _ if text.starts_with(['[', '{']) => JsonReader::new(JsonReaderSettings::default())
+            .parse_value(&text)
+            .unwrap_or(Value::String(text)),
+        _ => Value::String(text),
+    }
+}
+
+// 写入一个单元格的值. 写入CSV时缺少的成员写为空单元格, 因此展开时以`.`连接的列中的空单元格被忽略;
+// 其它列中的空单元格让位于同一前缀下的嵌套列, 例如`a`为空时`a.b`不会冲突.
+fn insert(map: &mut Map, column: &str, value: Value, unflatten: bool) -> Result<(), String> {
+    let parts: Vec<&str> = column.split('.').collect();
+    if !unflatten || parts.iter().any(|part| part.is_empty()) {
+        map.insert(column.to_string(), value);
+        return Ok(());
+    }
+    if parts.len() > 1 && is_empty(&value) {
+        return Ok(());
+    }
+
+    let conflict = || format!("Conflicting CSV column: {}", column);
+    let (last, parents) = parts.split_last().expect("split returns at least one part");
+    let mut target = map;
+    for part in parents {
+        let entry = target
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if is_empty(entry) {
+            *entry = Value::Object(Map::new());
+        }
+        target = match *entry {
+            Value::Object(ref mut obj) => obj,
+            _ => return Err(conflict()),
+        };
+    }
+
+    match target.get(*last) {
+        Some(Value::Object(_)) if is_empty(&value) => {}
+        Some(Value::Object(_)) => return Err(conflict()),
+        _ => {
+            target.insert(last.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+fn is_empty(value: &Value) -> bool {
+    match *value {
+        Value::Null => true,
+        Value::String(ref s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// 写入CSV时使用的设置.
+pub struct CsvWriterSettings {
+    pub delimiter: u8,                // 字段分隔符, 必须是ASCII字符.
+    pub newline: Newline,             // 每条记录之后的换行, RFC 4180规定为`\r\n`.
+    pub columns: Option<Vec<String>>, // 指定列名及其顺序, 行中其它的成员被忽略. 为None时使用所有行中列名的并集, 按字典序排列.
+}
+
+impl Default for CsvWriterSettings {
+    fn default() -> Self {
+        CsvWriterSettings {
+            delimiter: b',',
+            newline: Newline::CrLf,
+            columns: None,
+        }
+    }
+}
+
+/// 读取CSV时使用的设置.
+pub struct CsvReaderSettings {
+    pub delimiter: u8,     // 字段分隔符, 必须是ASCII字符.
+    pub infer_types: bool, // 是否推断值的类型: 空单元格为`Null`, `true`/`false`为`Bool`, 数字为`I64`或`F64`, Json数组和对象为`Vec`或`Object`. 否则所有值都是`String`.
+    pub unflatten: bool,   // 是否将以`.`连接的列名还原为嵌套的对象, 此时这些列中的空单元格被忽略.
+}
+
+impl Default for CsvReaderSettings {
+    fn default() -> Self {
+        CsvReaderSettings {
+            delimiter: b',',
+            infer_types: false,
+            unflatten: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        CsvReaderSettings, CsvWriterSettings, JsonWriterSettings, Map, Newline, Value, read_csv,
+    };
+
+    fn value(json: &str) -> Value {
+        let mut map = Map::new();
+        map.merge(&format!(r#"{{"v":{}}}"#, json)).unwrap();
+        map.remove("v").unwrap()
+    }
+
+    fn to_json(map: &Map) -> String {
+        map.to_json_with_settings(JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn write() {
+        let rows = value(
+            r#"[{"id":1,"name":"Tom","user":{"age":30,"tags":["a","b"]},"note":"say \"hi\", bye"},
+            {"id":2,"extra":null,"user":{"meta":{}},"note":"line\nbreak"}]"#,
+        );
+        assert_eq!(
+            rows.to_csv().unwrap(),
+            "extra,id,name,note,user.age,user.meta,user.tags\r\n\
+             ,1,Tom,\"say \"\"hi\"\", bye\",30,,\"[\"\"a\"\",\"\"b\"\"]\"\r\n\
+             ,2,,\"line\nbreak\",,{},\r\n"
+        );
+
+        let settings = CsvWriterSettings {
+            delimiter: b';',
+            newline: Newline::Lf,
+            columns: Some(vec!["name".to_string(), "id".to_string()]),
+        };
+        let mut output = Vec::new();
+        rows.write_csv(&mut output, settings).unwrap();
+        assert_eq!(output, b"name;id\nTom;1\n;2\n");
+
+        assert_eq!(
+            value("[1]").to_csv().err().unwrap(),
+            "CSV row 0 is not an object"
+        );
+        assert_eq!(
+            value(r#"[{"a.b":1,"a":{"b":2}}]"#).to_csv().err().unwrap(),
+            "Duplicate CSV column in row 0: a.b"
+        );
+    }
+
+    #[test]
+    fn read() {
+        let csv = "\u{feff}id,user.name,user.age,note,zip,tags\n\
+                   1,Tom,30,\"multi\r\nline, \"\"quoted\"\"\",007,\"[1,{\"\"a\"\":2}]\"\r\n\
+                   \n\
+                   2,,,,-1.5e3,[oops\n";
+        let maps: Vec<Map> = Map::from_csv(csv).unwrap();
+        assert_eq!(
+            to_json(&maps[0]),
+            r#"{"id":"1","note":"multi\r\nline, \"quoted\"","tags":"[1,{\"a\":2}]","user":{"age":"30","name":"Tom"},"zip":"007"}"#
+        );
+
+        let settings = CsvReaderSettings {
+            infer_types: true,
+            ..Default::default()
+        };
+        let maps = Map::from_csv_with_settings(csv, settings).unwrap();
+        assert_eq!(
+            to_json(&maps[0]),
+            r#"{"id":1,"note":"multi\r\nline, \"quoted\"","tags":[1,{"a":2}],"user":{"age":30,"name":"Tom"},"zip":"007"}"#
+        );
+        assert_eq!(
+            to_json(&maps[1]),
+            r#"{"id":2,"note":null,"tags":"[oops","zip":-1500}"#
+        );
+
+        // 写入的CSV可以读回, 空单元格让位于嵌套的列
+        let rows = value(r#"[{"a":{"b":1},"c":"x"},{"a":"y"}]"#);
+        let settings = CsvReaderSettings {
+            infer_types: true,
+            ..Default::default()
+        };
+        let maps = Map::from_csv_with_settings(&rows.to_csv().unwrap(), settings).unwrap();
+        assert_eq!(to_json(&maps[0]), r#"{"a":{"b":1},"c":"x"}"#);
+        assert_eq!(to_json(&maps[1]), r#"{"a":"y","c":null}"#);
+    }
+
+    #[test]
+    fn read_errors() {
+        let cases = [
+            ("a,b\n1\n", "CSV record at line 2 has 1 fields, expected 2"),
+            ("a,a\n", "Duplicate CSV column: a"),
+            ("a\n\"x\n", "Unterminated quoted CSV field at line 2"),
+            (
+                "a\n\"x\"y\n",
+                "Unexpected character after a quoted CSV field at line 2",
+            ),
+            ("a,a.b\nx,y\n", "Conflicting CSV column: a.b at line 2"),
+        ];
+        for (csv, expected) in cases {
+            match Map::from_csv(csv) {
+                Ok(_) => panic!("Expected an Err for {:?}", csv),
+                Err(e) => assert_eq!(e, expected),
+            }
+        }
+
+        // 出错后迭代结束
+        let mut records = read_csv("a\n1\n\"\n".as_bytes(), CsvReaderSettings::default());
+        assert!(records.next().unwrap().is_ok());
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+    }
+}
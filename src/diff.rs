@@ -0,0 +1,388 @@
+use crate::canonical;
+use crate::compare;
+use crate::json_writer::JsonWriter;
+use crate::{CompareSettings, JsonWriterSettings, Value};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// `diff`返回的一处改动, `pointer`为改动所在位置的JSON Pointer, 空字符串表示最外层.
+pub enum Change<'a> {
+    Added {
+        pointer: String,
+        value: &'a Value,
+    },
+    Removed {
+        pointer: String,
+        value: &'a Value,
+    },
+    Changed {
+        pointer: String,
+        old: &'a Value,
+        new: &'a Value,
+    },
+}
+
+impl Change<'_> {
+    pub fn pointer(&self) -> &str {
+        match self {
+            Change::Added { pointer, .. }
+            | Change::Removed { pointer, .. }
+            | Change::Changed { pointer, .. } => pointer,
+        }
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { value, .. } => write!(f, "Added {}", value.to_json())?,
+            Change::Removed { value, .. } => write!(f, "Removed {}", value.to_json())?,
+            Change::Changed { old, new, .. } => {
+                write!(f, "Changed {} to {}", old.to_json(), new.to_json())?
+            }
+        }
+        match self.pointer() {
+            "" => f.write_str(" at the root"),
+            pointer => write!(f, " at {}", pointer),
+        }
+    }
+}
+
+/// 列出从`old`到`new`的所有改动, 按路径排序. `I64`与`F64`按数学值比较,
+/// 数组按下标逐个比较, 与默认设置的`Value::compare`一致.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{Map, Value, diff};
+///
+/// let mut old = Map::new();
+/// old.merge(r#"{"a":1,"b":[1,2],"c":true}"#).unwrap();
+/// let mut new = Map::new();
+/// new.merge(r#"{"a":1.0,"b":[1,3,4],"d":null}"#).unwrap();
+///
+/// let changes: Vec<String> = diff(&Value::from(old), &Value::from(new))
+///     .iter()
+///     .map(|c| c.to_string())
+///     .collect();
+/// assert_eq!(
+///     changes,
+///     [
+///         "Changed 2 to 3 at /b/1",
+///         "Added 4 at /b/2",
+///         "Removed true at /c",
+///         "Added null at /d",
+///     ]
+/// );
+/// ```
+pub fn diff<'a>(old: &'a Value, new: &'a Value) -> Vec<Change<'a>> {
+    compare::compare_values(new, old, &CompareSettings::default())
+        .into_iter()
+        .map(|m| match (m.expected, m.actual) {
+            (Some(old), Some(new)) => Change::Changed {
+                pointer: m.pointer,
+                old,
+                new,
+            },
+            (Some(value), None) => Change::Removed {
+                pointer: m.pointer,
+                value,
+            },
+            (None, Some(value)) => Change::Added {
+                pointer: m.pointer,
+                value,
+            },
+            (None, None) => unreachable!(),
+        })
+        .collect()
+}
+
+/// 输出差异时使用的设置.
+pub struct DiffSettings {
+    pub indentation: String, // 每一层嵌套的缩进.
+    pub color: bool,         // 是否用ANSI转义序列将删除的行标为红色, 增加的行标为绿色.
+}
+
+impl Default for DiffSettings {
+    fn default() -> Self {
+        DiffSettings {
+            indentation: "  ".to_string(),
+            color: false,
+        }
+    }
+}
+
+/// 将`old`与`new`按多行格式并排输出为统一格式的差异, 对象成员按名称对齐, 数组元素按下标对齐.
+///
+/// 每行以`-`(只在`old`中), `+`(只在`new`中)或空格(两者相同)开头, 对象成员按名称排序.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{DiffSettings, Map, Value, render_diff};
+///
+/// let mut old = Map::new();
+/// old.merge(r#"{"name":"a","tags":["x"]}"#).unwrap();
+/// let mut new = Map::new();
+/// new.merge(r#"{"name":"b","tags":["x"]}"#).unwrap();
+///
+/// let text = render_diff(&Value::from(old), &Value::from(new), DiffSettings::default());
+/// assert_eq!(
+///     text,
+///     concat!(
+///         "  {\n",
+///         "-   \"name\": \"a\",\n",
+///         "+   \"name\": \"b\",\n",
+///         "    \"tags\": [\n",
+///         "      \"x\"\n",
+///         "    ]\n",
+///         "  }\n",
+///     )
+/// );
+/// ```
+pub fn render_diff(old: &Value, new: &Value, settings: DiffSettings) -> String {
+    let writer_settings = JsonWriterSettings {
+        indentation: settings.indentation.clone(),
+        sort_keys: true,
+        ..Default::default()
+    };
+    let mut renderer = DiffRenderer {
+        writer: JsonWriter::new(&writer_settings),
+        settings: &settings,
+        output: String::new(),
+    };
+    renderer.render(0, "", old, new, "");
+    renderer.output
+}
+
+// 同时遍历两个文档, 按行输出.
+struct DiffRenderer<'s> {
+    writer: JsonWriter<'s>,
+    settings: &'s DiffSettings,
+    output: String,
+}
+
+impl DiffRenderer<'_> {
+    fn line(&mut self, sign: char, depth: usize, text: &str) {
+        let color = match sign {
+            '-' if self.settings.color => Some("\x1b[31m"),
+            '+' if self.settings.color => Some("\x1b[32m"),
+            _ => None,
+        };
+        if let Some(color) = color {
+            self.output.push_str(color);
+        }
+        self.output.push(sign);
+        self.output.push(' ');
+        for _ in 0..depth {
+            self.output.push_str(&self.settings.indentation);
+        }
+        self.output.push_str(text);
+        if color.is_some() {
+            self.output.push_str("\x1b[0m");
+        }
+        self.output.push('\n');
+    }
+
+    // 输出整个值, `prefix`为对象成员的名称部分, `suffix`为之后的逗号.
+    fn value(&mut self, sign: char, depth: usize, prefix: &str, value: &Value, suffix: &str) {
+        let json = self.writer.format_value(value);
+        let mut lines = json.lines().peekable();
+        let mut first = true;
+        while let Some(line) = lines.next() {
+            let mut text = String::new();
+            if first {
+                text.push_str(prefix);
+                first = false;
+            }
+            text.push_str(line);
+            if lines.peek().is_none() {
+                text.push_str(suffix);
+            }
+            self.line(sign, depth, &text);
+        }
+    }
+
+    fn render(&mut self, depth: usize, prefix: &str, old: &Value, new: &Value, suffix: &str) {
+        if canonical::compare(old, new) == Ordering::Equal {
+            self.value(' ', depth, prefix, old, suffix);
+            return;
+        }
+
+        match (old, new) {
+            (Value::Object(o), Value::Object(n)) => {
+                self.line(' ', depth, &format!("{}{{", prefix));
+                let mut keys: Vec<&String> = o
+                    .keys()
+                    .chain(n.keys().filter(|k| !o.contains_key(*k)))
+                    .collect();
+                keys.sort_unstable();
+                for (i, key) in keys.iter().enumerate() {
+                    let comma = if i + 1 < keys.len() { "," } else { "" };
+                    let name = format!(
+                        "{}: ",
+                        self.writer.format_value(&Value::String(key.to_string()))
+                    );
+                    match (o.get(*key), n.get(*key)) {
+                        (Some(o), Some(n)) => self.render(depth + 1, &name, o, n, comma),
+                        (Some(o), None) => self.value('-', depth + 1, &name, o, comma),
+                        (None, Some(n)) => self.value('+', depth + 1, &name, n, comma),
+                        (None, None) => unreachable!(),
+                    }
+                }
+                self.line(' ', depth, &format!("}}{}", suffix));
+            }
+            (Value::Vec(o), Value::Vec(n)) => {
+                self.line(' ', depth, &format!("{}[", prefix));
+                let len = o.len().max(n.len());
+                for i in 0..len {
+                    let comma = if i + 1 < len { "," } else { "" };
+                    match (o.get(i), n.get(i)) {
+                        (Some(o), Some(n)) => self.render(depth + 1, "", o, n, comma),
+                        (Some(o), None) => self.value('-', depth + 1, "", o, comma),
+                        (None, Some(n)) => self.value('+', depth + 1, "", n, comma),
+                        (None, None) => unreachable!(),
+                    }
+                }
+                self.line(' ', depth, &format!("]{}", suffix));
+            }
+            _ => {
+                self.value('-', depth, prefix, old, suffix);
+                self.value('+', depth, prefix, new, suffix);
+            }
+        }
+    }
+}
+
+// `assert_json_eq!`的实现, 不属于公开的API.
+#[doc(hidden)]
+pub fn assert_json_eq_impl(actual: Value, expected: Value, message: Option<fmt::Arguments>) {
+    let mismatches = compare::compare_values(&actual, &expected, &CompareSettings::default());
+    if mismatches.is_empty() {
+        return;
+    }
+
+    let mut report = String::from("assertion failed: JSON values are not equal");
+    if let Some(message) = message {
+        report.push_str(&format!(": {}", message));
+    }
+    report.push('\n');
+    for mismatch in &mismatches {
+        report.push_str(&format!("  {}\n", mismatch));
+    }
+    report.push_str("--- expected\n+++ actual\n");
+    report.push_str(&render_diff(&expected, &actual, DiffSettings::default()));
+    panic!("{}", report);
+}
+
+/// 断言两个可以转换为`Value`的值语义相等(参见`diff`), 否则以差异报告panic.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{Map, Value, assert_json_eq};
+///
+/// let mut map = Map::new();
+/// map.merge(r#"{"a":[1,2]}"#).unwrap();
+/// let mut expected = Map::new();
+/// expected.merge(r#"{"a":[1.0,2.0]}"#).unwrap();
+/// assert_json_eq!(map, expected);
+/// assert_json_eq!(Value::I64(1), Value::F64(1.0), "numbers should match");
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_json_eq_impl(
+            ::std::convert::Into::into($actual),
+            ::std::convert::Into::into($expected),
+            ::std::option::Option::None,
+        )
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::assert_json_eq_impl(
+            ::std::convert::Into::into($actual),
+            ::std::convert::Into::into($expected),
+            ::std::option::Option::Some(::std::format_args!($($arg)+)),
+        )
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Map;
+
+    fn parse(json: &str) -> Value {
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        Value::from(map)
+    }
+
+    #[test]
+    fn changes() {
+        let old = parse(r#"{"a":{"b":1,"c":[1,2]},"d/e":"x"}"#);
+        let new = parse(r#"{"a":{"b":"1","c":[1]},"f":{}}"#);
+        let changes: Vec<(String, String)> = diff(&old, &new)
+            .iter()
+            .map(|c| (c.pointer().to_string(), c.to_string()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (
+                    "/a/b".to_string(),
+                    r#"Changed 1 to "1" at /a/b"#.to_string()
+                ),
+                ("/a/c/1".to_string(), "Removed 2 at /a/c/1".to_string()),
+                ("/d~1e".to_string(), r#"Removed "x" at /d~1e"#.to_string()),
+                ("/f".to_string(), "Added {} at /f".to_string()),
+            ]
+        );
+        assert!(diff(&old, &old.clone()).is_empty());
+        assert_eq!(
+            diff(&Value::Null, &Value::Bool(true))[0].to_string(),
+            "Changed null to true at the root"
+        );
+    }
+
+    #[test]
+    fn render() {
+        let old = parse(r#"{"a":{"b":1,"c":[1,2]},"d/e":"x","g":[{"h":null}]}"#);
+        let new = parse(r#"{"a":{"b":2,"c":[1]},"f":{"x":true},"g":[{"h":null}]}"#);
+        let text = render_diff(&old, &new, DiffSettings::default());
+        assert_eq!(
+            text,
+            concat!(
+                "  {\n",
+                "    \"a\": {\n",
+                "-     \"b\": 1,\n",
+                "+     \"b\": 2,\n",
+                "      \"c\": [\n",
+                "        1,\n",
+                "-       2\n",
+                "      ]\n",
+                "    },\n",
+                "-   \"d/e\": \"x\",\n",
+                "+   \"f\": {\n",
+                "+     \"x\": true\n",
+                "+   },\n",
+                "    \"g\": [\n",
+                "      {\n",
+                "        \"h\": null\n",
+                "      }\n",
+                "    ]\n",
+                "  }\n",
+            )
+        );
+
+        let settings = DiffSettings {
+            indentation: "\t".to_string(),
+            color: true,
+        };
+        let text = render_diff(&Value::I64(1), &Value::Vec(vec![Value::I64(1)]), settings);
+        assert_eq!(
+            text,
+            "\x1b[31m- 1\x1b[0m\n\x1b[32m+ [\x1b[0m\n\x1b[32m+ \t1\x1b[0m\n\x1b[32m+ ]\x1b[0m\n"
+        );
+    }
+}
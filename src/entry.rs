@@ -0,0 +1,145 @@
+use crate::{Map, PathSegment, Value, to_json_pointer};
+
+/// `Map::entry_path`返回的嵌套条目, 与`std::collections::hash_map::Entry`用法相同.
+pub enum PathEntry<'a> {
+    /// 路径上的值已经存在.
+    Occupied(&'a mut Value),
+    /// 路径上的值不存在, 插入时会创建缺少的中间对象.
+    Vacant(VacantPathEntry<'a>),
+}
+
+/// 不存在的嵌套条目, 保存了最深的已有对象和其下缺少的成员名称.
+pub struct VacantPathEntry<'a> {
+    map: &'a mut Map,
+    keys: Vec<String>,
+}
+
+impl<'a> PathEntry<'a> {
+    // `keys`不能为空, 中间的值不是对象时返回错误, 此时`map`不会被修改.
+    pub(crate) fn new(mut map: &'a mut Map, mut keys: Vec<String>) -> Result<Self, String> {
+        if keys.is_empty() {
+            return Err("The entry path is empty".to_string());
+        }
+
+        let mut i = 0;
+        loop {
+            if !map.contains_key(&keys[i]) {
+                let keys = keys.split_off(i);
+                return Ok(PathEntry::Vacant(VacantPathEntry { map, keys }));
+            }
+            if i + 1 == keys.len() {
+                return Ok(PathEntry::Occupied(map.get_mut(&keys[i]).unwrap()));
+            }
+            match map.get_mut(&keys[i]).unwrap() {
+                Value::Object(obj) => {
+                    map = obj;
+                    i += 1;
+                }
+                _ => {
+                    let path: Vec<PathSegment> = keys[..=i]
+                        .iter()
+                        .map(|k| PathSegment::Key(k.as_str().into()))
+                        .collect();
+                    return Err(format!(
+                        "Cannot create an entry below {} because it is not an object",
+                        to_json_pointer(&path)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// 值不存在时插入`default`, 返回路径上的值.
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        match self {
+            PathEntry::Occupied(value) => value,
+            PathEntry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// 值不存在时插入`default`的返回值, 返回路径上的值.
+    pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+        match self {
+            PathEntry::Occupied(value) => value,
+            PathEntry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// 值存在时对其调用`f`.
+    pub fn and_modify<F: FnOnce(&mut Value)>(self, f: F) -> Self {
+        match self {
+            PathEntry::Occupied(value) => {
+                f(value);
+                PathEntry::Occupied(value)
+            }
+            vacant => vacant,
+        }
+    }
+}
+
+impl<'a> VacantPathEntry<'a> {
+    /// 创建缺少的中间对象并插入`value`, 返回插入的值.
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        let mut keys = self.keys;
+        let last = keys.pop().unwrap();
+        let mut map = self.map;
+        for key in keys {
+            map = match map.entry(key).or_insert(Value::Object(Map::new())) {
+                Value::Object(obj) => obj,
+                _ => unreachable!(),
+            };
+        }
+
+        map.entry(last).or_insert(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Map, Value};
+
+    #[test]
+    fn entry_path() {
+        let mut map = Map::new();
+        map.merge(r#"{"a":{"b":1,"s":"x"}}"#).unwrap();
+
+        // 已有的值不会被覆盖.
+        let value = map.entry_path(["a", "b"]).unwrap().or_insert(Value::I64(2));
+        assert!(*value == Value::I64(1));
+
+        map.entry_path(["a", "c", "d"])
+            .unwrap()
+            .or_insert_with(|| Value::Vec(Vec::new()))
+            .as_vec_mut()
+            .unwrap()
+            .push(Value::Bool(true));
+        assert_eq!(map.pointer("/a/c/d/0").unwrap().as_bool(), Some(true));
+        assert_eq!(map.pointer("/a/s").unwrap().as_string(), Some("x"));
+
+        map.entry_path(vec!["a".to_string(), "b".to_string()])
+            .unwrap()
+            .and_modify(|v| *v = Value::I64(v.as_i64().unwrap() + 1))
+            .or_insert(Value::Null);
+        assert_eq!(map.pointer("/a/b").unwrap().as_i64(), Some(2));
+        map.entry_path(["x"])
+            .unwrap()
+            .and_modify(|_| unreachable!());
+        assert!(map.get("x").is_none());
+    }
+
+    #[test]
+    fn entry_path_errors() {
+        let mut map = Map::new();
+        map.merge(r#"{"a":{"b/c":1}}"#).unwrap();
+        assert_eq!(
+            map.entry_path(["a", "b/c", "d", "e"]).err().unwrap(),
+            "Cannot create an entry below /a/b~1c because it is not an object"
+        );
+        assert_eq!(
+            map.entry_path(Vec::<String>::new()).err().unwrap(),
+            "The entry path is empty"
+        );
+        assert_eq!(map.pointer("/a/b~1c").unwrap().as_i64(), Some(1));
+        assert_eq!(map["a"].as_object().unwrap().len(), 1);
+    }
+}
@@ -1,108 +1,382 @@
 use crate::json_token::JsonToken;
 use crate::json_tokenizer::JsonTokenizer;
-use crate::{Map, Value};
+use crate::{Map, MapRef, Value, ValueRef};
+use std::borrow::Cow;
 
 /// 将Json转换成`Map`的转换器.
+///
+/// 解析过程使用显式的栈而不是递归, 因此嵌套深度只受`JsonReaderSettings`中的限制约束, 而不受线程栈大小约束.
 pub struct JsonReader {
     settings: JsonReaderSettings,
 }
 
+// 单次解析过程中的计数状态, 用于检查`JsonReaderSettings`中的各项限制.
+#[derive(Default)]
+struct ParseContext {
+    depth: usize, // 当前的嵌套深度(对象与数组).
+    nodes: usize, // 已经读取的节点(值)总数.
+}
+
+// 解析结果的构建方式, 使同一个解析过程既可以生成`Map`, 也可以生成借用输入文本的`MapRef`.
+trait Tree<'a> {
+    type Value;
+    type Map: Default;
+
+    fn null() -> Self::Value;
+    fn bool(val: bool) -> Self::Value;
+    fn f64(val: f64) -> Self::Value;
+    fn i64(val: i64) -> Self::Value;
+    fn string(val: Cow<'a, str>) -> Self::Value;
+    fn vec(val: Vec<Self::Value>) -> Self::Value;
+    fn object(val: Self::Map) -> Self::Value;
+    fn insert(map: &mut Self::Map, name: Cow<'a, str>, val: Self::Value);
+}
+
+// 构建拥有所有权的`Map`.
+struct Owned;
+
+impl<'a> Tree<'a> for Owned {
+    type Value = Value;
+    type Map = Map;
+
+    fn null() -> Value {
+        Value::Null
+    }
+
+    fn bool(val: bool) -> Value {
+        Value::Bool(val)
+    }
+
+    fn f64(val: f64) -> Value {
+        Value::F64(val)
+    }
+
+    fn i64(val: i64) -> Value {
+        Value::I64(val)
+    }
+
+    fn string(val: Cow<'a, str>) -> Value {
+        Value::String(val.into_owned())
+    }
+
+    fn vec(val: Vec<Value>) -> Value {
+        Value::Vec(val)
+    }
+
+    fn object(val: Map) -> Value {
+        Value::Object(val)
+    }
+
+    fn insert(map: &mut Map, name: Cow<'a, str>, val: Value) {
+        map.insert(name.into_owned(), val);
+    }
+}
+
+// 构建借用输入文本的`MapRef`.
+struct Borrowed;
+
+impl<'a> Tree<'a> for Borrowed {
+    type Value = ValueRef<'a>;
+    type Map = MapRef<'a>;
+
+    fn null() -> ValueRef<'a> {
+        ValueRef::Null
+    }
+
+    fn bool(val: bool) -> ValueRef<'a> {
+        ValueRef::Bool(val)
+    }
+
+    fn f64(val: f64) -> ValueRef<'a> {
+        ValueRef::F64(val)
+    }
+
+    fn i64(val: i64) -> ValueRef<'a> {
+        ValueRef::I64(val)
+    }
+
+    fn string(val: Cow<'a, str>) -> ValueRef<'a> {
+        ValueRef::String(val)
+    }
+
+    fn vec(val: Vec<ValueRef<'a>>) -> ValueRef<'a> {
+        ValueRef::Vec(val)
+    }
+
+    fn object(val: MapRef<'a>) -> ValueRef<'a> {
+        ValueRef::Object(val)
+    }
+
+    fn insert(map: &mut MapRef<'a>, name: Cow<'a, str>, val: ValueRef<'a>) {
+        map.insert(name, val);
+    }
+}
+
+// 解析栈中尚未闭合的容器.
+enum Frame<'a, T: Tree<'a>> {
+    Object {
+        map: T::Map,
+        name: Option<Cow<'a, str>>, // 已读取名称, 正在等待其值的成员.
+        members: usize,
+    },
+    Array(Vec<T::Value>),
+}
+
 impl JsonReader {
     pub fn new(settings: JsonReaderSettings) -> Self {
         JsonReader { settings }
     }
 
+    /// 解析Json并将其中的成员插入`obj`. 解析失败时`obj`保持不变.
     pub fn parse(&self, obj: &mut Map, json: &str) -> Result<(), String> {
-        let mut tokenizer = JsonTokenizer::new(json);
+        let root = self.parse_root::<Owned>(json)?;
+        obj.merge_from(root);
 
-        self.parse_object(obj, &mut tokenizer)?;
+        Ok(())
+    }
+
+    /// 解析Json, 返回的`MapRef`直接借用`json`中没有转义字符的字符串和名称.
+    pub fn parse_borrowed<'a>(&self, json: &'a str) -> Result<MapRef<'a>, String> {
+        self.parse_root::<Borrowed>(json)
+    }
+
+    /// 解析任意的Json值, 最外层不要求是对象.
+    pub(crate) fn parse_value(&self, json: &str) -> Result<Value, String> {
+        if json.len() > self.settings.max_input_length {
+            return Err(format!(
+                "The set input length limit is exceeded: {}",
+                self.settings.max_input_length
+            ));
+        }
+
+        let mut tokenizer = self.tokenizer(json);
+        let mut ctx = ParseContext::default();
+
+        let value = match tokenizer.next()? {
+            JsonToken::StartObject => {
+                Value::Object(self.parse_object::<Owned>(&mut tokenizer, &mut ctx)?)
+            }
+            JsonToken::StartArray => {
+                self.enter_container(&mut ctx)?;
+                match self.parse_container::<Owned>(
+                    &mut tokenizer,
+                    &mut ctx,
+                    Frame::Array(Vec::new()),
+                )? {
+                    Frame::Array(vec) => Value::Vec(vec),
+                    Frame::Object { .. } => unreachable!("the root frame is an array"),
+                }
+            }
+            token => {
+                self.count_node(&mut ctx)?;
+                self.parse_single_value::<Owned>(token)?
+            }
+        };
+
+        if tokenizer.next()? != JsonToken::EndDocument {
+            return Err("Expected end of JSON after value".to_string());
+        }
+        Ok(value)
+    }
+
+    fn parse_root<'a, T: Tree<'a>>(&self, json: &'a str) -> Result<T::Map, String> {
+        if json.len() > self.settings.max_input_length {
+            return Err(format!(
+                "The set input length limit is exceeded: {}",
+                self.settings.max_input_length
+            ));
+        }
+
+        let mut tokenizer = self.tokenizer(json);
+        let mut ctx = ParseContext::default();
+
+        let token = tokenizer.next()?;
+        if token != JsonToken::StartObject {
+            return Err("Expected an object".to_string());
+        }
+        let root = self.parse_object::<T>(&mut tokenizer, &mut ctx)?;
 
         let last_token = tokenizer.next()?;
         if last_token != JsonToken::EndDocument {
             Err("Expected end of JSON after object".to_string())
         } else {
-            Ok(())
+            Ok(root)
         }
     }
 
-    fn parse_object(&self, obj: &mut Map, tokenizer: &mut JsonTokenizer) -> Result<(), String> {
-        let mut token = tokenizer.next()?;
-        if token != JsonToken::StartObject {
-            return Err("Expected an object".to_string());
-        }
+    // 字符串和数字的长度限制由标记器在扫描时检查.
+    fn tokenizer<'a>(&self, json: &'a str) -> JsonTokenizer<'a> {
+        JsonTokenizer::with_limits(
+            json,
+            self.settings.max_string_length,
+            self.settings.max_number_length,
+        )
+    }
 
-        if tokenizer.object_depth > self.settings.recursion_limit {
-            return Err(format!(
-                "The set recursion depth is exceeded: {}",
-                self.settings.recursion_limit
-            ));
+    // 解析一个对象, 直到与之匹配的EndObject. 假设开头的StartObject已经被读取.
+    fn parse_object<'a, T: Tree<'a>>(
+        &self,
+        tokenizer: &mut JsonTokenizer<'a>,
+        ctx: &mut ParseContext,
+    ) -> Result<T::Map, String> {
+        let root = self.start_object::<T>(tokenizer, ctx)?;
+        match self.parse_container::<T>(tokenizer, ctx, root)? {
+            Frame::Object { map, .. } => Ok(map),
+            Frame::Array(_) => unreachable!("the root frame is an object"),
         }
+    }
 
-        loop {
-            token = tokenizer.next()?;
-            if token == JsonToken::EndObject {
-                return Ok(());
-            }
+    // 解析一个对象或数组, 直到与`root`匹配的结束标记, 返回读取完成的`root`.
+    fn parse_container<'a, T: Tree<'a>>(
+        &self,
+        tokenizer: &mut JsonTokenizer<'a>,
+        ctx: &mut ParseContext,
+        root: Frame<'a, T>,
+    ) -> Result<Frame<'a, T>, String> {
+        let mut stack = vec![root];
 
-            match token {
+        loop {
+            let token = tokenizer.next()?;
+            let value = match token {
+                JsonToken::EndObject | JsonToken::EndArray => {
+                    ctx.depth -= 1;
+                    let frame = match stack.pop() {
+                        Some(frame @ Frame::Object { .. }) if token == JsonToken::EndObject => {
+                            frame
+                        }
+                        Some(frame @ Frame::Array(_)) if token == JsonToken::EndArray => frame,
+                        _ => return Err(format!("Unexpected token type {:?}", token)),
+                    };
+                    if stack.is_empty() {
+                        return Ok(frame);
+                    }
+                    match frame {
+                        Frame::Object { map, .. } => T::object(map),
+                        Frame::Array(vec) => T::vec(vec),
+                    }
+                }
                 JsonToken::Name(name) => {
-                    let val = self.parse_value_type(obj, name.as_str(), tokenizer)?;
-                    obj.insert(name, val);
+                    match stack.last_mut() {
+                        Some(Frame::Object {
+                            name: pending,
+                            members,
+                            ..
+                        }) => {
+                            if *members >= self.settings.max_object_members {
+                                return Err(format!(
+                                    "The set object member limit is exceeded: {}",
+                                    self.settings.max_object_members
+                                ));
+                            }
+                            *members += 1;
+                            *pending = Some(name);
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Unexpected token type {:?}",
+                                JsonToken::Name(name)
+                            ));
+                        }
+                    }
+                    continue;
+                }
+                JsonToken::StartObject => {
+                    self.check_array_length(&stack)?;
+                    let frame = self.start_object::<T>(tokenizer, ctx)?;
+                    stack.push(frame);
+                    continue;
                 }
-                _ => return Err(format!("Unexpected token type {:?}", token)),
+                JsonToken::StartArray => {
+                    self.check_array_length(&stack)?;
+                    self.enter_container(ctx)?;
+                    stack.push(Frame::Array(Vec::new()));
+                    continue;
+                }
+                _ => {
+                    self.check_array_length(&stack)?;
+                    self.count_node(ctx)?;
+                    self.parse_single_value::<T>(token)?
+                }
+            };
+
+            match stack.last_mut() {
+                Some(Frame::Object { map, name, .. }) => match name.take() {
+                    Some(name) => T::insert(map, name, value),
+                    None => return Err("Expected a name before the value".to_string()),
+                },
+                Some(Frame::Array(vec)) => vec.push(value),
+                None => return Err("Unexpected value after the end of object".to_string()),
             }
         }
     }
 
-    fn parse_value_type(
+    // 开始一个新对象. 假设StartObject已经被读取.
+    fn start_object<'a, T: Tree<'a>>(
         &self,
-        obj: &mut Map,
-        name: &str,
-        tokenizer: &mut JsonTokenizer,
-    ) -> Result<Value, String> {
-        let token = tokenizer.next()?;
-        if token == JsonToken::StartArray {
-            let vec = self.parse_array(obj, name, tokenizer)?;
-            Ok(vec)
-        } else if token == JsonToken::StartObject {
-            let mut nested_obj = Map::new();
+        tokenizer: &JsonTokenizer,
+        ctx: &mut ParseContext,
+    ) -> Result<Frame<'a, T>, String> {
+        if tokenizer.object_depth > self.settings.recursion_limit {
+            return Err(format!(
+                "The set recursion depth is exceeded: {}",
+                self.settings.recursion_limit
+            ));
+        }
+        self.enter_container(ctx)?;
 
-            tokenizer.push_back(token)?;
-            self.parse_object(&mut nested_obj, tokenizer)?;
+        Ok(Frame::Object {
+            map: T::Map::default(),
+            name: None,
+            members: 0,
+        })
+    }
 
-            Ok(Value::Object(nested_obj))
-        } else {
-            let val = self.parse_single_value(&token)?;
-            Ok(val)
+    // 如果即将读取的值是数组的元素, 检查数组是否已经达到最大长度.
+    fn check_array_length<'a, T: Tree<'a>>(&self, stack: &[Frame<'a, T>]) -> Result<(), String> {
+        match stack.last() {
+            Some(Frame::Array(vec)) if vec.len() >= self.settings.max_array_length => Err(format!(
+                "The set array length limit is exceeded: {}",
+                self.settings.max_array_length
+            )),
+            _ => Ok(()),
         }
     }
 
-    fn parse_array(
-        &self,
-        obj: &mut Map,
-        name: &str,
-        tokenizer: &mut JsonTokenizer,
-    ) -> Result<Value, String> {
-        let mut vec = Vec::<Value>::new();
-        loop {
-            let token = tokenizer.next()?;
-            if token == JsonToken::EndArray {
-                return Ok(Value::Vec(vec));
-            }
+    // 进入一个对象或数组: 增加嵌套深度, 并将其计为一个节点.
+    fn enter_container(&self, ctx: &mut ParseContext) -> Result<(), String> {
+        ctx.depth += 1;
+        if ctx.depth > self.settings.depth_limit {
+            return Err(format!(
+                "The set depth limit is exceeded: {}",
+                self.settings.depth_limit
+            ));
+        }
 
-            tokenizer.push_back(token)?;
-            let val = self.parse_value_type(obj, name, tokenizer)?;
-            vec.push(val);
+        self.count_node(ctx)
+    }
+
+    fn count_node(&self, ctx: &mut ParseContext) -> Result<(), String> {
+        ctx.nodes += 1;
+        if ctx.nodes > self.settings.max_nodes {
+            Err(format!(
+                "The set node limit is exceeded: {}",
+                self.settings.max_nodes
+            ))
+        } else {
+            Ok(())
         }
     }
 
-    fn parse_single_value(&self, token: &JsonToken) -> Result<Value, String> {
+    fn parse_single_value<'a, T: Tree<'a>>(
+        &self,
+        token: JsonToken<'a>,
+    ) -> Result<T::Value, String> {
         match token {
-            JsonToken::Null => Ok(Value::Null),
-            JsonToken::False => Ok(Value::Bool(false)),
-            JsonToken::True => Ok(Value::Bool(true)),
-            JsonToken::StringValue(s) => Ok(Value::String(s.to_string())),
-            JsonToken::Number(num) => self.parse_number(num),
+            JsonToken::Null => Ok(T::null()),
+            JsonToken::False => Ok(T::bool(false)),
+            JsonToken::True => Ok(T::bool(true)),
+            JsonToken::StringValue(s) => Ok(T::string(s)),
+            JsonToken::Number(num) => self.parse_number::<T>(num),
             _ => Err(format!(
                 "An error Token occurred while parsing single value: {:?}",
                 token
@@ -110,19 +384,19 @@ impl JsonReader {
         }
     }
 
-    fn parse_number(&self, s: &str) -> Result<Value, String> {
-        let n = s.parse::<i64>();
-        if n.is_ok() {
-            return Ok(Value::I64(n.unwrap()));
+    // 数字文本直接来自输入, 已经由标记器校验过语法. 不含小数点和指数的整数优先解析为`I64`,
+    // 超出`i64`范围时与其它数字一样解析为`F64`.
+    fn parse_number<'a, T: Tree<'a>>(&self, s: &str) -> Result<T::Value, String> {
+        let is_integer = !s.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'));
+        if is_integer && let Ok(n) = s.parse::<i64>() {
+            return Ok(T::i64(n));
         }
 
-        let n = s.parse::<f64>();
-        if n.is_ok() {
-            let val = n.unwrap();
+        if let Ok(val) = s.parse::<f64>() {
             return if val.is_nan() || val.is_infinite() {
                 Err("Reject special value".to_string())
             } else {
-                Ok(Value::F64(val))
+                Ok(T::f64(val))
             };
         }
 
@@ -130,22 +404,41 @@ impl JsonReader {
     }
 }
 
+/// 解析Json时使用的设置.
+///
+/// 除`recursion_limit`外, 其余各项限制用于安全地解析不可信的输入, 超出任意一项都会返回各自不同的错误.
 pub struct JsonReaderSettings {
-    pub recursion_limit: usize, // 要分析的消息的最大深度.
+    pub recursion_limit: usize,    // 要分析的消息的最大深度(仅计算对象).
+    pub depth_limit: usize,        // 对象与数组的最大总嵌套深度.
+    pub max_input_length: usize,   // 输入Json的最大字节数.
+    pub max_string_length: usize,  // 单个字符串(包括名称)的最大字节数.
+    pub max_array_length: usize,   // 单个数组的最大元素个数.
+    pub max_object_members: usize, // 单个对象的最大成员个数.
+    pub max_nodes: usize,          // 整个文档的最大节点(值)总数, 对象与数组也各计为一个节点.
+    pub max_number_length: usize,  // 单个数字字面量的最大字符数.
 }
 
 impl Default for JsonReaderSettings {
     fn default() -> Self {
         JsonReaderSettings {
             recursion_limit: 100,
+            depth_limit: 128,
+            max_input_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_members: usize::MAX,
+            max_nodes: usize::MAX,
+            max_number_length: usize::MAX,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::approx_constant, clippy::bool_assert_comparison)]
 mod test {
     use crate::json_reader::JsonReader;
-    use crate::{JsonReaderSettings, Map};
+    use crate::{JsonReaderSettings, Map, ValueRef};
+    use std::borrow::Cow;
 
     #[test]
     fn all_types_round_trip() {
@@ -275,6 +568,162 @@ mod test {
         assert_eq!(map.get("a2").unwrap().as_i64().unwrap(), 789i64);
     }
 
+    #[test]
+    fn depth_limit_counts_arrays() {
+        let settings = JsonReaderSettings {
+            depth_limit: 3,
+            ..Default::default()
+        };
+        assert!(parse_with_settings(r#"{"a":[[1]]}"#, settings).is_ok());
+
+        let settings = JsonReaderSettings {
+            depth_limit: 3,
+            ..Default::default()
+        };
+        let result = parse_with_settings(r#"{"a":[[[1]]]}"#, settings);
+        assert_eq!(result.unwrap_err(), "The set depth limit is exceeded: 3");
+    }
+
+    #[test]
+    fn deeply_nested_arrays_rejected_by_default() {
+        let json = format!(
+            "{{\"a\":{}{}}}",
+            "[".repeat(1_000_000),
+            "]".repeat(1_000_000)
+        );
+        let result = parse_with_settings(json.as_str(), JsonReaderSettings::default());
+        assert_eq!(result.unwrap_err(), "The set depth limit is exceeded: 128");
+    }
+
+    #[test]
+    fn resource_limits() {
+        let cases: [(&str, JsonReaderSettings, &str); 7] = [
+            (
+                r#"{"a":1}"#,
+                JsonReaderSettings {
+                    max_input_length: 6,
+                    ..Default::default()
+                },
+                "The set input length limit is exceeded: 6",
+            ),
+            (
+                r#"{"a":"hello"}"#,
+                JsonReaderSettings {
+                    max_string_length: 4,
+                    ..Default::default()
+                },
+                "The set string length limit is exceeded: 4 at 1:6",
+            ),
+            (
+                r#"{"hello":1}"#,
+                JsonReaderSettings {
+                    max_string_length: 4,
+                    ..Default::default()
+                },
+                "The set string length limit is exceeded: 4 at 1:2",
+            ),
+            // 按解码后的长度计算
+            (
+                r#"{"a":"\\\\\\\\\\"}"#,
+                JsonReaderSettings {
+                    max_string_length: 4,
+                    ..Default::default()
+                },
+                "The set string length limit is exceeded: 4 at 1:6",
+            ),
+            (
+                r#"{"a":[1,2,3]}"#,
+                JsonReaderSettings {
+                    max_array_length: 2,
+                    ..Default::default()
+                },
+                "The set array length limit is exceeded: 2",
+            ),
+            (
+                r#"{"a":1,"b":2,"c":3}"#,
+                JsonReaderSettings {
+                    max_object_members: 2,
+                    ..Default::default()
+                },
+                "The set object member limit is exceeded: 2",
+            ),
+            (
+                r#"{"a":[1,2],"b":{"c":3}}"#,
+                JsonReaderSettings {
+                    max_nodes: 5,
+                    ..Default::default()
+                },
+                "The set node limit is exceeded: 5",
+            ),
+        ];
+
+        for (json, settings, expected) in cases {
+            assert_eq!(parse_with_settings(json, settings).unwrap_err(), expected);
+        }
+
+        let settings = JsonReaderSettings {
+            max_number_length: 5,
+            ..Default::default()
+        };
+        let result = parse_with_settings(r#"{"a":1.23456}"#, settings);
+        assert_eq!(
+            result.unwrap_err(),
+            "The set number length limit is exceeded: 5 at 1:6"
+        );
+    }
+
+    #[test]
+    fn resource_limits_at_boundary() {
+        let settings = JsonReaderSettings {
+            max_input_length: 23,
+            max_string_length: 1,
+            max_array_length: 2,
+            max_object_members: 2,
+            max_nodes: 6,
+            max_number_length: 1,
+            ..Default::default()
+        };
+        assert!(parse_with_settings(r#"{"a":[1,2],"b":{"c":3}}"#, settings).is_ok());
+
+        let settings = JsonReaderSettings {
+            max_string_length: 4,
+            ..Default::default()
+        };
+        assert!(parse_with_settings(r#"{"a":"\\\\\\\\"}"#, settings).is_ok());
+    }
+
+    #[test]
+    fn borrowed_parse() {
+        let json = r#"{"a":"hello","b\n":"tab\t","c":["x",{"d":"y"}]}"#;
+        let map = JsonReader::new(JsonReaderSettings::default())
+            .parse_borrowed(json)
+            .unwrap();
+
+        let (key, val) = map.get_key_value("a").unwrap();
+        assert!(matches!(key, Cow::Borrowed(_)));
+        assert!(matches!(val, ValueRef::String(Cow::Borrowed("hello"))));
+
+        let (key, val) = map.get_key_value("b\n").unwrap();
+        assert!(matches!(key, Cow::Owned(_)));
+        assert!(matches!(val, ValueRef::String(Cow::Owned(s)) if s == "tab\t"));
+
+        let vec = map.get("c").unwrap().as_vec().unwrap();
+        assert!(matches!(vec[0], ValueRef::String(Cow::Borrowed("x"))));
+        let obj = vec[1].as_object().unwrap();
+        assert!(matches!(obj["d"], ValueRef::String(Cow::Borrowed("y"))));
+    }
+
+    #[test]
+    fn borrowed_into_owned() {
+        let json =
+            r#"{"a":null,"b":false,"c":618,"d":"he\"llo","e":[3.14,[6.18]],"f":{"a1":11,"b1":{}}}"#;
+        let map = JsonReader::new(JsonReaderSettings::default())
+            .parse_borrowed(json)
+            .unwrap();
+
+        assert!(map.into_owned() == parse_to_map(json));
+    }
+
     fn assert_string_to_f64_valid(left: &str, right: f64) {
         let json = format!("{{\"key_f64\":{}}}", left);
         let map = parse_to_map(json.as_str());
@@ -300,11 +749,15 @@ mod test {
         let json = format!("{{\"key_number\":{}}}", s);
         let result = parse_to_map_err(json.as_str());
 
-        assert!(matches!(result, Err(_)));
+        assert!(result.is_err());
     }
 
     fn parse_to_map_err(json: &str) -> Result<(), String> {
+        parse_with_settings(json, JsonReaderSettings::default())
+    }
+
+    fn parse_with_settings(json: &str, settings: JsonReaderSettings) -> Result<(), String> {
         let mut map = Map::new();
-        JsonReader::new(JsonReaderSettings::default()).parse(&mut map, json)
+        JsonReader::new(settings).parse(&mut map, json)
     }
 }
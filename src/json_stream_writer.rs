@@ -0,0 +1,719 @@
+use crate::json_token::JsonToken;
+use crate::json_tokenizer::{JsonTokenizer, TokenizerState};
+use crate::json_writer::{InlinePart, JsonWriter};
+use crate::redact::{self, RedactionAction};
+use crate::{JsonWriterSettings, PathSegment};
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read, Write};
+use std::ops::Range;
+use std::str;
+
+/// 按照`JsonWriterSettings`的排版规则, 将Json标记流直接写入输出, 而不构建`Map`.
+///
+/// 成员的顺序, 重复的名称和数字的原始文本都原样保留; 字符串按设置重新转义.
+/// 输出先写入一个小缓冲区, 不需要改写在同一行的部分会及时写入`output`, 因此内存占用与文档大小无关.
+pub struct JsonStreamWriter<'s, W: Write> {
+    writer: JsonWriter<'s>,
+    settings: &'s JsonWriterSettings,
+    output: W,
+    buf: String,
+    flushed_column: usize, // 已经写入`output`的部分的最后一行的宽度(字符数).
+    stack: Vec<StreamFrame>,
+    pending_key: Option<Range<usize>>, // 已经写入但还没有写入值的成员名称在`buf`中的范围.
+    pending_name: Option<String>,      // 设置了脱敏规则时, 还没有写入值的成员名称.
+    path: Vec<PathSegment<'static>>,   // 设置了脱敏规则时, 从根到当前值的路径.
+    redacted: Option<Redacted<'s>>,    // 正在跳过的被脱敏的对象或数组.
+}
+
+// 尚未闭合的对象或数组.
+struct StreamFrame {
+    is_object: bool,
+    first: bool,
+    index: usize, // 下一个数组元素的下标.
+    indentation_level: usize,
+    open_pos: usize,    // 开括号在`buf`中的位置, 只在`inline`不为None时有效.
+    open_column: usize, // 开括号所在的列.
+    inline: Option<InlineState>,
+}
+
+// 被脱敏的对象或数组. 其中的标记不写入输出, 闭合之后写入替换的字符串.
+struct Redacted<'s> {
+    action: &'s RedactionAction,
+    depth: usize,
+    text: Option<String>, // 需要时保存的紧凑格式的Json文本, 闭合后解析并用于哈希和预览.
+}
+
+// 容器还可能写在同一行时, 已写入的成员和写在同一行时的宽度.
+struct InlineState {
+    parts: Vec<InlinePart>,
+    width: usize,
+}
+
+impl<'s, W: Write> JsonStreamWriter<'s, W> {
+    const FLUSH_THRESHOLD: usize = 8 * 1024;
+
+    pub fn new(settings: &'s JsonWriterSettings, output: W) -> Self {
+        JsonStreamWriter {
+            writer: JsonWriter::new(settings),
+            settings,
+            output,
+            buf: String::new(),
+            flushed_column: 0,
+            stack: Vec::new(),
+            pending_key: None,
+            pending_name: None,
+            path: Vec::new(),
+            redacted: None,
+        }
+    }
+
+    // 写入一个标记. 标记流应该是`JsonTokenizer`产生的, 即已经验证过结构.
+    pub fn write_token(&mut self, token: JsonToken) -> Result<(), String> {
+        if self.redacted.is_some() {
+            return self.skip_token(token);
+        }
+
+        match token {
+            JsonToken::Name(name) => {
+                let frame = self
+                    .stack
+                    .last_mut()
+                    .ok_or("Unexpected name outside object")?;
+                let (key, _) = self.writer.write_member_prefix(
+                    &mut self.buf,
+                    &name,
+                    frame.first,
+                    frame.indentation_level + 1,
+                    0,
+                );
+                frame.first = false;
+                self.pending_key = Some(key);
+                if !self.settings.redactions.is_empty() {
+                    self.pending_name = Some(name.into_owned());
+                }
+            }
+            JsonToken::Null => self.write_scalar("null", false)?,
+            JsonToken::True => self.write_scalar("true", false)?,
+            JsonToken::False => self.write_scalar("false", false)?,
+            JsonToken::Number(text) => self.write_scalar(text, false)?,
+            JsonToken::StringValue(text) => self.write_scalar(&text, true)?,
+            JsonToken::StartObject => self.write_open(true),
+            JsonToken::StartArray => self.write_open(false),
+            JsonToken::EndObject | JsonToken::EndArray => self.write_close(),
+            JsonToken::EndDocument => {
+                if self.settings.trailing_newline {
+                    self.writer.write_line(&mut self.buf);
+                }
+                return self.flush();
+            }
+        }
+
+        if self.buf.len() >= Self::FLUSH_THRESHOLD {
+            self.maybe_flush()?;
+        }
+        Ok(())
+    }
+
+    // 写入值之前的部分, 并返回适用于这个值的脱敏规则. 对象成员的名称在`Name`标记中已经写入,
+    // 这里只处理数组元素. 设置了脱敏规则时, 将值的路径压入`path`, 值写完之后弹出.
+    fn write_value_prefix(&mut self) -> Option<&'s RedactionAction> {
+        let frame = self.stack.last_mut()?;
+        if !frame.is_object {
+            self.writer.write_element_prefix(
+                &mut self.buf,
+                frame.first,
+                frame.indentation_level + 1,
+            );
+            frame.first = false;
+        }
+        if self.settings.redactions.is_empty() {
+            return None;
+        }
+
+        let segment = match self.pending_name.take() {
+            Some(name) => PathSegment::Key(Cow::Owned(name)),
+            None => {
+                frame.index += 1;
+                PathSegment::Index(frame.index - 1)
+            }
+        };
+        self.path.push(segment);
+        redact::find(&self.settings.redactions, &self.path)
+    }
+
+    // 值写完之后, 弹出它的路径.
+    fn end_value(&mut self) {
+        if !self.settings.redactions.is_empty() && !self.stack.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    fn write_scalar(&mut self, text: &str, quoted: bool) -> Result<(), String> {
+        match self.write_value_prefix() {
+            // 数字按解析后的值计算替换的字符串, 与写入`Map`时相同.
+            Some(action) if action.needs_text() && !quoted => {
+                self.write_scalar_text(&action.apply(&redact::json_text(text)?), true)
+            }
+            Some(action) => self.write_scalar_text(&action.apply(text), true),
+            None => self.write_scalar_text(text, quoted),
+        }
+        self.end_value();
+        Ok(())
+    }
+
+    // 写入一个标量. `quoted`为true时写为字符串, 否则原样写入.
+    fn write_scalar_text(&mut self, text: &str, quoted: bool) {
+        let start = self.buf.len();
+        match quoted {
+            true => {
+                self.writer.write_string(&mut self.buf, text);
+            }
+            false => self.buf.push_str(text),
+        }
+        let key = self.pending_key.take();
+
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+        let Some(ref mut inline) = frame.inline else {
+            return;
+        };
+        if !inline.parts.is_empty() {
+            inline.width += 2; // ", "
+        }
+        if let Some(ref key) = key {
+            inline.width += self.buf[key.clone()].chars().count() + 2; // ": "
+        }
+        inline.width += self.buf[start..].chars().count();
+        inline.parts.push((key, start..self.buf.len()));
+
+        let max_line_width = self.settings.max_line_width.unwrap_or(0);
+        if frame.open_column + inline.width > max_line_width {
+            frame.inline = None;
+        }
+    }
+
+    fn write_open(&mut self, is_object: bool) {
+        let open_char = if is_object {
+            JsonWriter::STRUCT_OPEN_BRACKET
+        } else {
+            JsonWriter::ARRAY_BRACKET_OPEN
+        };
+        if let Some(action) = self.write_value_prefix() {
+            self.redacted = Some(Redacted {
+                action,
+                depth: 1,
+                text: action.needs_text().then(|| open_char.to_string()),
+            });
+            return;
+        }
+        self.pending_key = None;
+        let indentation_level = match self.stack.last_mut() {
+            Some(parent) => {
+                parent.inline = None;
+                parent.indentation_level + 1
+            }
+            None => 0,
+        };
+
+        let open_pos = self.buf.len();
+        let open_column = self.column();
+        self.writer.write_bracket_open(&mut self.buf, open_char);
+
+        let inline =
+            self.settings.max_line_width.is_some() && !self.settings.indentation.is_empty();
+        self.stack.push(StreamFrame {
+            is_object,
+            first: true,
+            index: 0,
+            indentation_level,
+            open_pos,
+            open_column,
+            inline: inline.then(|| InlineState {
+                parts: Vec::new(),
+                width: 2, // 开括号和闭括号
+            }),
+        });
+    }
+
+    fn write_close(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let close_char = if frame.is_object {
+            JsonWriter::STRUCT_CLOSE_BRACKET
+        } else {
+            JsonWriter::ARRAY_BRACKET_CLOSE
+        };
+        self.writer.write_bracket_close(
+            &mut self.buf,
+            close_char,
+            !frame.first,
+            frame.indentation_level,
+        );
+
+        if let Some(inline) = frame.inline {
+            self.writer.collapse_inline(
+                &mut self.buf,
+                frame.open_pos,
+                frame.open_column,
+                &inline.parts,
+                close_char,
+            );
+        }
+        self.end_value();
+    }
+
+    // 跳过被脱敏的对象或数组中的一个标记. 它闭合时写入替换的字符串.
+    fn skip_token(&mut self, token: JsonToken) -> Result<(), String> {
+        let Some(ref mut redacted) = self.redacted else {
+            return Ok(());
+        };
+
+        if let Some(ref mut text) = redacted.text {
+            // 紧凑格式中, 除了开括号和`:`之后, 名称和值之前都需要`,`.
+            let separate = !matches!(text.chars().last(), Some('{' | '[' | ':'));
+            match token {
+                JsonToken::EndObject | JsonToken::EndArray | JsonToken::EndDocument => {}
+                _ if separate => text.push(','),
+                _ => {}
+            }
+            match token {
+                JsonToken::Name(ref name) => {
+                    self.writer.write_string(text, name);
+                    text.push(':');
+                }
+                JsonToken::StringValue(ref value) => {
+                    self.writer.write_string(text, value);
+                }
+                JsonToken::Null => text.push_str("null"),
+                JsonToken::True => text.push_str("true"),
+                JsonToken::False => text.push_str("false"),
+                JsonToken::Number(number) => text.push_str(number),
+                JsonToken::StartObject => text.push('{'),
+                JsonToken::StartArray => text.push('['),
+                JsonToken::EndObject => text.push('}'),
+                JsonToken::EndArray => text.push(']'),
+                JsonToken::EndDocument => {}
+            }
+        }
+
+        match token {
+            JsonToken::StartObject | JsonToken::StartArray => redacted.depth += 1,
+            JsonToken::EndObject | JsonToken::EndArray => redacted.depth -= 1,
+            _ => {}
+        }
+        if redacted.depth > 0 {
+            return Ok(());
+        }
+
+        // 解析保存的文本再写出, 使成员顺序, 重复的名称和数字的写法与写入`Map`时相同.
+        let text = match redacted.text {
+            Some(ref text) => redact::json_text(text)?,
+            None => String::new(),
+        };
+        let replacement = redacted.action.apply(&text);
+        self.redacted = None;
+        self.write_scalar_text(&replacement, true);
+        self.end_value();
+        Ok(())
+    }
+
+    // 返回`buf`末尾所在的列.
+    fn column(&self) -> usize {
+        match self.buf.rfind('\n') {
+            Some(i) => self.buf[i + 1..].chars().count(),
+            None => self.flushed_column + self.buf.chars().count(),
+        }
+    }
+
+    // 最内层的容器还可能改写在同一行时, 需要保留它的内容, 否则将缓冲区写入输出.
+    fn maybe_flush(&mut self) -> Result<(), String> {
+        match self.stack.last() {
+            Some(frame) if frame.inline.is_some() => Ok(()),
+            _ => self.flush(),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.flushed_column = self.column();
+        self.output
+            .write_all(self.buf.as_bytes())
+            .map_err(|e| e.to_string())?;
+        self.buf.clear();
+        self.output.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// 按照`settings`重新排版Json文本, 不构建`Map`.
+///
+/// 与解析成`Map`再写出不同, 成员的顺序, 重复的名称和数字的原始文本都原样保留, 文档的最外层也可以是任意值.
+/// 字符串按`settings`中的转义策略重新转义, 脱敏规则`redactions`生效. 需要预先知道整个对象的设置不能流式处理,
+/// 设置了`sort_keys`, `align_values`, `skip_nulls`, `skip_empty_containers`, `prune_empty_containers`或`filter`时返回错误.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{reformat, JsonWriterSettings};
+///
+/// let json = r#"{ "b": 1.50, "a": [1e3], "b": null }"#;
+/// let json = reformat(json, &JsonWriterSettings::default()).unwrap();
+/// assert_eq!(json, r#"{"b":1.50,"a":[1e3],"b":null}"#);
+/// ```
+pub fn reformat(input: &str, settings: &JsonWriterSettings) -> Result<String, String> {
+    check_settings(settings)?;
+    let mut output = Vec::with_capacity(input.len());
+    let mut writer = JsonStreamWriter::new(settings, &mut output);
+    let mut tokenizer = JsonTokenizer::new(input);
+    loop {
+        let token = tokenizer.next()?;
+        let end = token == JsonToken::EndDocument;
+        writer.write_token(token)?;
+        if end {
+            break;
+        }
+    }
+
+    String::from_utf8(output).map_err(|e| e.to_string())
+}
+
+/// 与`reformat`相同, 但是分段读取`input`并写入`output`.
+///
+/// 内存占用只与最长的单个标记(例如一个很长的字符串)有关, 与文档大小无关, 适合处理很大的文件.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{reformat_stream, JsonWriterSettings};
+///
+/// let settings = JsonWriterSettings {
+///     indentation: "  ".to_string(),
+///     ..Default::default()
+/// };
+/// let mut output = Vec::new();
+/// reformat_stream(r#"[1,{"a":"b"}]"#.as_bytes(), &mut output, &settings).unwrap();
+/// assert_eq!(output, b"[\n  1,\n  {\n    \"a\": \"b\"\n  }\n]");
+/// ```
+pub fn reformat_stream<R: Read, W: Write>(
+    mut input: R,
+    output: W,
+    settings: &JsonWriterSettings,
+) -> Result<(), String> {
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    check_settings(settings)?;
+    let mut writer = JsonStreamWriter::new(settings, output);
+    let mut state = TokenizerState::default();
+    let mut buf = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let read = loop {
+            match input.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        };
+        buf.extend_from_slice(&chunk[..read]);
+        let eof = read == 0;
+
+        // 分段处可能截断一个多字节字符, 只读取完整的部分.
+        let text = match str::from_utf8(&buf) {
+            Ok(text) => text,
+            Err(e) if !eof && e.error_len().is_none() => {
+                str::from_utf8(&buf[..e.valid_up_to()]).map_err(|e| e.to_string())?
+            }
+            Err(e) => return Err(format!("Invalid UTF-8 in input: {}", e)),
+        };
+
+        let mut tokenizer = JsonTokenizer::resume(text, state);
+        loop {
+            let token = if eof {
+                tokenizer.next()?
+            } else {
+                match tokenizer.next_partial()? {
+                    Some(token) => token,
+                    None => break,
+                }
+            };
+
+            let end = token == JsonToken::EndDocument;
+            writer.write_token(token)?;
+            if end {
+                return Ok(());
+            }
+        }
+
+        let consumed = tokenizer.position();
+        state = tokenizer.into_state();
+        buf.drain(..consumed);
+    }
+}
+
+// 检查`settings`中是否有不写出整个对象就无法生效的设置.
+fn check_settings(settings: &JsonWriterSettings) -> Result<(), String> {
+    let unsupported = [
+        ("sort_keys", settings.sort_keys),
+        ("align_values", settings.align_values),
+        ("skip_nulls", settings.skip_nulls),
+        ("skip_empty_containers", settings.skip_empty_containers),
+        ("prune_empty_containers", settings.prune_empty_containers),
+        ("filter", settings.filter.is_some()),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((name, _)) => Err(format!(
+            "`{}` is not supported when reformatting without a Map",
+            name
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json_stream_writer::{reformat, reformat_stream};
+    use crate::{JsonWriterSettings, Map, Newline, Redaction, RedactionAction, RedactionTarget};
+    use std::io::Read;
+
+    #[test]
+    fn preserves_order_duplicates_and_numbers() {
+        let json = r#" { "z" : 1.0 , "a" : [ -0 , 1E+2 , 12345678901234567890 ] , "z" : "é" } "#;
+        let settings = JsonWriterSettings::default();
+        assert_eq!(
+            reformat(json, &settings).unwrap(),
+            r#"{"z":1.0,"a":[-0,1E+2,12345678901234567890],"z":"é"}"#
+        );
+    }
+
+    #[test]
+    fn pretty_layout_matches_writer() {
+        let json = r#"{"a":[1,2,{"b":null}],"c":{},"d":[],"e":"x\/y"}"#;
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            newline: Newline::CrLf,
+            trailing_newline: true,
+            ..Default::default()
+        };
+        let expected = "{\r\n  \"a\": [\r\n    1,\r\n    2,\r\n    {\r\n      \"b\": null\r\n    }\r\n  ],\r\n  \"c\": {},\r\n  \"d\": [],\r\n  \"e\": \"x/y\"\r\n}\r\n";
+        assert_eq!(reformat(json, &settings).unwrap(), expected);
+
+        // 单个成员的对象与`Map`写出的结果相同
+        let mut map = Map::new();
+        map.merge(r#"{"a":[1,[true,"s"]]}"#).unwrap();
+        let settings = JsonWriterSettings {
+            indentation: "\t".to_string(),
+            ..Default::default()
+        };
+        let formatted = reformat(r#"{"a":[1,[true,"s"]]}"#, &settings).unwrap();
+        assert_eq!(formatted, map.to_json_with_settings(settings));
+    }
+
+    #[test]
+    fn max_line_width() {
+        let json =
+            r#"{"k":[[1,2,3],{"a":1},[],["a long string","a long string","a long string"]]}"#;
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(30),
+            ..Default::default()
+        };
+        let expected = r#"{
+  "k": [
+    [1, 2, 3],
+    {"a": 1},
+    [],
+    [
+      "a long string",
+      "a long string",
+      "a long string"
+    ]
+  ]
+}"#;
+        assert_eq!(reformat(json, &settings).unwrap(), expected);
+    }
+
+    #[test]
+    fn redactions_match_writer() {
+        let json =
+            r#"{"a":{"password":"p","x":[true,null,{"k":"\/"}]},"b":[{"secret":1},"s"],"c":2.5}"#;
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            redactions: vec![
+                Redaction {
+                    target: RedactionTarget::Key("password".to_string()),
+                    action: RedactionAction::Mask("***".to_string()),
+                },
+                Redaction {
+                    target: RedactionTarget::Path("/a/x".to_string()),
+                    action: RedactionAction::Hash,
+                },
+                Redaction {
+                    target: RedactionTarget::Path("/b/0".to_string()),
+                    action: RedactionAction::Preview(5),
+                },
+                Redaction {
+                    target: RedactionTarget::Path("/c".to_string()),
+                    action: RedactionAction::Preview(10),
+                },
+            ],
+            ..Default::default()
+        };
+        let formatted = reformat(json, &settings).unwrap();
+        assert!(formatted.contains(r#""password": "***""#));
+        assert!(formatted.contains(r#""c": "2.5""#));
+        assert!(formatted.contains(r#""{\"sec…""#));
+
+        // 输入的成员已经按名称排序, 与`Map`按名称排序写出的结果相同
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        let settings = JsonWriterSettings {
+            sort_keys: true,
+            ..settings
+        };
+        assert_eq!(formatted, map.to_json_with_settings(settings));
+    }
+
+    #[test]
+    fn hashes_match_writer() {
+        // 成员顺序, 数字的写法和转义策略都不影响哈希值
+        let settings = JsonWriterSettings {
+            escape_solidus: true,
+            redactions: vec![Redaction {
+                target: RedactionTarget::Key("token".to_string()),
+                action: RedactionAction::Hash,
+            }],
+            ..Default::default()
+        };
+        let json = r#"{"token":{"z":1,"a":[2.50,"/"],"z":1.0}}"#;
+        let formatted = reformat(json, &settings).unwrap();
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        assert_eq!(formatted, map.to_json_with_settings(settings));
+
+        let settings = JsonWriterSettings {
+            redactions: vec![Redaction {
+                target: RedactionTarget::Key("token".to_string()),
+                action: RedactionAction::Hash,
+            }],
+            ..Default::default()
+        };
+        let json = r#"{"token":{"z":1,"a":2}}"#;
+        let formatted = reformat(json, &settings).unwrap();
+        assert_eq!(
+            formatted,
+            reformat(r#"{"token":{"a":2,"z":1}}"#, &settings).unwrap()
+        );
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        assert_eq!(formatted, map.to_json_with_settings(settings));
+
+        // 标量数字也按解析后的值计算
+        let settings = JsonWriterSettings {
+            redactions: vec![Redaction {
+                target: RedactionTarget::Key("n".to_string()),
+                action: RedactionAction::Preview(10),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat(r#"{"n":1.50}"#, &settings).unwrap(),
+            r#"{"n":"1.5"}"#
+        );
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let settings = JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat(r#"{"b":1,"a":2}"#, &settings),
+            Err("`sort_keys` is not supported when reformatting without a Map".to_string())
+        );
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            align_values: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat("{}", &settings),
+            Err("`align_values` is not supported when reformatting without a Map".to_string())
+        );
+
+        let settings = JsonWriterSettings {
+            skip_nulls: true,
+            ..Default::default()
+        };
+        assert!(reformat("{}", &settings).is_err());
+        let settings = JsonWriterSettings {
+            skip_empty_containers: true,
+            ..Default::default()
+        };
+        assert!(reformat("{}", &settings).is_err());
+        let settings = JsonWriterSettings {
+            prune_empty_containers: true,
+            ..Default::default()
+        };
+        assert!(reformat("{}", &settings).is_err());
+
+        let settings = JsonWriterSettings {
+            filter: Some(Box::new(|_, _| true)),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        assert_eq!(
+            reformat_stream("{}".as_bytes(), &mut output, &settings),
+            Err("`filter` is not supported when reformatting without a Map".to_string())
+        );
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn top_level_values() {
+        let settings = JsonWriterSettings::default();
+        assert_eq!(reformat(" 1.50 ", &settings).unwrap(), "1.50");
+        assert_eq!(reformat("[ ]", &settings).unwrap(), "[]");
+        assert!(reformat("{} x", &settings).is_err());
+        assert!(reformat("[1,]", &settings).is_err());
+    }
+
+    #[test]
+    fn stream_in_small_chunks() {
+        // 每次只读取一个字节, 使每个标记(包括多字节字符和数字)都被截断
+        struct OneByte<'a>(&'a [u8]);
+        impl Read for OneByte<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let Some((&first, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+                buf[0] = first;
+                self.0 = rest;
+                Ok(1)
+            }
+        }
+
+        let json = r#"{"名字":"值😀","n":-12.5e-3,"l":[true,false,null],"o":{"x":123}}"#;
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(40),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        reformat_stream(OneByte(json.as_bytes()), &mut output, &settings).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            reformat(json, &settings).unwrap()
+        );
+
+        let mut output = Vec::new();
+        let err = reformat_stream(OneByte(br#"{"a":tru"#), &mut output, &settings).unwrap_err();
+        assert_eq!(
+            err,
+            "Unexpected end of text while reading literal token true at 1:7"
+        );
+        assert!(reformat_stream(OneByte(b"[1, 2"), &mut output, &settings).is_err());
+        assert!(reformat_stream(OneByte(b"[\"\xff\"]"), &mut output, &settings).is_err());
+    }
+}
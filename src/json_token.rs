@@ -1,14 +1,16 @@
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::Hash;
 
+/// Json标记. 没有转义字符的字符串和名称直接借用输入文本.
 #[derive(Debug, PartialEq, Hash)]
-pub enum JsonToken {
+pub enum JsonToken<'a> {
     Null,
     False,
     True,
-    StringValue(String),
-    Number(String),
-    Name(String),
+    StringValue(Cow<'a, str>),
+    Number(&'a str),
+    Name(Cow<'a, str>),
     StartObject,
     EndObject,
     StartArray,
@@ -1,5 +1,5 @@
 use crate::json_token::JsonToken;
-use std::str::Chars;
+use std::borrow::Cow;
 
 /// 简单但严格的JSON标记器, 严格遵循RFC 7159.
 ///
@@ -10,7 +10,7 @@ use std::str::Chars;
 ///
 /// 实现细节: 基类处理单个令牌推回, 但不是线程安全的.
 pub struct JsonTokenizer<'a> {
-    buffered_token: Vec<JsonToken>,
+    buffered_token: Vec<JsonToken<'a>>,
 
     // 返回堆栈深度，纯对象(不是集合).
     // 非正式地, 这是我们拥有的剩余未关闭的"{"字符的数量.
@@ -27,7 +27,68 @@ impl<'a> JsonTokenizer<'a> {
         }
     }
 
-    pub fn push_back(&mut self, token: JsonToken) -> Result<(), String> {
+    // 限制单个字符串(解码后的字节数, 包括名称)和数字字面量的最大长度. 在扫描时检查,
+    // 超过限制时立即返回错误, 不会先读完整个标记或为其分配内存.
+    pub fn with_limits(input: &'a str, max_string_length: usize, max_number_length: usize) -> Self {
+        let mut tokenizer = JsonTokenizer::new(input);
+        tokenizer.proxy.max_string_length = max_string_length;
+        tokenizer.proxy.max_number_length = max_number_length;
+        tokenizer
+    }
+
+    // 从保存的状态继续读取新的一段输入, 用于分段读取流式输入. `input`从上一段输入中未读取的位置开始.
+    pub fn resume(input: &'a str, state: TokenizerState) -> Self {
+        let mut proxy = JsonTextTokenizer::new(input);
+        proxy.container_stack = state.container_stack;
+        proxy.state = state.state;
+        proxy.line = state.line;
+        proxy.column = state.column;
+        JsonTokenizer {
+            buffered_token: Vec::with_capacity(1),
+            object_depth: state.object_depth,
+            proxy,
+        }
+    }
+
+    // 保存读取下一段输入需要的状态. 不能有推回的标记.
+    pub fn into_state(self) -> TokenizerState {
+        let (line, column) = self.proxy.location(self.proxy.pos);
+        TokenizerState {
+            container_stack: self.proxy.container_stack,
+            state: self.proxy.state,
+            object_depth: self.object_depth,
+            line,
+            column,
+        }
+    }
+
+    // 返回已经读取的完整标记在输入中占用的字节数.
+    pub fn position(&self) -> usize {
+        self.proxy.pos
+    }
+
+    // 读取后面可能还有更多输入的下一个标记. 如果输入在一个标记中间结束, 或者在数字之后结束(数字可能还没读完),
+    // 回退到该标记之前并返回None, 调用者应追加输入后从`position()`处继续. 输入全部读完后应改用`next()`,
+    // 此时被截断的标记才会报错.
+    pub fn next_partial(&mut self) -> Result<Option<JsonToken<'a>>, String> {
+        let (pos, state) = (self.proxy.pos, self.proxy.state);
+        let at_end = |proxy: &JsonTextTokenizer| proxy.pos >= proxy.input.len();
+
+        match self.next() {
+            Ok(JsonToken::EndDocument) => {}
+            Ok(JsonToken::Number(_)) if at_end(&self.proxy) => {}
+            Ok(token) => return Ok(Some(token)),
+            Err(e) if at_end(&self.proxy) || e.starts_with("Unexpected end of") => {}
+            Err(e) => return Err(e),
+        }
+
+        self.proxy.pos = pos;
+        self.proxy.state = state;
+        Ok(None)
+    }
+
+    #[allow(dead_code)]
+    pub fn push_back(&mut self, token: JsonToken<'a>) -> Result<(), String> {
         if !self.buffered_token.is_empty() {
             return Err(String::from("Can't push back twice"));
         }
@@ -47,13 +108,15 @@ impl<'a> JsonTokenizer<'a> {
     //
     // 此实现提供单令牌缓冲, 如果没有缓冲令牌, 则调用next_impl().
     // 流中的下一个标记. 它永远不会为空.
-    pub fn next(&mut self) -> Result<JsonToken, String> {
-        let token_to_return: JsonToken;
-        if !self.buffered_token.is_empty() {
-            token_to_return = self.buffered_token.pop().unwrap();
-        } else {
-            token_to_return = self.proxy.next_impl()?;
-        }
+    // 出错时, 错误信息以出错位置的`行:列`结尾, 行和列都从1开始, 列按字符计算.
+    pub fn next(&mut self) -> Result<JsonToken<'a>, String> {
+        let token_to_return = match self.buffered_token.pop() {
+            Some(token) => token,
+            None => self.proxy.next_impl().map_err(|e| {
+                let (line, column) = self.proxy.location(self.proxy.pos);
+                format!("{} at {}:{}", e, line, column + 1)
+            })?,
+        };
 
         if token_to_return == JsonToken::StartObject {
             self.object_depth += 1;
@@ -94,11 +157,40 @@ impl<'a> JsonTokenizer<'a> {
     }
 }
 
+/// 标记器在两段输入之间需要保存的状态.
+pub struct TokenizerState {
+    container_stack: Vec<ContainerType>,
+    state: i32,
+    object_depth: usize,
+    line: usize,   // 下一段输入开头所在的行, 从1开始.
+    column: usize, // 下一段输入开头所在的列, 从0开始.
+}
+
+impl Default for TokenizerState {
+    fn default() -> Self {
+        TokenizerState {
+            container_stack: vec![ContainerType::Document],
+            state: State::START_OF_DOCUMENT,
+            object_depth: 0,
+            line: 1,
+            column: 0,
+        }
+    }
+}
+
 /// Tokenizer, 它完成了解析JSON的所有*真正*工作.
+///
+/// 直接按字节扫描输入: 空白和字符串中不需要转义的连续字节都通过快速路径一次跳过,
+/// 数字只校验语法并返回输入中的原始文本, 不会分配中间字符串.
 struct JsonTextTokenizer<'a> {
     container_stack: Vec<ContainerType>,
-    reader: PushBackReader<'a>,
+    input: &'a str,
+    pos: usize, // 下一个要读取的字节在`input`中的位置.
     state: i32,
+    line: usize, // `input`开头所在的行, 从1开始. 分段读取时不是第一段的输入不从第1行开始.
+    column: usize, // `input`开头所在的列, 从0开始.
+    max_string_length: usize, // 单个字符串解码后的最大字节数.
+    max_number_length: usize, // 单个数字字面量的最大字符数.
 }
 
 impl<'a> JsonTextTokenizer<'a> {
@@ -108,105 +200,169 @@ impl<'a> JsonTextTokenizer<'a> {
         | State::START_OF_DOCUMENT;
 
     fn new(input: &'a str) -> Self {
-        let mut container_stack = Vec::new();
-        container_stack.push(ContainerType::Document);
-
-        let reader = PushBackReader::new(input);
-        let state = State::START_OF_DOCUMENT;
         JsonTextTokenizer {
-            container_stack,
-            reader,
-            state,
+            container_stack: vec![ContainerType::Document],
+            input,
+            pos: 0,
+            state: State::START_OF_DOCUMENT,
+            line: 1,
+            column: 0,
+            max_string_length: usize::MAX,
+            max_number_length: usize::MAX,
+        }
+    }
+
+    // 返回`input`中第`pos`个字节所在的行和列.
+    fn location(&self, pos: usize) -> (usize, usize) {
+        let mut pos = pos.min(self.input.len());
+        while !self.input.is_char_boundary(pos) {
+            pos -= 1;
+        }
+
+        let text = &self.input[..pos];
+        match text.rfind('\n') {
+            Some(i) => (
+                self.line + text.bytes().filter(|&b| b == b'\n').count(),
+                text[i + 1..].chars().count(),
+            ),
+            None => (self.line, self.column + text.chars().count()),
         }
     }
 
     // 这个方法本质上只是循环通过字符跳过空白, 验证和改变状态(例如, 从ObjectBeforeColon到ObjectAfterColon),
-    // 直到它到达一个真正的令牌(例如, 一个开始对象, 或一个值), 在这一点上它返回令牌. 虽然这个方法很大, 但要进一步分
-    // 解它相对来说比较困难...其中大部分是大型switch语句, 它有时返回, 有时不返回.
-    fn next_impl(&mut self) -> Result<JsonToken, String> {
+    // 直到它到达一个真正的令牌(例如, 一个开始对象, 或一个值), 在这一点上它返回令牌.
+    fn next_impl(&mut self) -> Result<JsonToken<'a>, String> {
         if self.state == State::READER_EXHAUSTED {
             return Err(String::from("Next() called after end of document"));
         }
 
+        let bytes = self.input.as_bytes();
         loop {
-            let next = self.reader.read_char();
-            if None == next {
+            // Skip whitespace between tokens
+            while self.pos < bytes.len() && CLASS[bytes[self.pos] as usize] & WHITESPACE != 0 {
+                self.pos += 1;
+            }
+
+            let Some(&next) = bytes.get(self.pos) else {
                 self.validate_state(
                     State::EXPECTED_END_OF_DOCUMENT,
                     "Unexpected end of document in state: ",
                 )?;
                 self.state = State::READER_EXHAUSTED;
                 return Ok(JsonToken::EndDocument);
-            }
+            };
+            let start = self.pos;
+            self.pos += 1;
 
             match next {
-                // Skip whitespace between tokens
-                Some(' ') | Some('\t') | Some('\r') | Some('\n') => continue,
-                Some(':') => {
-                    self.validate_state(State::OBJECT_BEFORE_COLON, "Invalid state to read a colon: ")?;
+                b':' => {
+                    self.validate_structural(
+                        State::OBJECT_BEFORE_COLON,
+                        "Invalid state to read a colon: ",
+                    )?;
                     self.state = State::OBJECT_AFTER_COLON;
                 }
-                Some(',') => {
-                    self.validate_state(State::OBJECT_AFTER_PROPERTY | State::ARRAY_AFTER_VALUE, "Invalid state to read a comma: ")?;
-                    self.state = if self.state == State::OBJECT_AFTER_PROPERTY { State::OBJECT_AFTER_COMMA } else { State::ARRAY_AFTER_COMMA }
+                b',' => {
+                    self.validate_structural(
+                        State::OBJECT_AFTER_PROPERTY | State::ARRAY_AFTER_VALUE,
+                        "Invalid state to read a comma: ",
+                    )?;
+                    self.state = if self.state == State::OBJECT_AFTER_PROPERTY {
+                        State::OBJECT_AFTER_COMMA
+                    } else {
+                        State::ARRAY_AFTER_COMMA
+                    }
                 }
-                Some('"') => {
+                b'"' => {
                     let string_value = self.read_string()?;
-                    return if (self.state & (State::OBJECT_START | State::OBJECT_AFTER_COMMA)) != 0 {
+                    return if (self.state & (State::OBJECT_START | State::OBJECT_AFTER_COMMA)) != 0
+                    {
                         self.state = State::OBJECT_BEFORE_COLON;
                         Ok(JsonToken::Name(string_value))
                     } else {
-                        self.validate_and_modify_state_for_value("Invalid state to read a double quote: ")?;
+                        self.validate_value_at(start, "Invalid state to read a double quote: ")?;
                         Ok(JsonToken::StringValue(string_value))
                     };
                 }
-                Some('{') => {
-                    self.validate_state(Self::VALUE_STATES, "Invalid state to read an open brace: ")?;
+                b'{' => {
+                    self.validate_structural(
+                        Self::VALUE_STATES,
+                        "Invalid state to read an open brace: ",
+                    )?;
                     self.state = State::OBJECT_START;
                     self.container_stack.push(ContainerType::Object);
                     return Ok(JsonToken::StartObject);
                 }
-                Some('}') => {
-                    self.validate_state(State::OBJECT_AFTER_PROPERTY | State::OBJECT_START, "Invalid state to read a close brace: ")?;
+                b'}' => {
+                    self.validate_structural(
+                        State::OBJECT_AFTER_PROPERTY | State::OBJECT_START,
+                        "Invalid state to read a close brace: ",
+                    )?;
                     self.pop_container();
                     return Ok(JsonToken::EndObject);
                 }
-                Some('[') => {
-                    self.validate_state(Self::VALUE_STATES, "Invalid state to read an open square bracket: ")?;
+                b'[' => {
+                    self.validate_structural(
+                        Self::VALUE_STATES,
+                        "Invalid state to read an open square bracket: ",
+                    )?;
                     self.state = State::ARRAY_START;
                     self.container_stack.push(ContainerType::Array);
                     return Ok(JsonToken::StartArray);
                 }
-                Some(']') => {
-                    self.validate_state(State::ARRAY_AFTER_VALUE | State::ARRAY_START, "Invalid state to read a close square bracket: ")?;
+                b']' => {
+                    self.validate_structural(
+                        State::ARRAY_AFTER_VALUE | State::ARRAY_START,
+                        "Invalid state to read a close square bracket: ",
+                    )?;
                     self.pop_container();
                     return Ok(JsonToken::EndArray);
                 }
-                Some('n') => { // Start of null
+                b'n' => {
+                    // Start of null
                     self.consume_literal("null")?;
-                    self.validate_and_modify_state_for_value("Invalid state to read a null literal: ")?;
+                    self.validate_value_at(start, "Invalid state to read a null literal: ")?;
                     return Ok(JsonToken::Null);
                 }
-                Some('t') => { // Start of true
+                b't' => {
+                    // Start of true
                     self.consume_literal("true")?;
-                    self.validate_and_modify_state_for_value("Invalid state to read a true literal: ")?;
+                    self.validate_value_at(start, "Invalid state to read a true literal: ")?;
                     return Ok(JsonToken::True);
                 }
-                Some('f') => { // Start of false
+                b'f' => {
+                    // Start of false
                     self.consume_literal("false")?;
-                    self.validate_and_modify_state_for_value("Invalid state to read a false literal: ")?;
+                    self.validate_value_at(start, "Invalid state to read a false literal: ")?;
                     return Ok(JsonToken::False);
                 }
-                Some('-') /* Start of a number*/ | Some('0') | Some('1') | Some('2') | Some('3') | Some('4') | Some('5') | Some('6') | Some('7') | Some('8') | Some('9') => {
-                    let number = self.read_number(next.unwrap())?;
-                    self.validate_and_modify_state_for_value("Invalid state to read a number token: ")?;
+                b'-' | b'0'..=b'9' => {
+                    // Start of a number
+                    let number = self.read_number()?;
+                    self.validate_value_at(start, "Invalid state to read a number token: ")?;
                     return Ok(JsonToken::Number(number));
                 }
-                _ => return Err(format!("Invalid first character of token: {:?}", next)),
+                _ => {
+                    self.pos -= 1;
+                    let c = self.input[self.pos..].chars().next();
+                    return Err(format!("Invalid first character of token: {:?}", c));
+                }
             }
         }
     }
 
+    // 验证刚刚读取的结构字符. 无效时回退到该字符, 使错误位置指向它.
+    fn validate_structural(&mut self, valid_state: i32, error_prefix: &str) -> Result<(), String> {
+        self.validate_state(valid_state, error_prefix)
+            .inspect_err(|_| self.pos -= 1)
+    }
+
+    // 验证并修改读取值之后的状态. 无效时回退到值的开头`start`, 使错误位置指向它.
+    fn validate_value_at(&mut self, start: usize, error_prefix: &str) -> Result<(), String> {
+        self.validate_and_modify_state_for_value(error_prefix)
+            .inspect_err(|_| self.pos = start)
+    }
+
     fn validate_state(&self, valid_state: i32, error_prefix: &str) -> Result<(), String> {
         if valid_state & self.state == 0 {
             Err(format!("{}{:?}", error_prefix, State::name(self.state)))
@@ -216,211 +372,265 @@ impl<'a> JsonTextTokenizer<'a> {
     }
 
     // 读取字符串标记. 假设开头 " 已经被读过了.
-    fn read_string(&mut self) -> Result<String, String> {
-        let mut val = String::new();
+    // 如果字符串中没有转义字符, 则直接借用输入文本, 否则才分配新的字符串.
+    fn read_string(&mut self) -> Result<Cow<'a, str>, String> {
+        let bytes = self.input.as_bytes();
+        let quote = self.pos - 1;
+        let mut start = self.pos;
+        let mut val: Option<String> = None;
 
         loop {
-            let mut c = self
-                .reader
-                .read_char()
-                .ok_or(String::from("Unexpected end of text while reading string"))?;
-            if c < ' ' {
-                return Err(format!(
-                    "Invalid character in string literal: U+{:04X}",
-                    c as u32
-                ));
+            // 快速跳过不需要特殊处理的连续字节. 输入来自`&str`, 所以多字节字符一定是合法的UTF-8.
+            while self.pos < bytes.len() && CLASS[bytes[self.pos] as usize] & SPECIAL == 0 {
+                self.pos += 1;
             }
 
-            if c == '"' {
-                return Ok(val);
+            let Some(&c) = bytes.get(self.pos) else {
+                return Err(String::from("Unexpected end of text while reading string"));
+            };
+
+            // 在追加下一段之前检查, 超长的字符串不会被完整地复制. 错误位置为字符串的开头.
+            if val.as_ref().map_or(0, String::len) + self.pos - start > self.max_string_length {
+                self.pos = quote;
+                return Err(format!(
+                    "The set string length limit is exceeded: {}",
+                    self.max_string_length
+                ));
             }
 
-            if c == '\\' {
-                c = self.read_escaped_character()?;
+            match c {
+                b'"' => {
+                    let tail = &self.input[start..self.pos];
+                    self.pos += 1;
+                    return Ok(match val {
+                        Some(mut val) => {
+                            val.push_str(tail);
+                            Cow::Owned(val)
+                        }
+                        None => Cow::Borrowed(tail),
+                    });
+                }
+                b'\\' => {
+                    let val = val.get_or_insert_with(String::new);
+                    val.push_str(&self.input[start..self.pos]);
+                    self.pos += 1;
+                    let escaped = self.read_escaped_character()?;
+                    val.push(escaped);
+                    start = self.pos;
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid character in string literal: U+{:04X}",
+                        c as u32
+                    ));
+                }
             }
+        }
+    }
 
-            val.push(c);
+    fn read_byte(&mut self) -> Option<u8> {
+        let c = self.input.as_bytes().get(self.pos).copied();
+        if c.is_some() {
+            self.pos += 1;
         }
+        c
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
     }
 
     // 读取转义字符. 假设前面的反斜杠已经被读取.
     fn read_escaped_character(&mut self) -> Result<char, String> {
-        let c = self.reader.read_char().ok_or(String::from(
+        let c = self.read_byte().ok_or(String::from(
             "Unexpected end of text while reading character escape sequence",
         ))?;
         match c {
-            'n' => Ok('\n'),
-            '\\' => Ok('\\'),
-            'b' => Ok('\x08'), // \b
-            'f' => Ok('\x0C'), // \f
-            'r' => Ok('\r'),
-            't' => Ok('\t'),
-            '"' => Ok('"'),
-            '/' => Ok('/'),
-            'u' => self.read_unicode_escape(),
-            _ => Err(format!(
-                "Invalid character in character escape sequence: U+{:04X}",
-                c as u32
-            )),
+            b'n' => Ok('\n'),
+            b'\\' => Ok('\\'),
+            b'b' => Ok('\x08'), // \b
+            b'f' => Ok('\x0C'), // \f
+            b'r' => Ok('\r'),
+            b't' => Ok('\t'),
+            b'"' => Ok('"'),
+            b'/' => Ok('/'),
+            b'u' => self.read_unicode_escape(),
+            _ => {
+                let c = self.input[self.pos - 1..]
+                    .chars()
+                    .next()
+                    .unwrap_or_default();
+                Err(format!(
+                    "Invalid character in character escape sequence: U+{:04X}",
+                    c as u32
+                ))
+            }
         }
     }
 
-    // 读取转义的Unicode 4-nybble十六进制序列. 假设前面的\u已经被读取.
+    // 读取转义的Unicode字符. 假设前面的\u已经被读取.
+    // 高代理项必须紧跟一个转义的低代理项, 二者组合为一个字符; 单独出现的代理项是错误.
     fn read_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.read_hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.read_byte() != Some(b'\\') || self.read_byte() != Some(b'u') {
+                    return Err(format!(
+                        "Invalid Unicode escape sequence: unpaired surrogate U+{:04X}",
+                        high
+                    ));
+                }
+                let low = self.read_hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(format!(
+                        "Invalid Unicode escape sequence: unpaired surrogate U+{:04X}",
+                        high
+                    ));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => {
+                return Err(format!(
+                    "Invalid Unicode escape sequence: unpaired surrogate U+{:04X}",
+                    high
+                ));
+            }
+            _ => high,
+        };
+
+        char::from_u32(code).ok_or(format!("Invalid Unicode escape sequence: U+{:04X}", code))
+    }
+
+    // 读取4位十六进制数.
+    fn read_hex4(&mut self) -> Result<u32, String> {
         let mut result = 0;
         for _ in 0..4 {
-            let c = self.reader.read_char().ok_or(String::from(
+            let c = self.read_byte().ok_or(String::from(
                 "Unexpected end of text while reading Unicode escape sequence",
             ))?;
-            let nybble = if c >= '0' && c <= '9' {
-                c as u32 - '0' as u32
-            } else if c >= 'a' && c <= 'f' {
-                c as u32 - 'a' as u32 + 10
-            } else if c >= 'A' && c <= 'F' {
-                c as u32 - 'A' as u32 + 10
-            } else {
-                return Err(format!(
-                    "Invalid character in escape sequence: U+{:04X}",
-                    c as u32
-                ));
+            let nybble = match c {
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'f' => c - b'a' + 10,
+                b'A'..=b'F' => c - b'A' + 10,
+                _ => {
+                    return Err(format!(
+                        "Invalid character in escape sequence: U+{:04X}",
+                        c as u32
+                    ));
+                }
             };
 
-            result = (result << 4) + nybble as i32;
+            result = (result << 4) + nybble as u32;
         }
 
-        Ok(result as u8 as char)
+        Ok(result)
     }
 
     // 消耗一个纯文本字面量, 如果读取的文本与之不匹配, 则抛出异常. 假定文本的第一个字母已经被读取.
     fn consume_literal(&mut self, text: &str) -> Result<(), String> {
-        let mut chars = text.chars();
-        chars.next(); // Skip the first
-        while let Some(c) = chars.next() {
-            let next = self.reader.read_char().ok_or(format!(
+        let rest = &text.as_bytes()[1..]; // Skip the first
+        let input = &self.input.as_bytes()[self.pos..];
+        if input.starts_with(rest) {
+            self.pos += rest.len();
+            return Ok(());
+        }
+
+        let matched = input.iter().zip(rest).take_while(|(a, b)| a == b).count();
+        if matched == input.len() {
+            Err(format!(
                 "Unexpected end of text while reading literal token {}",
                 text
-            ))?;
-            if next != c {
-                return Err(format!(
-                    "Unexpected character while reading literal token {}",
-                    text
-                ));
-            }
+            ))
+        } else {
+            Err(format!(
+                "Unexpected character while reading literal token {}",
+                text
+            ))
         }
-
-        Ok(())
     }
 
-    fn read_number(&mut self, initial_character: char) -> Result<String, String> {
-        let mut builder = String::new();
-        if initial_character == '-' {
-            builder.push('-');
-        } else {
-            self.reader.push_back(initial_character)?;
+    // 读取数字标记, 返回输入中数字的原始文本. 假设第一个字符('-'或数字)已经被读取.
+    fn read_number(&mut self) -> Result<&'a str, String> {
+        let start = self.pos - 1;
+        if self.input.as_bytes()[start] != b'-' {
+            self.pos = start;
         }
 
-        // 每个方法返回它读取的不属于该部分的字符,
-        // 这样我们就知道下一步该做什么, 包括在最后把字符推回去.
-        // "end of text"返回null.
-        let mut next_char = self.read_int(&mut builder)?;
-        if let Some(val) = next_char {
-            if val == '.' {
-                next_char = self.read_frac(&mut builder)?;
-            }
+        self.read_int()?;
+        if self.peek_byte() == Some(b'.') {
+            self.pos += 1;
+            self.read_frac()?;
         }
-
-        if let Some(val) = next_char {
-            if val == 'e' || val == 'E' {
-                next_char = self.read_exp(&mut builder)?;
-            }
+        if let Some(b'e' | b'E') = self.peek_byte() {
+            self.pos += 1;
+            self.read_exp()?;
         }
 
-        // 如果读取的字符不是数字的一部分, 则将其推回, 以便再次读取以解析下一个标记.
-        if let Some(val) = next_char {
-            self.reader.push_back(val)?;
+        if self.pos - start > self.max_number_length {
+            self.pos = start;
+            return Err(format!(
+                "The set number length limit is exceeded: {}",
+                self.max_number_length
+            ));
         }
-
-        Ok(builder)
+        Ok(&self.input[start..self.pos])
     }
 
-    fn read_int(&mut self, builder: &mut String) -> Result<Option<char>, String> {
-        let first = self.reader.read_char();
-        match first {
-            None => Err(String::from("Invalid numeric literal")),
-            Some(val) => {
-                if val < '0' || val > '9' {
-                    return Err(String::from("Invalid numeric literal"));
-                }
-
-                builder.push(val);
-                let result = self.consume_digits(builder);
-                if val == '0' && !result.1 {
+    fn read_int(&mut self) -> Result<(), String> {
+        match self.read_byte() {
+            Some(first @ b'0'..=b'9') => {
+                let count = self.consume_digits();
+                if first == b'0' && count > 0 {
                     Err(String::from(
                         "Invalid numeric literal: leading 0 for non-zero value.",
                     ))
                 } else {
-                    Ok(result.0)
+                    Ok(())
                 }
             }
+            _ => Err(String::from("Invalid numeric literal")),
         }
     }
 
-    fn read_frac(&mut self, builder: &mut String) -> Result<Option<char>, String> {
-        builder.push('.'); // Already consumed this
-
-        let result = self.consume_digits(builder);
-        if result.1 {
+    fn read_frac(&mut self) -> Result<(), String> {
+        if self.consume_digits() == 0 {
             Err(String::from(
                 "Invalid numeric literal: fraction with no trailing digits",
             ))
         } else {
-            Ok(result.0)
+            Ok(())
         }
     }
 
-    fn read_exp(&mut self, builder: &mut String) -> Result<Option<char>, String> {
-        builder.push('E'); // Already consumed this (or 'e')
-        let next = self.reader.read_char();
-        match next {
-            None => Err(String::from(
-                "Invalid numeric literal: exponent with no trailing digits",
-            )),
-            Some(val) => {
-                if val == '-' || val == '+' {
-                    builder.push(val);
-                } else {
-                    self.reader.push_back(val)?;
-                }
-
-                let result = self.consume_digits(builder);
-                if result.1 {
-                    Err(String::from(
-                        "Invalid numeric literal: exponent without value",
-                    ))
-                } else {
-                    Ok(result.0)
-                }
+    fn read_exp(&mut self) -> Result<(), String> {
+        match self.peek_byte() {
+            None => {
+                return Err(String::from(
+                    "Invalid numeric literal: exponent with no trailing digits",
+                ));
             }
+            Some(b'-' | b'+') => self.pos += 1,
+            _ => {}
         }
-    }
 
-    fn consume_digits(&mut self, builder: &mut String) -> (Option<char>, bool) {
-        let mut count: usize = 0;
-        loop {
-            let next = self.reader.read_char();
+        if self.consume_digits() == 0 {
+            Err(String::from(
+                "Invalid numeric literal: exponent without value",
+            ))
+        } else {
+            Ok(())
+        }
+    }
 
-            match next {
-                Some(val) => {
-                    if val < '0' || val > '9' {
-                        return (next, count == 0);
-                    } else {
-                        count += 1;
-                        builder.push(val);
-                    }
-                }
-                None => return (next, count == 0),
-            }
+    // 跳过连续的数字, 返回跳过的个数.
+    fn consume_digits(&mut self) -> usize {
+        let bytes = self.input.as_bytes();
+        let start = self.pos;
+        while self.pos < bytes.len() && bytes[self.pos].is_ascii_digit() {
+            self.pos += 1;
         }
+
+        self.pos - start
     }
 
     // 验证我们是否处于读取值的有效状态(必要时使用给定的错误前缀), 并将状态更改为适当的状态,
@@ -460,6 +670,27 @@ impl<'a> JsonTextTokenizer<'a> {
     }
 }
 
+// 字节分类表中的标志位.
+const SPECIAL: u8 = 1; // 字符串中需要特殊处理的字节: 引号, 反斜杠和控制字符.
+const WHITESPACE: u8 = 2; // 标记之间的空白.
+
+// 按字节值索引的分类表, 代替逐个字符的比较.
+static CLASS: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 0x20 {
+        table[i] = SPECIAL;
+        i += 1;
+    }
+    table[b'"' as usize] = SPECIAL;
+    table[b'\\' as usize] = SPECIAL;
+    table[b' ' as usize] = WHITESPACE;
+    table[b'\t' as usize] |= WHITESPACE;
+    table[b'\r' as usize] |= WHITESPACE;
+    table[b'\n' as usize] |= WHITESPACE;
+    table
+};
+
 #[derive(Debug)]
 enum ContainerType {
     Document,
@@ -548,47 +779,12 @@ impl State {
     }
 }
 
-struct PushBackReader<'a> {
-    chars: Chars<'a>,
-    next_char: Option<char>,
-}
-
-impl<'a> PushBackReader<'a> {
-    fn new(input: &'a str) -> Self {
-        PushBackReader {
-            chars: input.chars(),
-            next_char: None,
-        }
-    }
-
-    // 返回迭代器中的下一个字符, 如果已到达末尾则返回None.
-    fn read_char(&mut self) -> Option<char> {
-        if self.next_char != None {
-            let tmp = self.next_char;
-            self.next_char = None;
-            return tmp;
-        }
-
-        self.chars.next()
-    }
-
-    fn push_back(&mut self, c: char) -> Result<(), String> {
-        match self.next_char {
-            Some(_) => Err(String::from(
-                "Cannot push back when already buffering a character",
-            )),
-            None => {
-                self.next_char = Some(c);
-                Ok(())
-            }
-        }
-    }
-}
-
 #[cfg(test)]
+#[allow(clippy::redundant_pattern_matching)]
 mod test {
     use crate::json_token::JsonToken;
     use crate::json_tokenizer::JsonTokenizer;
+    use std::borrow::Cow;
 
     #[test]
     fn empty_object_value() {
@@ -618,55 +814,99 @@ mod test {
 
         assert_tokens_no_replacement(
             warp_quotes(case1.0).as_str(),
-            &[JsonToken::StringValue(String::from(case1.1))],
+            &[JsonToken::StringValue(Cow::from(case1.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case2.0).as_str(),
-            &[JsonToken::StringValue(String::from(case2.1))],
+            &[JsonToken::StringValue(Cow::from(case2.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case3.0).as_str(),
-            &[JsonToken::StringValue(String::from(case3.1))],
+            &[JsonToken::StringValue(Cow::from(case3.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case4.0).as_str(),
-            &[JsonToken::StringValue(String::from(case4.1))],
+            &[JsonToken::StringValue(Cow::from(case4.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case5.0).as_str(),
-            &[JsonToken::StringValue(String::from(case5.1))],
+            &[JsonToken::StringValue(Cow::from(case5.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case6.0).as_str(),
-            &[JsonToken::StringValue(String::from(case6.1))],
+            &[JsonToken::StringValue(Cow::from(case6.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case7.0).as_str(),
-            &[JsonToken::StringValue(String::from(case7.1))],
+            &[JsonToken::StringValue(Cow::from(case7.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case8.0).as_str(),
-            &[JsonToken::StringValue(String::from(case8.1))],
+            &[JsonToken::StringValue(Cow::from(case8.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case9.0).as_str(),
-            &[JsonToken::StringValue(String::from(case9.1))],
+            &[JsonToken::StringValue(Cow::from(case9.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case10.0).as_str(),
-            &[JsonToken::StringValue(String::from(case10.1))],
+            &[JsonToken::StringValue(Cow::from(case10.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case11.0).as_str(),
-            &[JsonToken::StringValue(String::from(case11.1))],
+            &[JsonToken::StringValue(Cow::from(case11.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case12.0).as_str(),
-            &[JsonToken::StringValue(String::from(case12.1))],
+            &[JsonToken::StringValue(Cow::from(case12.1))],
         );
         assert_tokens_no_replacement(
             warp_quotes(case13.0).as_str(),
-            &[JsonToken::StringValue(String::from(case13.1))],
+            &[JsonToken::StringValue(Cow::from(case13.1))],
+        );
+    }
+
+    #[test]
+    fn unicode_escape_value() {
+        let case1 = ("\\u0041", "A");
+        let case2 = ("foo\\u09afbar", "foo\u{09af}bar");
+        let case3 = ("\\u00e9\\u00E9", "éé");
+        let case4 = ("ab\\ud800\\udc00cd", "ab\u{10000}cd"); // 代理对
+        let case5 = ("\\uD83D\\uDE00", "😀");
+
+        for case in [case1, case2, case3, case4, case5] {
+            assert_tokens_no_replacement(
+                warp_quotes(case.0).as_str(),
+                &[JsonToken::StringValue(Cow::from(case.1))],
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_unicode_escape_value() {
+        let case1 = "lone high \\ud800";
+        let case2 = "lone high \\ud800x";
+        let case3 = "lone low \\udc00";
+        let case4 = "high then high \\ud800\\ud800";
+        let case5 = "high then bmp \\ud800\\u0041";
+
+        for case in [case1, case2, case3, case4, case5] {
+            assert_error_after(warp_quotes(case).as_str(), &[]);
+        }
+    }
+
+    #[test]
+    fn number_text_is_borrowed_as_written() {
+        assert_tokens(
+            "[1e5, -0.5E-3, 0, 10]",
+            &[
+                JsonToken::StartArray,
+                JsonToken::Number("1e5"),
+                JsonToken::Number("-0.5E-3"),
+                JsonToken::Number("0"),
+                JsonToken::Number("10"),
+                JsonToken::EndArray,
+            ],
         );
     }
 
@@ -678,34 +918,19 @@ mod test {
         assert_eq!(tokenizer.object_depth, 0);
         assert_eq!(tokenizer.next().unwrap(), JsonToken::StartObject);
         assert_eq!(tokenizer.object_depth, 1);
-        assert_eq!(
-            tokenizer.next().unwrap(),
-            JsonToken::Name(String::from("foo"))
-        );
+        assert_eq!(tokenizer.next().unwrap(), JsonToken::Name(Cow::from("foo")));
         assert_eq!(tokenizer.object_depth, 1);
         assert_eq!(tokenizer.next().unwrap(), JsonToken::StartObject);
         assert_eq!(tokenizer.object_depth, 2);
-        assert_eq!(
-            tokenizer.next().unwrap(),
-            JsonToken::Name(String::from("x"))
-        );
+        assert_eq!(tokenizer.next().unwrap(), JsonToken::Name(Cow::from("x")));
         assert_eq!(tokenizer.object_depth, 2);
-        assert_eq!(
-            tokenizer.next().unwrap(),
-            JsonToken::Number(String::from("1"))
-        );
+        assert_eq!(tokenizer.next().unwrap(), JsonToken::Number("1"));
         assert_eq!(tokenizer.object_depth, 2);
-        assert_eq!(
-            tokenizer.next().unwrap(),
-            JsonToken::Name(String::from("y"))
-        );
+        assert_eq!(tokenizer.next().unwrap(), JsonToken::Name(Cow::from("y")));
         assert_eq!(tokenizer.object_depth, 2);
         assert_eq!(tokenizer.next().unwrap(), JsonToken::StartArray);
         assert_eq!(tokenizer.object_depth, 2); // 数组的深度没有改变
-        assert_eq!(
-            tokenizer.next().unwrap(),
-            JsonToken::Number(String::from("0"))
-        );
+        assert_eq!(tokenizer.next().unwrap(), JsonToken::Number("0"));
         assert_eq!(tokenizer.object_depth, 2);
         assert_eq!(tokenizer.next().unwrap(), JsonToken::EndArray);
         assert_eq!(tokenizer.object_depth, 2);
@@ -778,49 +1003,49 @@ mod test {
 
         assert_tokens(
             case1.0.parse::<i32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case1.1))],
+            &[JsonToken::Number(case1.1)],
         );
         assert_tokens(
             case2.0.parse::<i32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case2.1))],
+            &[JsonToken::Number(case2.1)],
         );
         assert_tokens(
             case3.0.parse::<i32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case3.1))],
+            &[JsonToken::Number(case3.1)],
         );
         assert_tokens(
             case4.0.parse::<i32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case4.1))],
+            &[JsonToken::Number(case4.1)],
         );
         assert_tokens(
             case5.0.parse::<f32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case5.1))],
+            &[JsonToken::Number(case5.1)],
         );
         assert_tokens(
             (case6.0.parse::<f32>().unwrap() as i32)
                 .to_string()
                 .as_str(),
-            &[JsonToken::Number(String::from(case6.1))],
+            &[JsonToken::Number(case6.1)],
         );
         assert_tokens(
             (case7.0.parse::<f32>().unwrap() as i32)
                 .to_string()
                 .as_str(),
-            &[JsonToken::Number(String::from(case7.1))],
+            &[JsonToken::Number(case7.1)],
         );
         assert_tokens(
             (case8.0.parse::<f32>().unwrap() as i32)
                 .to_string()
                 .as_str(),
-            &[JsonToken::Number(String::from(case8.1))],
+            &[JsonToken::Number(case8.1)],
         );
         assert_tokens(
             case9.0.parse::<f32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case9.1))],
+            &[JsonToken::Number(case9.1)],
         );
         assert_tokens(
             case10.0.trim().parse::<i32>().unwrap().to_string().as_str(),
-            &[JsonToken::Number(String::from(case10.1))],
+            &[JsonToken::Number(case10.1)],
         );
     }
 
@@ -886,10 +1111,10 @@ mod test {
         assert_tokens(
             "{'x': 'y'}",
             &[
-                JsonToken::StartObject,                    //
-                JsonToken::Name(String::from("x")),        //
-                JsonToken::StringValue(String::from("y")), //
-                JsonToken::EndObject,                      //
+                JsonToken::StartObject,                 //
+                JsonToken::Name(Cow::from("x")),        //
+                JsonToken::StringValue(Cow::from("y")), //
+                JsonToken::EndObject,                   //
             ],
         );
     }
@@ -975,17 +1200,17 @@ mod test {
             "[1, 'foo', null, false, true, [2], {'x':'y' }]",
             &[
                 JsonToken::StartArray,
-                JsonToken::Number(String::from("1")),
-                JsonToken::StringValue(String::from("foo")),
+                JsonToken::Number("1"),
+                JsonToken::StringValue(Cow::from("foo")),
                 JsonToken::Null,
                 JsonToken::False,
                 JsonToken::True,
                 JsonToken::StartArray,
-                JsonToken::Number(String::from("2")),
+                JsonToken::Number("2"),
                 JsonToken::EndArray,
                 JsonToken::StartObject,
-                JsonToken::Name(String::from("x")),
-                JsonToken::StringValue(String::from("y")),
+                JsonToken::Name(Cow::from("x")),
+                JsonToken::StringValue(Cow::from("y")),
                 JsonToken::EndObject,
                 JsonToken::EndArray,
             ],
@@ -998,24 +1223,24 @@ mod test {
             "{'a': 1, 'b': 'bar', 'c': null, 'd': false, 'e': true, 'f': [2], 'g': {'x':'y' }}",
             &[
                 JsonToken::StartObject,
-                JsonToken::Name(String::from("a")),
-                JsonToken::Number(String::from("1")),
-                JsonToken::Name(String::from("b")),
-                JsonToken::StringValue(String::from("bar")),
-                JsonToken::Name(String::from("c")),
+                JsonToken::Name(Cow::from("a")),
+                JsonToken::Number("1"),
+                JsonToken::Name(Cow::from("b")),
+                JsonToken::StringValue(Cow::from("bar")),
+                JsonToken::Name(Cow::from("c")),
                 JsonToken::Null,
-                JsonToken::Name(String::from("d")),
+                JsonToken::Name(Cow::from("d")),
                 JsonToken::False,
-                JsonToken::Name(String::from("e")),
+                JsonToken::Name(Cow::from("e")),
                 JsonToken::True,
-                JsonToken::Name(String::from("f")),
+                JsonToken::Name(Cow::from("f")),
                 JsonToken::StartArray,
-                JsonToken::Number(String::from("2")),
+                JsonToken::Number("2"),
                 JsonToken::EndArray,
-                JsonToken::Name(String::from("g")),
+                JsonToken::Name(Cow::from("g")),
                 JsonToken::StartObject,
-                JsonToken::Name(String::from("x")),
-                JsonToken::StringValue(String::from("y")),
+                JsonToken::Name(Cow::from("x")),
+                JsonToken::StringValue(Cow::from("y")),
                 JsonToken::EndObject,
                 JsonToken::EndObject,
             ],
@@ -1061,11 +1286,11 @@ mod test {
             assert_eq!(tokenizer.next().unwrap(), JsonToken::StartObject);
 
             if let JsonToken::Name(val) = tokenizer.next().unwrap() {
-                assert_eq!(val.as_str(), "skip");
+                assert_eq!(val, "skip");
             }
             tokenizer.skip_value().unwrap();
             if let JsonToken::Name(val) = tokenizer.next().unwrap() {
-                assert_eq!(val.as_str(), "next");
+                assert_eq!(val, "next");
             }
         };
 
@@ -1077,6 +1302,40 @@ mod test {
         assert_skip(case6);
     }
 
+    #[test]
+    fn error_location() {
+        let cases = [
+            (
+                "{\n  \"a\": x\n}",
+                "Invalid first character of token: Some('x') at 2:8",
+            ),
+            (
+                "[\"é\", 1,]",
+                "Invalid state to read a close square bracket: \"ARRAY_AFTER_COMMA\" at 1:9",
+            ),
+            (
+                "\r\n\r\n[\"\u{1}\"]",
+                "Invalid character in string literal: U+0001 at 3:3",
+            ),
+            (
+                "[1",
+                "Unexpected end of document in state: \"ARRAY_AFTER_VALUE\" at 1:3",
+            ),
+        ];
+
+        for (json, expected) in cases {
+            let mut tokenizer = JsonTokenizer::new(json);
+            let err = loop {
+                match tokenizer.next() {
+                    Ok(JsonToken::EndDocument) => panic!("Expected an Err for {:?}", json),
+                    Ok(_) => {}
+                    Err(e) => break e,
+                }
+            };
+            assert_eq!(err, expected);
+        }
+    }
+
     fn warp_quotes(s: &str) -> String {
         let mut builder = String::new();
         builder.push('\"');
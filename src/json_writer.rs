@@ -1,131 +1,500 @@
-use crate::{Map, Value};
+use crate::redact::{self, Redaction};
+use crate::{Map, PathSegment, Value};
+use std::borrow::Cow;
+use std::collections::hash_map;
+use std::ops::Range;
+use std::{slice, vec};
 
 /// 将`Map`转换成Json的转换器.
-pub struct JsonWriter {
-    settings: JsonWriterSettings,
+#[derive(Clone, Copy)]
+pub struct JsonWriter<'s> {
+    settings: &'s JsonWriterSettings,
 }
 
-impl JsonWriter {
+impl<'s> JsonWriter<'s> {
     const NAME_VALUE_SEPARATOR: &'static str = ":";
     const VALUE_SEPARATOR: &'static str = ",";
     const MULTILINE_VALUE_SEPARATOR: &'static str = ",";
-    const STRUCT_OPEN_BRACKET: char = '{';
-    const STRUCT_CLOSE_BRACKET: char = '}';
-    const ARRAY_BRACKET_OPEN: char = '[';
-    const ARRAY_BRACKET_CLOSE: char = ']';
+    pub(crate) const STRUCT_OPEN_BRACKET: char = '{';
+    pub(crate) const STRUCT_CLOSE_BRACKET: char = '}';
+    pub(crate) const ARRAY_BRACKET_OPEN: char = '[';
+    pub(crate) const ARRAY_BRACKET_CLOSE: char = ']';
 
-    pub fn new(settings: JsonWriterSettings) -> Self {
+    pub fn new(settings: &'s JsonWriterSettings) -> Self {
         JsonWriter { settings }
     }
 
     pub fn format(&self, obj: &Map) -> String {
-        let mut writer = String::new();
+        // 计算长度需要多遍历一次, 设置了过滤器或脱敏规则时它们也会多执行一次, 此时不预先分配.
+        let capacity = match self.settings.filter.is_none() && self.settings.redactions.is_empty() {
+            true => self.json_len(obj),
+            false => 0,
+        };
+        let mut writer = String::with_capacity(capacity);
         self.write_struct(&mut writer, obj, 0);
+        if self.settings.trailing_newline {
+            self.write_line(&mut writer);
+        }
 
         writer
     }
 
-    fn write_struct(&self, writer: &mut String, obj: &Map, indentation_level: usize) {
-        self.write_bracket_open(writer, Self::STRUCT_OPEN_BRACKET);
-        let written_entries = self.write_struct_entries(writer, obj, false, indentation_level + 1);
-        self.write_bracket_close(
+    // 计算`format`写出的字节数, 而不构建字符串. 是否改写在同一行由`Counter`记录的列和各成员的宽度决定.
+    pub fn json_len(&self, obj: &Map) -> usize {
+        let mut counter = Counter::default();
+        self.write_struct(&mut counter, obj, 0);
+        if self.settings.trailing_newline {
+            self.write_line(&mut counter);
+        }
+        counter.len
+    }
+
+    // 是否可能将容器改写在同一行.
+    fn inline_enabled(&self) -> bool {
+        self.settings.max_line_width.is_some() && !self.settings.indentation.is_empty()
+    }
+
+    // 将任意值转换为Json, 最外层不一定是对象.
+    pub fn format_value(&self, value: &Value) -> String {
+        let mut writer = String::new();
+        if let Some(frame) = self.write_value(&mut writer, value, 0) {
+            self.write_nested(&mut writer, frame);
+        }
+        if self.settings.trailing_newline {
+            self.write_line(&mut writer);
+        }
+
+        writer
+    }
+
+    // 写入整个对象.
+    fn write_struct<W: Sink>(&self, writer: &mut W, obj: &Map, indentation_level: usize) {
+        let frame = self.new_frame(
             writer,
-            Self::STRUCT_CLOSE_BRACKET,
-            written_entries,
+            self.struct_entries(obj),
             indentation_level,
+            self.key_width(obj),
         );
+        self.write_nested(writer, frame);
+    }
+
+    // 写入开括号已经写入的容器的全部成员. 嵌套的对象和数组通过显式的栈写入, 而不是递归,
+    // 因此写入深度不受线程栈大小约束.
+    fn write_nested<'a, W: Sink>(&self, writer: &mut W, frame: Frame<'a>) {
+        let filtering = self.settings.filter.is_some() || !self.settings.redactions.is_empty();
+        // 设置了过滤器或脱敏规则时, 记录从根对象到当前容器的路径.
+        let mut path: Vec<PathSegment<'a>> = Vec::new();
+        let mut stack = vec![frame];
+
+        while let Some(frame) = stack.last_mut() {
+            let index = frame.index;
+            let next = match frame.entries {
+                Entries::Struct(ref mut iter) => iter.next().map(|(key, val)| (Some(key), val)),
+                Entries::Sorted(ref mut iter) => iter.next().map(|(key, val)| (Some(key), val)),
+                Entries::Vec(ref mut iter) => iter.next().map(|val| (None, val)),
+            };
+
+            let Some((key, val)) = next else {
+                let frame = stack.pop().unwrap();
+                if let Some(parent) = stack.last_mut() {
+                    if filtering {
+                        path.pop();
+                    }
+                    // 原本非空的容器的成员全部被过滤掉了, 撤销写入的整个成员或元素.
+                    // 上一层容器的状态保持不变, 仍然可以写在同一行.
+                    if frame.first
+                        && frame.has_entries
+                        && let Some((position, first)) = frame.rollback
+                    {
+                        writer.rewind(position);
+                        parent.first = first;
+                        continue;
+                    }
+                    // 包含对象或数组的容器不写在同一行.
+                    parent.inline_parts = None;
+                }
+                self.write_bracket_close(
+                    writer,
+                    frame.entries.close_char(),
+                    !frame.first,
+                    frame.indentation_level,
+                );
+                self.maybe_write_inline(writer, &frame);
+                continue;
+            };
+
+            frame.index += 1;
+            let segment = || match key {
+                Some(key) => PathSegment::Key(Cow::Borrowed(key.as_str())),
+                None => PathSegment::Index(index),
+            };
+            if !self.should_write(&mut path, key.is_some(), val, segment) {
+                continue;
+            }
+
+            let rollback = match self.settings.prune_empty_containers {
+                true => Some((writer.position(), frame.first)),
+                false => None,
+            };
+            let indentation_level = frame.indentation_level + 1;
+            let key_part = key.map(|key| {
+                self.write_member_prefix(
+                    writer,
+                    key,
+                    frame.first,
+                    indentation_level,
+                    frame.key_width,
+                )
+            });
+            if key.is_none() {
+                self.write_element_prefix(writer, frame.first, indentation_level);
+            }
+            frame.first = false;
+
+            let value_start = writer.len();
+            let value_column = match frame.inline_parts {
+                Some(_) => writer.column(),
+                None => 0,
+            };
+            let nested = match self.redaction(&mut path, val, segment) {
+                Some(text) => {
+                    self.write_string(writer, &text);
+                    None
+                }
+                None => self.write_value(writer, val, indentation_level),
+            };
+            match nested {
+                Some(mut nested) => {
+                    nested.rollback = rollback;
+                    if filtering {
+                        path.push(segment());
+                    }
+                    stack.push(nested);
+                }
+                None => {
+                    if let Some(ref mut parts) = frame.inline_parts {
+                        // 写在同一行时的宽度: ", "分隔符, 名称和": ", 值.
+                        if !parts.is_empty() {
+                            frame.inline_width += 2;
+                        }
+                        if let Some((_, key_width)) = key_part {
+                            frame.inline_width += key_width + 2;
+                        }
+                        frame.inline_width += writer.column() - value_column;
+                        parts.push((key_part.map(|(key, _)| key), value_start..writer.len()));
+                    }
+                }
+            }
+        }
     }
 
-    fn write_struct_entries(
+    // 判断是否写入一个对象成员(`is_member`为true时)或数组元素. `segment`返回该成员在其容器中的路径.
+    // `skip_nulls`和`skip_empty_containers`只作用于对象成员, 过滤器对两者都生效.
+    fn should_write<'a>(
         &self,
-        writer: &mut String,
-        obj: &Map,
-        assume_first_entry_written: bool,
-        indentation_level: usize,
+        path: &mut Vec<PathSegment<'a>>,
+        is_member: bool,
+        val: &Value,
+        segment: impl FnOnce() -> PathSegment<'a>,
     ) -> bool {
-        let mut first = !assume_first_entry_written;
-        for (key, val) in obj.iter() {
-            self.maybe_write_value_separator(writer, first);
-            self.maybe_write_value_whitespace(writer, indentation_level);
+        if is_member {
+            match *val {
+                Value::Null if self.settings.skip_nulls => return false,
+                Value::Vec(ref vec) if vec.is_empty() && self.settings.skip_empty_containers => {
+                    return false;
+                }
+                Value::Object(ref obj) if obj.is_empty() && self.settings.skip_empty_containers => {
+                    return false;
+                }
+                _ => {}
+            }
+        }
+
+        match self.settings.filter {
+            Some(ref filter) => {
+                path.push(segment());
+                let keep = filter(path, val);
+                path.pop();
+                keep
+            }
+            None => true,
+        }
+    }
+
+    // 有匹配的脱敏规则时, 返回替换成员值的字符串.
+    fn redaction<'a>(
+        &self,
+        path: &mut Vec<PathSegment<'a>>,
+        val: &Value,
+        segment: impl FnOnce() -> PathSegment<'a>,
+    ) -> Option<String> {
+        if self.settings.redactions.is_empty() {
+            return None;
+        }
+
+        path.push(segment());
+        let action = redact::find(&self.settings.redactions, path);
+        path.pop();
+        let action = action?;
+        if !action.needs_text() {
+            return Some(action.apply(""));
+        }
+
+        Some(action.apply(&redact::value_text(val)))
+    }
 
-            self.write_string(writer, key);
+    // 写入开括号, 返回写入容器成员的栈帧.
+    fn new_frame<'a, W: Sink>(
+        &self,
+        writer: &mut W,
+        entries: Entries<'a>,
+        indentation_level: usize,
+        key_width: usize,
+    ) -> Frame<'a> {
+        let inline = self.inline_enabled();
+        let open_pos = writer.len();
+        let open_column = if inline { writer.column() } else { 0 };
+        self.write_bracket_open(writer, entries.open_char());
+
+        let has_entries = match entries {
+            Entries::Struct(ref iter) => iter.len() > 0,
+            Entries::Sorted(ref iter) => iter.len() > 0,
+            Entries::Vec(ref iter) => iter.len() > 0,
+        };
+        Frame {
+            entries,
+            first: true,
+            index: 0,
+            has_entries,
+            indentation_level,
+            key_width,
+            open_pos,
+            open_column,
+            rollback: None,
+            inline_parts: inline.then(Vec::new),
+            inline_width: 0,
+        }
+    }
 
-            self.write_name_value_separator(writer);
+    // 写入对象成员的值之前的部分: 分隔符, 空白, 名称和名称与值之间的分隔符. 返回名称在`writer`中的范围和字符数.
+    // `key_width`不为0时, 在分隔符后补齐空格, 使同一对象中的值对齐.
+    pub(crate) fn write_member_prefix<W: Sink>(
+        &self,
+        writer: &mut W,
+        key: &str,
+        first: bool,
+        indentation_level: usize,
+        key_width: usize,
+    ) -> (Range<usize>, usize) {
+        self.maybe_write_value_separator(writer, first);
+        self.maybe_write_value_whitespace(writer, indentation_level);
 
-            self.write_value(writer, val, indentation_level);
+        let start = writer.len();
+        let width = self.write_string(writer, key);
+        let end = writer.len();
 
-            first = false;
+        self.write_name_value_separator(writer);
+        for _ in width..key_width {
+            writer.push(' ');
         }
 
-        !first
+        (start..end, width)
     }
 
-    fn maybe_write_value_separator(&self, writer: &mut String, first: bool) {
+    fn struct_entries<'a>(&self, obj: &'a Map) -> Entries<'a> {
+        if !self.settings.sort_keys {
+            return Entries::Struct(obj.iter());
+        }
+
+        let mut entries: Vec<_> = obj.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        Entries::Sorted(entries.into_iter())
+    }
+
+    // 需要对齐时, 返回对象中最长的名称(转义后)的宽度, 否则返回0.
+    fn key_width(&self, obj: &Map) -> usize {
+        if !self.settings.align_values || self.settings.indentation.is_empty() {
+            return 0;
+        }
+
+        obj.keys()
+            .map(|key| self.write_string(&mut Counter::default(), key))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn maybe_write_value_separator<W: Sink>(&self, writer: &mut W, first: bool) {
         if first {
             return;
         }
 
-        if self.settings.indentation == "" {
+        if self.settings.indentation.is_empty() {
             writer.push_str(Self::VALUE_SEPARATOR);
+            if self.settings.space_after_comma {
+                writer.push(' ');
+            }
         } else {
             writer.push_str(Self::MULTILINE_VALUE_SEPARATOR);
         }
     }
 
-    fn write_name_value_separator(&self, writer: &mut String) {
+    fn write_name_value_separator<W: Sink>(&self, writer: &mut W) {
         writer.push_str(Self::NAME_VALUE_SEPARATOR);
 
-        if self.settings.indentation != INDENTATION_DEFAULT {
+        if self.settings.indentation != INDENTATION_DEFAULT || self.settings.space_after_colon {
             writer.push(' ');
         }
     }
 
-    fn write_null(&self, writer: &mut String) {
+    fn write_null<W: Sink>(&self, writer: &mut W) {
         writer.push_str("null");
     }
 
-    fn write_bool(&self, writer: &mut String, val: bool) {
+    fn write_bool<W: Sink>(&self, writer: &mut W, val: bool) {
         let result = if val { "true" } else { "false" };
         writer.push_str(result);
     }
 
-    fn write_f64(&self, writer: &mut String, val: f64) {
+    fn write_f64<W: Sink>(&self, writer: &mut W, val: f64) {
         writer.push_str(val.to_string().as_str());
     }
 
-    fn write_i64(&self, writer: &mut String, val: i64) {
+    fn write_i64<W: Sink>(&self, writer: &mut W, val: i64) {
         writer.push_str(val.to_string().as_str());
     }
 
-    fn write_value(&self, writer: &mut String, value: &Value, indentation_level: usize) {
+    // 写入一个值. 对于对象和数组, 只写入开括号, 并返回需要继续写入其成员的栈帧.
+    fn write_value<'a, W: Sink>(
+        &self,
+        writer: &mut W,
+        value: &'a Value,
+        indentation_level: usize,
+    ) -> Option<Frame<'a>> {
         match *value {
             Value::Null => self.write_null(writer),
             Value::Bool(val) => self.write_bool(writer, val),
             Value::F64(val) => self.write_f64(writer, val),
             Value::I64(val) => self.write_i64(writer, val),
-            Value::String(ref val) => self.write_string(writer, val),
-            Value::Vec(ref val) => self.write_vec(writer, val, indentation_level),
-            Value::Object(ref val) => self.write_struct(writer, val, indentation_level),
+            Value::String(ref val) => {
+                self.write_string(writer, val);
+            }
+            Value::Vec(ref val) => {
+                return Some(self.new_frame(
+                    writer,
+                    Entries::Vec(val.iter()),
+                    indentation_level,
+                    0,
+                ));
+            }
+            Value::Object(ref val) => {
+                return Some(self.new_frame(
+                    writer,
+                    self.struct_entries(val),
+                    indentation_level,
+                    self.key_width(val),
+                ));
+            }
+        }
+
+        None
+    }
+
+    // 多行格式下, 如果设置了最大行宽, 只写入了标量的非空对象或数组在放得下时改写在同一行, 例如`[1, 2, 3]`.
+    // 在容器闭合之后调用, 此时已经知道过滤后实际写入的成员.
+    // 只计算长度时不改写文本, 而是根据各成员的宽度和长度移动到改写之后的位置.
+    fn maybe_write_inline<W: Sink>(&self, writer: &mut W, frame: &Frame) {
+        let (Some(parts), Some(max_line_width)) =
+            (&frame.inline_parts, self.settings.max_line_width)
+        else {
+            return;
+        };
+        // 加上两个括号.
+        let width = frame.inline_width + 2;
+        if parts.is_empty() || frame.open_column + width > max_line_width {
+            return;
+        }
+
+        match writer.as_string_mut() {
+            Some(writer) => {
+                self.collapse_inline(
+                    writer,
+                    frame.open_pos,
+                    frame.open_column,
+                    parts,
+                    frame.entries.close_char(),
+                );
+            }
+            None => {
+                let len: usize = parts
+                    .iter()
+                    .map(|(key, val)| key.as_ref().map_or(0, |key| key.len() + 2) + val.len())
+                    .sum();
+                writer.rewind(Position {
+                    len: frame.open_pos + len + 2 * (parts.len() - 1) + 2,
+                    column: frame.open_column + width,
+                });
+            }
         }
     }
 
-    fn write_vec(&self, writer: &mut String, vec: &Vec<Value>, indentation_level: usize) {
-        self.write_bracket_open(writer, Self::ARRAY_BRACKET_OPEN);
-        let mut first = true;
-        for val in vec {
-            self.maybe_write_value_separator(writer, first);
-            self.maybe_write_value_whitespace(writer, indentation_level + 1);
-            self.write_value(writer, val, indentation_level + 1);
-            first = false;
+    // 将从`open_pos`开始已经写成多行的容器改写在同一行, 如果从第`column`列开始放得下的话.
+    // `parts`为各成员的名称和值在`writer`中的范围. 返回是否已经改写.
+    pub(crate) fn collapse_inline(
+        &self,
+        writer: &mut String,
+        open_pos: usize,
+        column: usize,
+        parts: &[InlinePart],
+        close_char: char,
+    ) -> bool {
+        let Some(max_line_width) = self.settings.max_line_width else {
+            return false;
+        };
+        if parts.is_empty() {
+            return false;
+        }
+
+        let mut inline = String::new();
+        inline.push_str(&writer[open_pos..open_pos + 1]);
+        for (i, (key, val)) in parts.iter().enumerate() {
+            if i > 0 {
+                inline.push_str(", ");
+            }
+            if let Some(key) = key {
+                inline.push_str(&writer[key.clone()]);
+                inline.push_str(": ");
+            }
+            inline.push_str(&writer[val.clone()]);
         }
+        inline.push(close_char);
+
+        if column + inline.chars().count() > max_line_width {
+            return false;
+        }
+
+        writer.truncate(open_pos);
+        writer.push_str(&inline);
+        true
+    }
 
-        self.write_bracket_close(writer, Self::ARRAY_BRACKET_CLOSE, !first, indentation_level);
+    // 写入数组元素之前的部分: 分隔符和空白.
+    pub(crate) fn write_element_prefix<W: Sink>(
+        &self,
+        writer: &mut W,
+        first: bool,
+        indentation_level: usize,
+    ) {
+        self.maybe_write_value_separator(writer, first);
+        self.maybe_write_value_whitespace(writer, indentation_level);
     }
 
-    // 将字符串(包括前导和尾双引号)写入构建器, 并根据需要进行转义.
-    fn write_string(&self, writer: &mut String, text: &str) {
+    // 将字符串(包括前导和尾双引号)写入构建器, 并根据需要进行转义. 返回写入的字符数.
+    pub(crate) fn write_string<W: Sink>(&self, writer: &mut W, text: &str) -> usize {
+        let escaping = self.settings.escaping;
+        let mut width = 2;
         writer.push('"');
         for c in text.chars() {
+            let len = writer.len();
             match c {
                 '"' => writer.push_str("\\\""),
                 '\\' => writer.push_str("\\\\"),
@@ -134,26 +503,47 @@ impl JsonWriter {
                 '\n' => writer.push_str("\\n"),
                 '\r' => writer.push_str("\\r"),
                 '\t' => writer.push_str("\\t"),
-                '/' => writer.push_str("\\/"),
-                c if c.is_control() => {
-                    writer.push_str(format!("\\u{:04x}", c as u32).as_str());
+                '/' if self.settings.escape_solidus => writer.push_str("\\/"),
+                c if c < ' ' => self.write_unicode_escape(writer, c),
+                '<' | '>' | '&' | '\'' | '\u{2028}' | '\u{2029}'
+                    if escaping == StringEscaping::HtmlSafe =>
+                {
+                    self.write_unicode_escape(writer, c)
+                }
+                c if !c.is_ascii() && escaping == StringEscaping::AsciiOnly => {
+                    self.write_unicode_escape(writer, c)
+                }
+                _ => {
+                    writer.push(c);
+                    width += 1;
+                    continue;
                 }
-                _ => writer.push(c),
             }
+            // 转义序列只包含ASCII字符, 字符数等于字节数.
+            width += writer.len() - len;
         }
         writer.push('"');
+        width
+    }
+
+    // 将字符写为\uXXXX转义序列, 超出基本多文种平面的字符写为UTF-16代理对.
+    fn write_unicode_escape<W: Sink>(&self, writer: &mut W, c: char) {
+        let mut buf = [0; 2];
+        for unit in c.encode_utf16(&mut buf) {
+            writer.push_str(format!("\\u{:04x}", unit).as_str());
+        }
     }
 
-    fn write_bracket_open(&self, writer: &mut String, open_char: char) {
+    pub(crate) fn write_bracket_open<W: Sink>(&self, writer: &mut W, open_char: char) {
         writer.push(open_char);
         if self.settings.indentation == INDENTATION_DEFAULT {
             writer.push_str("");
         }
     }
 
-    fn write_bracket_close(
+    pub(crate) fn write_bracket_close<W: Sink>(
         &self,
-        writer: &mut String,
+        writer: &mut W,
         close_char: char,
         has_entries: bool,
         indentation_level: usize,
@@ -170,38 +560,702 @@ impl JsonWriter {
         writer.push(close_char);
     }
 
-    fn maybe_write_value_whitespace(&self, writer: &mut String, indentation_level: usize) {
+    fn maybe_write_value_whitespace<W: Sink>(&self, writer: &mut W, indentation_level: usize) {
         if self.settings.indentation != INDENTATION_DEFAULT {
             self.write_line(writer);
             self.write_indentation(writer, indentation_level);
         }
     }
 
-    fn write_indentation(&self, writer: &mut String, indentation_level: usize) {
+    fn write_indentation<W: Sink>(&self, writer: &mut W, indentation_level: usize) {
         for _ in 0..indentation_level {
             writer.push_str(self.settings.indentation.as_str());
         }
     }
 
-    fn write_line(&self, writer: &mut String) {
-        if cfg!(target_os = "windows") {
-            writer.push_str("\r\n");
-        } else {
-            writer.push_str("\n");
+    pub(crate) fn write_line<W: Sink>(&self, writer: &mut W) {
+        match self.settings.newline {
+            Newline::Lf => writer.push('\n'),
+            Newline::CrLf => writer.push_str("\r\n"),
+        }
+    }
+}
+
+// 写入Json的目标. `String`保存写入的文本, `Counter`只计算长度.
+pub(crate) trait Sink {
+    fn push(&mut self, c: char);
+    fn push_str(&mut self, s: &str);
+    fn len(&self) -> usize;
+    // 返回当前行已写入的字符数.
+    fn column(&self) -> usize;
+    // 回到之前记录的位置, 丢弃之后写入的内容.
+    fn rewind(&mut self, position: Position);
+    // 返回写入的文本, 只计算长度时返回None.
+    fn as_string_mut(&mut self) -> Option<&mut String>;
+
+    fn position(&self) -> Position {
+        Position {
+            len: self.len(),
+            column: self.column(),
         }
     }
 }
 
+// `Sink`中的位置. `Counter`没有文本, 回退之后需要记录的列才能继续计算行宽.
+#[derive(Clone, Copy)]
+pub(crate) struct Position {
+    len: usize,
+    column: usize,
+}
+
+impl Sink for String {
+    fn push(&mut self, c: char) {
+        String::push(self, c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        String::push_str(self, s);
+    }
+
+    fn len(&self) -> usize {
+        String::len(self)
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.rfind('\n').map_or(0, |i| i + 1);
+        self[line_start..].chars().count()
+    }
+
+    fn rewind(&mut self, position: Position) {
+        String::truncate(self, position.len);
+    }
+
+    fn as_string_mut(&mut self) -> Option<&mut String> {
+        Some(self)
+    }
+}
+
+// 只记录写入的字节数和当前行的字符数的`Sink`.
+#[derive(Default)]
+struct Counter {
+    len: usize,
+    column: usize,
+}
+
+impl Sink for Counter {
+    fn push(&mut self, c: char) {
+        self.len += c.len_utf8();
+        self.column = if c == '\n' { 0 } else { self.column + 1 };
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.len += s.len();
+        self.column = match s.rfind('\n') {
+            Some(i) => s[i + 1..].chars().count(),
+            None => self.column + s.chars().count(),
+        };
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn column(&self) -> usize {
+        self.column
+    }
+
+    fn rewind(&mut self, position: Position) {
+        self.len = position.len;
+        self.column = position.column;
+    }
+
+    fn as_string_mut(&mut self) -> Option<&mut String> {
+        None
+    }
+}
+
+// 写入栈中尚未闭合的对象或数组.
+struct Frame<'a> {
+    entries: Entries<'a>,
+    first: bool,       // 是否还没有写入任何成员.
+    index: usize,      // 下一个成员的下标.
+    has_entries: bool, // 过滤之前是否有成员.
+    indentation_level: usize,
+    key_width: usize,                      // 对齐值时名称的宽度, 0表示不对齐.
+    open_pos: usize,                       // 开括号在`writer`中的位置.
+    open_column: usize,                    // 开括号所在的列, 只在`inline_parts`不为None时有效.
+    rollback: Option<(Position, bool)>, // 需要剪除时, 写入该成员之前`writer`的位置和父容器的`first`.
+    inline_parts: Option<Vec<InlinePart>>, // 可以写在同一行时, 已写入的各成员的名称和值的位置.
+    inline_width: usize,                // 已写入的成员写在同一行时的宽度(不包括括号).
+}
+
+// 一个成员的名称(数组元素没有名称)和值在`writer`中的范围.
+pub(crate) type InlinePart = (Option<Range<usize>>, Range<usize>);
+
+enum Entries<'a> {
+    Struct(hash_map::Iter<'a, String, Value>),
+    Sorted(vec::IntoIter<(&'a String, &'a Value)>), // 按名称排序的对象成员.
+    Vec(slice::Iter<'a, Value>),
+}
+
+impl Entries<'_> {
+    fn open_char(&self) -> char {
+        match *self {
+            Entries::Struct(_) | Entries::Sorted(_) => JsonWriter::STRUCT_OPEN_BRACKET,
+            Entries::Vec(_) => JsonWriter::ARRAY_BRACKET_OPEN,
+        }
+    }
+
+    fn close_char(&self) -> char {
+        match *self {
+            Entries::Struct(_) | Entries::Sorted(_) => JsonWriter::STRUCT_CLOSE_BRACKET,
+            Entries::Vec(_) => JsonWriter::ARRAY_BRACKET_CLOSE,
+        }
+    }
+}
+
+/// 写入Json时使用的设置.
+///
+/// `indentation`为空时输出紧凑格式, 否则输出多行格式, 每一层嵌套缩进一次`indentation`.
+///
+/// `skip_nulls`, `skip_empty_containers`, `prune_empty_containers`和`filter`在写入时过滤成员,
+/// `redactions`在写入时替换敏感值, 都不需要先复制并修改`Map`.
 pub struct JsonWriterSettings {
     pub indentation: String,
+    pub escaping: StringEscaping,      // 字符串的转义策略.
+    pub escape_solidus: bool,          // 是否将`/`转义为`\/`, 默认不转义.
+    pub newline: Newline,              // 多行格式使用的换行符, 与运行的操作系统无关.
+    pub space_after_colon: bool, // 紧凑格式下, 是否在名称与值之间的`:`后加一个空格. 多行格式总是会加.
+    pub space_after_comma: bool, // 紧凑格式下, 是否在值之间的`,`后加一个空格.
+    pub max_line_width: Option<usize>, // 多行格式下, 只包含标量的对象或数组在这个宽度(字符数)内时写在同一行.
+    pub trailing_newline: bool,        // 是否在文档末尾加一个换行符.
+    pub align_values: bool, // 多行格式下, 是否在`:`后补齐空格, 使同一对象中各成员的值对齐.
+    pub skip_nulls: bool,   // 是否跳过值为`Null`的对象成员.
+    pub skip_empty_containers: bool, // 是否跳过值为空数组或空对象的对象成员.
+    pub prune_empty_containers: bool, // 对象成员或数组元素的值(对象或数组)中的成员全部被过滤掉时, 是否也跳过它. 逐层向上生效.
+    pub filter: Option<EntryFilter>,  // 对每个对象成员和数组元素调用, 返回false时跳过.
+    pub sort_keys: bool,              // 是否按名称(逐字节比较)排序对象成员, 使输出稳定.
+    pub redactions: Vec<Redaction>,   // 写入时替换敏感值的规则, 在过滤之后生效, 不修改`Map`.
 }
 
+/// 写入时过滤成员的谓词. 参数为成员的路径(最后一段为成员的名称或下标)和成员的值.
+pub type EntryFilter = Box<dyn Fn(&[PathSegment], &Value) -> bool + Send + Sync>;
+
 const INDENTATION_DEFAULT: &str = "";
 
 impl Default for JsonWriterSettings {
     fn default() -> Self {
         JsonWriterSettings {
             indentation: INDENTATION_DEFAULT.to_string(),
+            escaping: StringEscaping::Minimal,
+            escape_solidus: false,
+            newline: Newline::Lf,
+            space_after_colon: false,
+            space_after_comma: false,
+            max_line_width: None,
+            trailing_newline: false,
+            align_values: false,
+            skip_nulls: false,
+            skip_empty_containers: false,
+            prune_empty_containers: false,
+            filter: None,
+            sort_keys: false,
+            redactions: Vec::new(),
+        }
+    }
+}
+
+/// 换行符的风格.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Newline {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+/// 写入字符串时的转义策略.
+///
+/// 无论哪种策略, 引号, 反斜杠和U+0000到U+001F的控制字符总是会被转义; `/`是否转义由`JsonWriterSettings::escape_solidus`单独控制.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringEscaping {
+    /// 只转义Json要求转义的字符, 其余字符(包括U+007F到U+009F和其他非ASCII字符)原样输出.
+    Minimal,
+    /// 额外将所有非ASCII字符转义为`\uXXXX`, 超出基本多文种平面的字符使用代理对,
+    /// 输出只包含7位ASCII字符.
+    AsciiOnly,
+    /// 额外转义`<`, `>`, `&`, `'`, U+2028和U+2029, 使输出可以安全地嵌入HTML的`<script>`标签和JavaScript源码.
+    HtmlSafe,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json_writer::JsonWriter;
+    use crate::{
+        JsonWriterSettings, Map, Newline, Redaction, RedactionAction, RedactionTarget,
+        StringEscaping, Value,
+    };
+
+    #[test]
+    fn default_escaping() {
+        let text = "a\"b\\c/d\n\u{1}\u{1f}\u{7f}\u{85}é😀<";
+        assert_eq!(
+            format_string(text, JsonWriterSettings::default()),
+            "{\"k\":\"a\\\"b\\\\c/d\\n\\u0001\\u001f\u{7f}\u{85}é😀<\"}"
+        );
+    }
+
+    #[test]
+    fn escaped_solidus() {
+        let settings = JsonWriterSettings {
+            escape_solidus: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_string("https://example.com/a", settings),
+            r#"{"k":"https:\/\/example.com\/a"}"#
+        );
+    }
+
+    #[test]
+    fn ascii_only_escaping() {
+        let settings = JsonWriterSettings {
+            escaping: StringEscaping::AsciiOnly,
+            ..Default::default()
+        };
+        let json = format_string("aé中😀\u{7f}\u{85}", settings);
+        assert_eq!(
+            json,
+            "{\"k\":\"a\\u00e9\\u4e2d\\ud83d\\ude00\u{7f}\\u0085\"}"
+        );
+        assert!(json.is_ascii());
+
+        let mut map = Map::new();
+        map.merge(json.as_str()).unwrap();
+        assert_eq!(map["k"].as_string().unwrap(), "aé中😀\u{7f}\u{85}");
+    }
+
+    #[test]
+    fn html_safe_escaping() {
+        let settings = JsonWriterSettings {
+            escaping: StringEscaping::HtmlSafe,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_string("</script><a href='x'>&\u{2028}\u{2029}é", settings),
+            r#"{"k":"\u003c/script\u003e\u003ca href=\u0027x\u0027\u003e\u0026\u2028\u2029é"}"#
+        );
+    }
+
+    #[test]
+    fn compact_spacing_and_trailing_newline() {
+        let mut map = Map::new();
+        map.insert(
+            "k".to_string(),
+            Value::Vec(vec![Value::I64(1), Value::I64(2)]),
+        );
+
+        let settings = JsonWriterSettings {
+            space_after_colon: true,
+            space_after_comma: true,
+            trailing_newline: true,
+            ..Default::default()
+        };
+        assert_eq!(JsonWriter::new(&settings).format(&map), "{\"k\": [1, 2]}\n");
+    }
+
+    #[test]
+    fn newline_style() {
+        let mut map = Map::new();
+        map.insert("k".to_string(), Value::Vec(vec![Value::I64(1)]));
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            newline: Newline::CrLf,
+            trailing_newline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            JsonWriter::new(&settings).format(&map),
+            "{\r\n  \"k\": [\r\n    1\r\n  ]\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn max_line_width() {
+        let mut nested = Map::new();
+        nested.insert("a".to_string(), Value::I64(1));
+        let mut map = Map::new();
+        map.insert(
+            "k".to_string(),
+            Value::Vec(vec![
+                Value::Vec(vec![Value::I64(1), Value::I64(2), Value::I64(3)]),
+                Value::Object(nested),
+                Value::Vec(vec![]),
+                Value::Vec(vec![Value::String("a long string".to_string()); 3]),
+            ]),
+        );
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(30),
+            ..Default::default()
+        };
+        let expected = r#"{
+  "k": [
+    [1, 2, 3],
+    {"a": 1},
+    [],
+    [
+      "a long string",
+      "a long string",
+      "a long string"
+    ]
+  ]
+}"#;
+        assert_eq!(JsonWriter::new(&settings).format(&map), expected);
+    }
+
+    #[test]
+    fn align_values() {
+        let mut map = Map::new();
+        map.insert("a".to_string(), Value::I64(1));
+        map.insert("abc".to_string(), Value::I64(2));
+        map.insert("\"".to_string(), Value::I64(3));
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            align_values: true,
+            ..Default::default()
+        };
+        let json = JsonWriter::new(&settings).format(&map);
+        let mut lines: Vec<&str> = json
+            .lines()
+            .map(|line| line.trim_end_matches(','))
+            .collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            [r#"  "\"":  3"#, r#"  "a":   1"#, r#"  "abc": 2"#, "{", "}"]
+        );
+    }
+
+    #[test]
+    fn skip_nulls_and_empty_containers() {
+        let mut map = Map::new();
+        map.insert("a".to_string(), Value::Null);
+        map.insert("b".to_string(), Value::Vec(vec![Value::Null]));
+        map.insert("c".to_string(), Value::Vec(vec![]));
+        map.insert("d".to_string(), Value::Object(Map::new()));
+
+        let settings = JsonWriterSettings {
+            skip_nulls: true,
+            skip_empty_containers: true,
+            ..Default::default()
+        };
+        // 数组元素不受影响
+        assert_eq!(JsonWriter::new(&settings).format(&map), r#"{"b":[null]}"#);
+    }
+
+    #[test]
+    fn prune_empty_containers() {
+        let mut inner = Map::new();
+        inner.insert("x".to_string(), Value::Null);
+        let mut middle = Map::new();
+        middle.insert("inner".to_string(), Value::Object(inner));
+        middle.insert("list".to_string(), Value::Vec(vec![Value::Null]));
+        let mut map = Map::new();
+        map.insert("middle".to_string(), Value::Object(middle));
+        map.insert("empty".to_string(), Value::Object(Map::new()));
+        map.insert("k".to_string(), Value::I64(1));
+
+        let settings = JsonWriterSettings {
+            skip_nulls: true,
+            prune_empty_containers: true,
+            filter: Some(Box::new(|_, val| *val != Value::Null)),
+            ..Default::default()
+        };
+        let json = JsonWriter::new(&settings).format(&map);
+        // 原本就为空的对象不算被过滤掉
+        let case1 = r#"{"empty":{},"k":1}"#;
+        let case2 = r#"{"k":1,"empty":{}}"#;
+        assert!(json == case1 || json == case2, "{}", json);
+    }
+
+    #[test]
+    fn prune_array_elements() {
+        let mut map = Map::new();
+        map.merge(r#"{"k":[{"a":null}]}"#).unwrap();
+        let settings = JsonWriterSettings {
+            skip_nulls: true,
+            ..Default::default()
+        };
+        assert_eq!(JsonWriter::new(&settings).format(&map), r#"{"k":[{}]}"#);
+
+        let settings = JsonWriterSettings {
+            skip_nulls: true,
+            prune_empty_containers: true,
+            ..Default::default()
+        };
+        assert_eq!(JsonWriter::new(&settings).format(&map), "{}");
+
+        // 剪掉嵌套的元素之后, 只剩标量的数组仍然可以写在同一行.
+        map.merge(r#"{"k":[{"a":null},1,[{"b":null}],2]}"#).unwrap();
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(30),
+            skip_nulls: true,
+            prune_empty_containers: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            JsonWriter::new(&settings).format(&map),
+            "{\n  \"k\": [1, 2]\n}"
+        );
+    }
+
+    #[test]
+    fn filter_receives_path() {
+        let mut user = Map::new();
+        user.insert("name".to_string(), Value::String("li".to_string()));
+        user.insert("password".to_string(), Value::String("secret".to_string()));
+        let mut map = Map::new();
+        map.insert(
+            "users".to_string(),
+            Value::Vec(vec![Value::Object(user), Value::I64(2), Value::I64(3)]),
+        );
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(80),
+            filter: Some(Box::new(|path, _| {
+                let pointer = crate::to_json_pointer(path);
+                pointer != "/users/2" && !pointer.ends_with("/password")
+            })),
+            ..Default::default()
+        };
+        let expected = r#"{
+  "users": [
+    {"name": "li"},
+    2
+  ]
+}"#;
+        assert_eq!(JsonWriter::new(&settings).format(&map), expected);
+    }
+
+    #[test]
+    fn sort_keys() {
+        let mut nested = Map::new();
+        for key in ["b", "a", "B"] {
+            nested.insert(key.to_string(), Value::Null);
+        }
+        let mut map = Map::new();
+        map.insert("z".to_string(), Value::Vec(vec![Value::Object(nested)]));
+        map.insert("y".to_string(), Value::I64(1));
+
+        let settings = JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            JsonWriter::new(&settings).format(&map),
+            r#"{"y":1,"z":[{"B":null,"a":null,"b":null}]}"#
+        );
+    }
+
+    #[test]
+    fn redactions() {
+        let mut map = Map::new();
+        map.merge(
+            r#"{"Token":"abc","auth":{"token":"x","user":"li"},"cards":["4111111111111111",[1,2]],"pin":1234}"#,
+        )
+        .unwrap();
+
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            max_line_width: Some(80),
+            sort_keys: true,
+            filter: Some(Box::new(|path, _| {
+                crate::to_json_pointer(path) != "/auth/user"
+            })),
+            redactions: vec![
+                Redaction {
+                    target: RedactionTarget::KeyPattern("*TOKEN".to_string()),
+                    action: RedactionAction::Mask("[redacted]".to_string()),
+                },
+                Redaction {
+                    target: RedactionTarget::Path("/cards/*".to_string()),
+                    action: RedactionAction::Preview(4),
+                },
+                Redaction {
+                    target: RedactionTarget::Key("pin".to_string()),
+                    action: RedactionAction::Hash,
+                },
+                Redaction {
+                    target: RedactionTarget::Key("auth".to_string()),
+                    action: RedactionAction::Hash,
+                },
+            ],
+            ..Default::default()
+        };
+        // 对象和数组按紧凑格式的文本替换, 被替换的对象中的成员不再过滤.
+        let expected = r#"{
+  "Token": "[redacted]",
+  "auth": "fnv1a:{auth}",
+  "cards": ["4111…", "[1,2…"],
+  "pin": "fnv1a:{pin}"
+}"#;
+        let expected = expected
+            .replace("{auth}", &hash(r#"{"token":"x","user":"li"}"#))
+            .replace("{pin}", &hash("1234"));
+        assert_eq!(JsonWriter::new(&settings).format(&map), expected);
+        assert_eq!(map["pin"], Value::I64(1234));
+
+        fn hash(text: &str) -> String {
+            RedactionAction::Hash.apply(text)["fnv1a:".len()..].to_string()
+        }
+    }
+
+    #[test]
+    fn json_len() {
+        let mut map = Map::new();
+        map.merge(
+            r#"{"s":"a/b\"c\u0001é😀<","n":[1,-2.5,1e300,null,true],"o":{"x":{},"y":[],"long key":{"z":null}},"e":{}}"#,
+        )
+        .unwrap();
+
+        let all_settings = [
+            JsonWriterSettings::default(),
+            JsonWriterSettings {
+                indentation: "\t".to_string(),
+                newline: Newline::CrLf,
+                trailing_newline: true,
+                align_values: true,
+                escaping: StringEscaping::AsciiOnly,
+                ..Default::default()
+            },
+            JsonWriterSettings {
+                space_after_colon: true,
+                space_after_comma: true,
+                escaping: StringEscaping::HtmlSafe,
+                ..Default::default()
+            },
+            JsonWriterSettings {
+                indentation: "  ".to_string(),
+                skip_nulls: true,
+                skip_empty_containers: true,
+                prune_empty_containers: true,
+                filter: Some(Box::new(|path, _| path.len() < 3)),
+                ..Default::default()
+            },
+            JsonWriterSettings {
+                indentation: "  ".to_string(),
+                max_line_width: Some(30),
+                ..Default::default()
+            },
+            JsonWriterSettings {
+                sort_keys: true,
+                ..Default::default()
+            },
+            JsonWriterSettings {
+                indentation: "  ".to_string(),
+                sort_keys: true,
+                max_line_width: Some(30),
+                redactions: vec![
+                    Redaction {
+                        target: RedactionTarget::Key("o".to_string()),
+                        action: RedactionAction::Hash,
+                    },
+                    Redaction {
+                        target: RedactionTarget::Path("/n/*".to_string()),
+                        action: RedactionAction::Preview(2),
+                    },
+                    Redaction {
+                        target: RedactionTarget::Key("s".to_string()),
+                        action: RedactionAction::Mask("***".to_string()),
+                    },
+                ],
+                ..Default::default()
+            },
+        ];
+        for settings in all_settings {
+            let writer = JsonWriter::new(&settings);
+            assert_eq!(writer.json_len(&map), writer.format(&map).len());
+        }
+        assert_eq!(Map::new().json_len(&JsonWriterSettings::default()), 2);
+    }
+
+    #[test]
+    fn json_len_with_max_line_width() {
+        // 名称和值包含多字节字符, 使字符数与字节数不同; 各种宽度下改写在同一行的容器不同.
+        let mut map = Map::new();
+        map.merge(
+            r#"{"é":[1,"中文",[true,null],{"ключ":"😀"}],"b":{"x":[],"y":{"z":[null,"a\"b"]}},"c":[{"d":null}],"长":"值"}"#,
+        )
+        .unwrap();
+
+        for max_line_width in 0..50 {
+            let all_settings = [
+                JsonWriterSettings {
+                    indentation: "  ".to_string(),
+                    max_line_width: Some(max_line_width),
+                    ..Default::default()
+                },
+                JsonWriterSettings {
+                    indentation: "\t".to_string(),
+                    newline: Newline::CrLf,
+                    trailing_newline: true,
+                    align_values: true,
+                    sort_keys: true,
+                    escaping: StringEscaping::AsciiOnly,
+                    max_line_width: Some(max_line_width),
+                    ..Default::default()
+                },
+                JsonWriterSettings {
+                    indentation: "    ".to_string(),
+                    sort_keys: true,
+                    skip_nulls: true,
+                    prune_empty_containers: true,
+                    max_line_width: Some(max_line_width),
+                    redactions: vec![Redaction {
+                        target: RedactionTarget::Key("ключ".to_string()),
+                        action: RedactionAction::Preview(1),
+                    }],
+                    ..Default::default()
+                },
+            ];
+            for settings in all_settings {
+                let writer = JsonWriter::new(&settings);
+                assert_eq!(
+                    writer.json_len(&map),
+                    writer.format(&map).len(),
+                    "max_line_width: {}",
+                    max_line_width
+                );
+            }
         }
     }
+
+    #[test]
+    fn format_value() {
+        let settings = JsonWriterSettings {
+            indentation: "  ".to_string(),
+            ..Default::default()
+        };
+        let writer = JsonWriter::new(&settings);
+        assert_eq!(
+            writer.format_value(&Value::String("a".to_string())),
+            r#""a""#
+        );
+        assert_eq!(
+            writer.format_value(&Value::Vec(vec![Value::I64(1), Value::Vec(vec![])])),
+            "[\n  1,\n  []\n]"
+        );
+    }
+
+    fn format_string(text: &str, settings: JsonWriterSettings) -> String {
+        let mut map = Map::new();
+        map.insert("k".to_string(), Value::String(text.to_string()));
+        JsonWriter::new(&settings).format(&map)
+    }
 }
@@ -1,11 +1,57 @@
+pub use canonical::CanonicalValue;
+pub use cbor::{
+    ByteStringPolicy, CborReaderSettings, CborTagPolicy, CborWriterSettings, MapKeyPolicy,
+    NonFinitePolicy,
+};
+pub use codegen::{CodegenSettings, generate_rust};
+pub use compare::{CompareSettings, Mismatch, Tolerance};
+pub use config::{Config, ConfigBuilder};
+pub use convert::{FromValue, ToValue, type_error};
+pub use csv::{CsvReaderSettings, CsvRecords, CsvWriterSettings, read_csv};
+#[doc(hidden)]
+pub use diff::assert_json_eq_impl;
+pub use diff::{Change, DiffSettings, diff, render_diff};
+pub use entry::{PathEntry, VacantPathEntry};
 pub use json_reader::JsonReaderSettings;
-pub use json_writer::JsonWriterSettings;
+pub use json_stream_writer::{reformat, reformat_stream};
+pub use json_writer::{EntryFilter, JsonWriterSettings, Newline, StringEscaping};
 pub use map::Map;
+pub use map_ref::MapRef;
+pub use msgpack::{ExtensionPolicy, MsgpackReaderSettings};
+pub use number::{Number, NumberError};
+pub use path::{PathSegment, to_json_pointer};
+pub use query_string::{KeyNesting, QueryStringSettings};
+pub use redact::{Redaction, RedactionAction, RedactionTarget};
+pub use schema::{ArrayShape, NumberRange, ObjectShape, Shape, StringFormat, StringShape};
 pub use value::Value;
+pub use value_ref::ValueRef;
+pub use walk::{Visitor, Walk};
 
+mod canonical;
+mod cbor;
+mod codegen;
+mod compare;
+mod config;
+mod convert;
+mod csv;
+mod diff;
+mod entry;
 mod json_reader;
+mod json_stream_writer;
 mod json_token;
 mod json_tokenizer;
 mod json_writer;
 mod map;
+mod map_ref;
+mod msgpack;
+mod number;
+mod path;
+mod query_string;
+mod redact;
+mod schema;
+#[cfg(test)]
+mod test_util;
+mod toml;
 mod value;
+mod value_ref;
+mod walk;
@@ -1,12 +1,23 @@
+use crate::Value;
+use crate::compare::{self, Mismatch};
+use crate::csv;
 use crate::json_reader::JsonReader;
 use crate::json_writer::JsonWriter;
-use crate::Value;
-use crate::{JsonReaderSettings, JsonWriterSettings};
+use crate::msgpack::{MsgpackReader, MsgpackWriter};
+use crate::path;
+use crate::query_string::{QueryStringReader, QueryStringWriter};
+use crate::toml::{TomlReader, TomlWriter};
+use crate::walk::{self, Children, ChildrenMut};
+use crate::{
+    CompareSettings, CsvReaderSettings, JsonReaderSettings, JsonWriterSettings,
+    MsgpackReaderSettings, PathEntry, PathSegment, QueryStringSettings, Visitor,
+};
 use std::collections::HashMap;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 
 /// 可以与Json格式互相转换的`Map`.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Map(HashMap<String, Value>);
 
 impl Map {
@@ -49,12 +60,171 @@ impl Map {
     /// assert_eq!(map1["111"].as_f64().unwrap(), 111.);
     /// assert_eq!(map1["333"].as_f64().unwrap(), 333.);
     /// ```
-    pub fn merge_from(&mut self, other: Map) {
-        for (k, v) in other.0.into_iter() {
+    pub fn merge_from(&mut self, mut other: Map) {
+        for (k, v) in other.drain() {
             self.0.insert(k, v);
         }
     }
 
+    /// 按照JSON Pointer (RFC 6901)查找值, 例如`/a/0/b`. 找不到或格式错误时返回None;
+    /// 空字符串表示整个`Map`, 由于它不是`Value`, 也返回None.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":[{"b":true}],"~":1}"#).unwrap();
+    /// assert_eq!(map.pointer("/a/0/b").unwrap().as_bool(), Some(true));
+    /// assert_eq!(map.pointer("/~0").unwrap().as_i64(), Some(1));
+    /// assert!(map.pointer("/a/1").is_none());
+    /// assert!(map.pointer("a").is_none());
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let mut tokens = path::pointer_tokens(pointer)?;
+        let first = self.get(tokens.next()?.as_ref())?;
+        first.pointer_tokens(tokens)
+    }
+
+    /// 返回按成员名称逐层深入的嵌套条目. 插入时会创建缺少的中间对象;
+    /// 路径为空, 或者中间已有的值不是对象时返回错误, 此时`Map`不会被修改.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, Value};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"server":{"host":"localhost"}}"#).unwrap();
+    /// map.entry_path(["server", "tls", "enabled"])
+    ///     .unwrap()
+    ///     .or_insert_with(|| Value::Bool(false));
+    /// assert_eq!(map.pointer("/server/tls/enabled").unwrap().as_bool(), Some(false));
+    ///
+    /// let host = map.entry_path(["server", "host"]).unwrap().or_insert(Value::Null);
+    /// host.as_string_mut().unwrap().push_str(":80");
+    /// assert_eq!(map.pointer("/server/host").unwrap().as_string(), Some("localhost:80"));
+    ///
+    /// assert!(map.entry_path(["server", "host", "name"]).is_err());
+    /// ```
+    pub fn entry_path<I, K>(&mut self, path: I) -> Result<PathEntry<'_>, String>
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        PathEntry::new(self, path.into_iter().map(Into::into).collect())
+    }
+
+    /// 深度优先遍历所有成员及其子节点, 返回是否被`Walk::Stop`提前结束.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, PathSegment, Value, Walk, to_json_pointer};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":{"secret":{"b":1}},"c":[true]}"#).unwrap();
+    ///
+    /// let mut paths = Vec::new();
+    /// map.walk(&mut |path: &[PathSegment], _: &Value| {
+    ///     paths.push(to_json_pointer(path));
+    ///     if path.last().and_then(PathSegment::as_key) == Some("secret") {
+    ///         Walk::SkipChildren
+    ///     } else {
+    ///         Walk::Continue
+    ///     }
+    /// });
+    /// paths.sort();
+    /// assert_eq!(paths, ["/a", "/a/secret", "/c", "/c/0"]);
+    /// ```
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) -> bool {
+        walk::walk(Children::of_map(self), visitor)
+    }
+
+    /// 对所有不是数组或对象的值调用`f`, 参数为值的路径和值的可变引用.
+    pub fn map_values<F>(&mut self, f: F)
+    where
+        F: FnMut(&[PathSegment], &mut Value),
+    {
+        walk::map_values(ChildrenMut::of_map(self), f)
+    }
+
+    /// 删除所有`f`返回false的成员和元素, 参见`Value::retain_deep`.
+    /// 只需过滤直接成员时使用`HashMap::retain`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, PathSegment};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":{"_id":1,"b":2},"_rev":3}"#).unwrap();
+    /// map.retain_deep(|path, _| {
+    ///     !path.last().and_then(PathSegment::as_key).unwrap_or("").starts_with('_')
+    /// });
+    /// assert_eq!(map.to_json(), r#"{"a":{"b":2}}"#);
+    /// ```
+    pub fn retain_deep<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[PathSegment], &Value) -> bool,
+    {
+        walk::retain_members(self, &mut Vec::new(), &mut f);
+        walk::retain(ChildrenMut::of_map(self), f);
+    }
+
+    /// 重命名所有对象的成员, 参见`Value::rename_keys`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"A":{"B":[{"C":1}]}}"#).unwrap();
+    /// map.rename_keys(|_, key| Some(key.to_lowercase())).unwrap();
+    /// assert_eq!(map.to_json(), r#"{"a":{"b":[{"c":1}]}}"#);
+    /// ```
+    pub fn rename_keys<F>(&mut self, mut f: F) -> Result<(), String>
+    where
+        F: FnMut(&[PathSegment], &str) -> Option<String>,
+    {
+        walk::rename_members(self, &mut Vec::new(), &mut f)?;
+        walk::rename_keys(ChildrenMut::of_map(self), f)
+    }
+
+    /// 与`expected`进行语义比较, 带有默认设置, 参见`Value::compare`.
+    pub fn compare<'a>(&'a self, expected: &'a Map) -> Vec<Mismatch<'a>> {
+        self.compare_with_settings(expected, CompareSettings::default())
+    }
+
+    /// 与`expected`进行语义比较, 自定义比较设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{CompareSettings, Map};
+    ///
+    /// let mut actual = Map::new();
+    /// actual.merge(r#"{"id":7,"name":"a","tags":["x","y"]}"#).unwrap();
+    /// let mut expected = Map::new();
+    /// expected.merge(r#"{"id":1,"name":"a","tags":["y","x"]}"#).unwrap();
+    ///
+    /// let settings = CompareSettings {
+    ///     ignore_array_order: true,
+    ///     ignore_paths: vec!["/id".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// assert!(actual.compare_with_settings(&expected, settings).is_empty());
+    /// ```
+    pub fn compare_with_settings<'a>(
+        &'a self,
+        expected: &'a Map,
+        settings: CompareSettings,
+    ) -> Vec<Mismatch<'a>> {
+        compare::compare_maps(self, expected, &settings)
+    }
+
     /// 将`Map`转换为Json结构, 带有默认设置.
     ///
     /// # 例子
@@ -109,11 +279,36 @@ impl Map {
     ///
     /// let settings = JsonWriterSettings {
     ///     indentation: "  ".to_string(),
+    ///     ..Default::default()
     /// };
-    /// assert_eq!(map.to_json_with_settings(settings).len(), 147);
+    /// assert_eq!(map.to_json_with_settings(settings).len(), 134);
     /// ```
     pub fn to_json_with_settings(&self, settings: JsonWriterSettings) -> String {
-        JsonWriter::new(settings).format(self)
+        JsonWriter::new(&settings).format(self)
+    }
+
+    /// 计算`to_json_with_settings`写出的字节数, 而不构建Json文本. 可以用于在发送前检查大小限制.
+    ///
+    /// 字符串的转义, 缩进, 数字的格式和各项过滤设置都计算在内, 设置了`filter`时它会被调用.
+    /// 多行格式下设置了`max_line_width`时, 是否写在同一行也按各成员的宽度计算, 同样不构建文本.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{JsonWriterSettings, Map};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":[1,2.5,"é\n"],"b":null}"#).unwrap();
+    /// let settings = JsonWriterSettings {
+    ///     indentation: "  ".to_string(),
+    ///     skip_nulls: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(map.json_len(&settings), 43);
+    /// assert_eq!(map.to_json_with_settings(settings).len(), 43);
+    /// ```
+    pub fn json_len(&self, settings: &JsonWriterSettings) -> usize {
+        JsonWriter::new(settings).json_len(self)
     }
 
     /// 将Json解析，并赋值给自身, 带有默认设置.
@@ -147,7 +342,8 @@ impl Map {
     ///
     /// let mut map = Map::new();
     /// let settings = JsonReaderSettings {
-    ///     recursion_limit: 2
+    ///     recursion_limit: 2,
+    ///     ..Default::default()
     /// };
     ///
     /// if let Err(e) = map.merge_with_settings(json, settings) {
@@ -161,6 +357,180 @@ impl Map {
     ) -> Result<(), String> {
         JsonReader::new(settings).parse(self, json)
     }
+
+    /// 将自身编码为MessagePack, 整数, 浮点数和长度总是使用最短的编码.
+    ///
+    /// MessagePack的长度最多为32位, 字符串, 数组或映射超过这个长度时返回错误.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":[1,-1,1.5]}"#).unwrap();
+    ///
+    /// let bytes = map.to_msgpack().unwrap();
+    /// assert_eq!(bytes, [0x81, 0xa1, b'a', 0x93, 0x01, 0xff, 0xca, 0x3f, 0xc0, 0x00, 0x00]);
+    /// assert!(Map::from_msgpack(&bytes).unwrap() == map);
+    /// ```
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, String> {
+        MsgpackWriter.format_map(self)
+    }
+
+    /// 解码MessagePack, 带有默认设置. 最外层必须是映射.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Map, String> {
+        Map::from_msgpack_with_settings(bytes, MsgpackReaderSettings::default())
+    }
+
+    /// 解码MessagePack, 自定义解码设置. 最外层必须是映射.
+    pub fn from_msgpack_with_settings(
+        bytes: &[u8],
+        settings: MsgpackReaderSettings,
+    ) -> Result<Map, String> {
+        match MsgpackReader::new(settings).parse(bytes)? {
+            Value::Object(ref mut map) => Ok(mem::take(map)),
+            _ => Err(String::from("Expected a MessagePack map")),
+        }
+    }
+
+    /// 解析TOML (v1.0.0). 日期和时间在`Value`中没有对应的类型, 按原文转换为字符串.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let toml = r#"
+    /// name = "app"
+    ///
+    /// [server]
+    /// port = 8080
+    /// started = 1979-05-27T07:32:00Z
+    /// "#;
+    ///
+    /// let map = Map::from_toml(toml).unwrap();
+    /// assert_eq!(map.pointer("/server/port").unwrap().as_i64(), Some(8080));
+    /// assert_eq!(
+    ///     map.pointer("/server/started").unwrap().as_string(),
+    ///     Some("1979-05-27T07:32:00Z")
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Map::from_toml("a = 1\na = 2").err().unwrap(),
+    ///     "Duplicate key `a` at 2:1"
+    /// );
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Map, String> {
+        TomlReader::new(toml).parse()
+    }
+
+    /// 将自身转换为TOML, 键按字典序排列.
+    ///
+    /// TOML无法表示`Null`, 也不能在一个数组中同时包含对象和其它值, 遇到时返回错误.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"name":"app","server":{"port":8080}}"#).unwrap();
+    /// assert_eq!(map.to_toml().unwrap(), "name = \"app\"\n\n[server]\nport = 8080\n");
+    ///
+    /// map.merge(r#"{"name":null}"#).unwrap();
+    /// assert_eq!(map.to_toml().err().unwrap(), "TOML cannot represent null at /name");
+    /// ```
+    pub fn to_toml(&self) -> Result<String, String> {
+        TomlWriter.format(self)
+    }
+
+    /// 解析URL查询字符串或`application/x-www-form-urlencoded`表单, 带有默认设置.
+    ///
+    /// 参数名称支持`a[b][c]`, `list[]`和`list[0]`形式的嵌套, 重复的参数合并为数组. 所有的值都是`String`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let map = Map::from_query_string("a[b][c]=1&list[]=x&list[]=y").unwrap();
+    /// assert_eq!(map.pointer("/a/b/c").unwrap().as_string(), Some("1"));
+    /// assert_eq!(map.pointer("/list/1").unwrap().as_string(), Some("y"));
+    /// ```
+    pub fn from_query_string(query: &str) -> Result<Map, String> {
+        Map::from_query_string_with_settings(query, QueryStringSettings::default())
+    }
+
+    /// 解析URL查询字符串, 自定义设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{KeyNesting, Map, QueryStringSettings};
+    ///
+    /// let settings = QueryStringSettings {
+    ///     nesting: KeyNesting::Dots,
+    ///     infer_types: true,
+    ///     ..Default::default()
+    /// };
+    /// let map = Map::from_query_string_with_settings("page.size=20&page.all=false", settings).unwrap();
+    /// assert_eq!(map.pointer("/page/size").unwrap().as_i64(), Some(20));
+    /// assert_eq!(map.pointer("/page/all").unwrap().as_bool(), Some(false));
+    /// ```
+    pub fn from_query_string_with_settings(
+        query: &str,
+        settings: QueryStringSettings,
+    ) -> Result<Map, String> {
+        QueryStringReader::new(settings).parse(query)
+    }
+
+    /// 将自身转换为URL查询字符串, 带有默认设置. 成员按名称的字典序排列, 空的对象和数组不输出, `Null`输出为空值.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":{"b":"x y"},"list":[1,2]}"#).unwrap();
+    /// assert_eq!(map.to_query_string().unwrap(), "a[b]=x+y&list[]=1&list[]=2");
+    /// ```
+    pub fn to_query_string(&self) -> Result<String, String> {
+        self.to_query_string_with_settings(QueryStringSettings::default())
+    }
+
+    /// 将自身转换为URL查询字符串, 自定义设置. `KeyNesting::None`时遇到对象或嵌套的数组返回错误.
+    pub fn to_query_string_with_settings(
+        &self,
+        settings: QueryStringSettings,
+    ) -> Result<String, String> {
+        QueryStringWriter::new(settings).format(self)
+    }
+
+    /// 读取CSV (RFC 4180), 带有默认设置. 第一条记录为列名, 之后的每条记录转换为一个`Map`,
+    /// 以`.`连接的列名还原为嵌套的对象, 所有的值都是`String`. 以流的方式读取见`read_csv`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Map;
+    ///
+    /// let maps = Map::from_csv("id,user.name\r\n1,Tom\r\n2,\"Smith, J\"\r\n").unwrap();
+    /// assert_eq!(maps.len(), 2);
+    /// assert_eq!(maps[1].pointer("/user/name").unwrap().as_string(), Some("Smith, J"));
+    /// ```
+    pub fn from_csv(csv: &str) -> Result<Vec<Map>, String> {
+        Map::from_csv_with_settings(csv, CsvReaderSettings::default())
+    }
+
+    /// 读取CSV, 自定义设置.
+    pub fn from_csv_with_settings(
+        csv: &str,
+        settings: CsvReaderSettings,
+    ) -> Result<Vec<Map>, String> {
+        csv::read_csv(csv.as_bytes(), settings).collect()
+    }
 }
 
 // 通过 Deref 暴露内部方法
@@ -0,0 +1,83 @@
+use crate::json_reader::JsonReader;
+use crate::{JsonReaderSettings, Map, ValueRef};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+/// 借用Json文本的只读`Map`.
+///
+/// 没有转义字符的名称和字符串直接借用被解析的文本, 不会分配新的内存, 适合只读取少量字段的场景.
+/// 需要修改或长期保存时, 可以通过`into_owned`转换为`Map`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapRef<'a>(HashMap<Cow<'a, str>, ValueRef<'a>>);
+
+impl<'a> MapRef<'a> {
+    /// 创建一个空的`MapRef`.
+    pub fn new() -> Self {
+        MapRef(HashMap::new())
+    }
+
+    /// 解析Json, 带有默认设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use mapjson::{MapRef, ValueRef};
+    ///
+    /// let json = r#"{"a":"hello","b":"line\nfeed"}"#;
+    /// let map = MapRef::parse(json).unwrap();
+    ///
+    /// assert!(matches!(map["a"], ValueRef::String(Cow::Borrowed("hello"))));
+    /// assert!(matches!(map["b"], ValueRef::String(Cow::Owned(_))));
+    /// ```
+    pub fn parse(json: &'a str) -> Result<Self, String> {
+        Self::parse_with_settings(json, JsonReaderSettings::default())
+    }
+
+    /// 解析Json, 自定义设置.
+    pub fn parse_with_settings(
+        json: &'a str,
+        settings: JsonReaderSettings,
+    ) -> Result<Self, String> {
+        JsonReader::new(settings).parse_borrowed(json)
+    }
+
+    /// 转换为拥有所有权的`Map`, 复制所有借用的名称和字符串.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::MapRef;
+    ///
+    /// let json = r#"{"a":null,"b":true,"c":3.14,"d":"hello","e":["hi","china"],"f":{"a1":11,"b1":22}}"#;
+    ///
+    /// let map = MapRef::parse(json).unwrap().into_owned();
+    /// assert_eq!(map.len(), 6);
+    /// assert_eq!(map["d"].as_string().unwrap(), "hello");
+    /// ```
+    pub fn into_owned(mut self) -> Map {
+        let mut map = Map::new();
+        map.reserve(self.len());
+        for (key, value) in self.drain() {
+            map.insert(key.into_owned(), value.into_owned());
+        }
+        map
+    }
+}
+
+// 通过 Deref 暴露内部方法
+impl<'a> Deref for MapRef<'a> {
+    type Target = HashMap<Cow<'a, str>, ValueRef<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// 通过 DerefMut 暴露内部方法
+impl DerefMut for MapRef<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
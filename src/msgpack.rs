@@ -0,0 +1,806 @@
+use crate::cbor::encode_base64;
+use crate::{ByteStringPolicy, Map, MapKeyPolicy, NonFinitePolicy, Value};
+use std::collections::hash_map;
+use std::{mem, slice};
+
+/// 将`Value`转换成MessagePack的转换器. 整数, 浮点数和各类长度总是使用能无损表示的最短编码.
+pub struct MsgpackWriter;
+
+impl MsgpackWriter {
+    // 字符串, 数组或映射的长度超过32位时返回错误, MessagePack无法表示.
+    pub fn format(&self, value: &Value) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        let entries = self.write_value(&mut out, value)?;
+        self.write_nested(&mut out, entries)?;
+        Ok(out)
+    }
+
+    pub fn format_map(&self, map: &Map) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        write_length(&mut out, map.len(), 0x80, 0xde)?;
+        self.write_nested(&mut out, Some(Entries::Struct(map.iter())))?;
+        Ok(out)
+    }
+
+    // 写入容器的全部成员. 嵌套的对象和数组通过显式的栈写入, 而不是递归, 因此写入深度不受线程栈大小约束.
+    fn write_nested<'a>(
+        &self,
+        out: &mut Vec<u8>,
+        entries: Option<Entries<'a>>,
+    ) -> Result<(), String> {
+        let mut stack: Vec<Entries<'a>> = entries.into_iter().collect();
+        while let Some(entries) = stack.last_mut() {
+            let next = match *entries {
+                Entries::Struct(ref mut iter) => match iter.next() {
+                    Some((key, val)) => {
+                        write_str(out, key)?;
+                        Some(val)
+                    }
+                    None => None,
+                },
+                Entries::Vec(ref mut iter) => iter.next(),
+            };
+
+            match next {
+                Some(val) => {
+                    if let Some(nested) = self.write_value(out, val)? {
+                        stack.push(nested);
+                    }
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // 写入一个值. 对于对象和数组, 只写入头部, 并返回需要继续写入的成员.
+    fn write_value<'a>(
+        &self,
+        out: &mut Vec<u8>,
+        value: &'a Value,
+    ) -> Result<Option<Entries<'a>>, String> {
+        match *value {
+            Value::Null => out.push(NIL),
+            Value::Bool(false) => out.push(FALSE),
+            Value::Bool(true) => out.push(TRUE),
+            Value::I64(n) => write_int(out, n),
+            Value::F64(n) => {
+                // 单精度可以精确表示时使用单精度, NaN也使用单精度
+                let single = n as f32;
+                if single as f64 == n || n.is_nan() {
+                    out.push(FLOAT32);
+                    out.extend_from_slice(&single.to_be_bytes());
+                } else {
+                    out.push(FLOAT64);
+                    out.extend_from_slice(&n.to_be_bytes());
+                }
+            }
+            Value::String(ref s) => write_str(out, s)?,
+            Value::Vec(ref vec) => {
+                write_length(out, vec.len(), 0x90, 0xdc)?;
+                return Ok(Some(Entries::Vec(vec.iter())));
+            }
+            Value::Object(ref obj) => {
+                write_length(out, obj.len(), 0x80, 0xde)?;
+                return Ok(Some(Entries::Struct(obj.iter())));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// 写入栈中尚未写完的对象或数组的成员.
+enum Entries<'a> {
+    Struct(hash_map::Iter<'a, String, Value>),
+    Vec(slice::Iter<'a, Value>),
+}
+
+fn write_int(out: &mut Vec<u8>, n: i64) {
+    match n {
+        0..=0x7f => out.push(n as u8),
+        -32..0 => out.push(n as i8 as u8),
+        0x80..=0xff => out.extend_from_slice(&[UINT8, n as u8]),
+        0x100..=0xffff => {
+            out.push(UINT16);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(UINT32);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        0x1_0000_0000.. => {
+            out.push(UINT64);
+            out.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+        -0x80..-32 => out.extend_from_slice(&[INT8, n as u8]),
+        -0x8000..-0x80 => {
+            out.push(INT16);
+            out.extend_from_slice(&(n as i16).to_be_bytes());
+        }
+        -0x8000_0000..-0x8000 => {
+            out.push(INT32);
+            out.extend_from_slice(&(n as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(INT64);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) -> Result<(), String> {
+    let len = s.len();
+    match len {
+        0..32 => out.push(0xa0 | len as u8),
+        32..0x100 => out.extend_from_slice(&[STR8, len as u8]),
+        0x100..0x1_0000 => {
+            out.push(STR16);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(STR32);
+            out.extend_from_slice(&length32(len, "a string")?.to_be_bytes());
+        }
+    }
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+// 写入数组或映射的头部. 少于16个成员时使用`fix`格式, 否则使用16位或32位长度, `marker16`之后紧跟32位的标记.
+fn write_length(out: &mut Vec<u8>, len: usize, fix: u8, marker16: u8) -> Result<(), String> {
+    match len {
+        0..16 => out.push(fix | len as u8),
+        16..0x1_0000 => {
+            out.push(marker16);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            let kind = if fix == 0x90 { "an array" } else { "a map" };
+            let len = length32(len, kind)?;
+            out.push(marker16 + 1);
+            out.extend_from_slice(&len.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+// MessagePack的长度最多为32位.
+fn length32(len: usize, kind: &str) -> Result<u32, String> {
+    u32::try_from(len).map_err(|_| {
+        format!(
+            "MessagePack cannot represent {} of length {}, the limit is {}",
+            kind,
+            len,
+            u32::MAX
+        )
+    })
+}
+
+/// 将MessagePack转换成`Value`的转换器.
+///
+/// 解析过程使用显式的栈而不是递归, 因此嵌套深度只受`MsgpackReaderSettings`中的限制约束.
+pub struct MsgpackReader {
+    settings: MsgpackReaderSettings,
+}
+
+// 解析栈中尚未结束的容器. `remaining`为剩余的成员数.
+enum Frame {
+    Array {
+        vec: Vec<Value>,
+        remaining: usize,
+    },
+    Map {
+        map: Map,
+        remaining: usize,
+        key: Option<String>, // 已读取名称, 正在等待其值的成员.
+    },
+}
+
+// 正在读取的输入.
+struct Input<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+// 一个数据项的头部.
+enum Head {
+    Value(Value),
+    Str(usize),
+    Bin(usize),
+    Ext(usize),
+    Array(usize),
+    Map(usize),
+}
+
+impl MsgpackReader {
+    pub fn new(settings: MsgpackReaderSettings) -> Self {
+        MsgpackReader { settings }
+    }
+
+    pub fn parse(&self, bytes: &[u8]) -> Result<Value, String> {
+        if bytes.len() > self.settings.max_input_length {
+            return Err(format!(
+                "The set input length limit is exceeded: {}",
+                self.settings.max_input_length
+            ));
+        }
+
+        let mut input = Input { bytes, pos: 0 };
+        let mut stack = Vec::new();
+        let mut nodes = 0;
+
+        loop {
+            let expecting_key = matches!(stack.last(), Some(Frame::Map { key: None, .. }));
+            let head = input.read_head()?;
+            if expecting_key
+                && !matches!(head, Head::Str(_))
+                && self.settings.map_keys == MapKeyPolicy::Reject
+            {
+                return Err(String::from("Unsupported MessagePack map key"));
+            }
+
+            nodes += 1;
+            if nodes > self.settings.max_nodes {
+                return Err(format!(
+                    "The set node limit is exceeded: {}",
+                    self.settings.max_nodes
+                ));
+            }
+
+            let value = match head {
+                Head::Value(Value::F64(n)) if !n.is_finite() => match self.settings.non_finite {
+                    NonFinitePolicy::Reject => {
+                        return Err(format!("Unsupported non-finite MessagePack float: {}", n));
+                    }
+                    NonFinitePolicy::Null => Value::Null,
+                },
+                Head::Value(value) => value,
+                Head::Str(len) => {
+                    self.check_string_length(len)?;
+                    let text = std::str::from_utf8(input.take(len)?)
+                        .map_err(|_| String::from("Invalid UTF-8 in MessagePack str"))?;
+                    Value::String(text.to_string())
+                }
+                Head::Bin(len) => {
+                    self.check_string_length(len)?;
+                    let bytes = input.take(len)?;
+                    match self.settings.binary {
+                        ByteStringPolicy::Reject => {
+                            return Err(String::from("Unsupported MessagePack bin"));
+                        }
+                        ByteStringPolicy::Base64 => Value::String(encode_base64(bytes)),
+                        // 展开后与数组相同, 受相同的长度限制.
+                        ByteStringPolicy::Array if len > self.settings.max_array_length => {
+                            return Err(limit_error(false, self.settings.max_array_length));
+                        }
+                        ByteStringPolicy::Array => {
+                            Value::Vec(bytes.iter().map(|&b| Value::I64(b as i64)).collect())
+                        }
+                    }
+                }
+                Head::Ext(len) => {
+                    let [ext_type] = input.take_array::<1>()?;
+                    let ext_type = ext_type as i8;
+                    self.check_string_length(len)?;
+                    let data = input.take(len)?;
+                    match self.settings.extensions {
+                        ExtensionPolicy::Reject => {
+                            return Err(format!("Unsupported MessagePack ext type: {}", ext_type));
+                        }
+                        ExtensionPolicy::Object => {
+                            let mut obj = Map::new();
+                            obj.insert("type".to_string(), Value::I64(ext_type as i64));
+                            obj.insert("data".to_string(), Value::String(encode_base64(data)));
+                            Value::Object(obj)
+                        }
+                    }
+                }
+                Head::Array(0) => Value::Vec(Vec::new()),
+                Head::Map(0) => Value::Object(Map::new()),
+                Head::Array(len) | Head::Map(len) => {
+                    let is_map = matches!(head, Head::Map(_));
+                    let limit = if is_map {
+                        self.settings.max_object_members
+                    } else {
+                        self.settings.max_array_length
+                    };
+                    if len > limit {
+                        return Err(limit_error(is_map, limit));
+                    }
+                    // 每个成员至少占用一个字节, 声明的长度超过剩余输入时直接报错, 不会按照不可信的长度分配内存.
+                    if len > input.remaining() {
+                        return Err(String::from("Unexpected end of MessagePack input"));
+                    }
+                    if stack.len() >= self.settings.depth_limit {
+                        return Err(format!(
+                            "The set depth limit is exceeded: {}",
+                            self.settings.depth_limit
+                        ));
+                    }
+
+                    stack.push(if is_map {
+                        Frame::Map {
+                            map: Map::new(),
+                            remaining: len,
+                            key: None,
+                        }
+                    } else {
+                        Frame::Array {
+                            vec: Vec::with_capacity(len),
+                            remaining: len,
+                        }
+                    });
+                    continue;
+                }
+            };
+
+            if let Some(root) = self.complete(&mut stack, value)? {
+                if input.pos != input.bytes.len() {
+                    return Err(String::from(
+                        "Unexpected data after the end of MessagePack value",
+                    ));
+                }
+                return Ok(root);
+            }
+        }
+    }
+
+    // 将读取完的值加入栈顶的容器. 容器因此结束时, 继续将它加入上一层容器. 栈为空时返回最外层的值.
+    fn complete(&self, stack: &mut Vec<Frame>, mut value: Value) -> Result<Option<Value>, String> {
+        loop {
+            let remaining = match stack.last_mut() {
+                None => return Ok(Some(value)),
+                Some(Frame::Array { vec, remaining }) => {
+                    vec.push(value);
+                    *remaining -= 1;
+                    *remaining
+                }
+                Some(Frame::Map {
+                    map,
+                    remaining,
+                    key,
+                }) => match key.take() {
+                    None => {
+                        *key = Some(self.map_key(value)?);
+                        return Ok(None);
+                    }
+                    Some(name) => {
+                        map.insert(name, value);
+                        *remaining -= 1;
+                        *remaining
+                    }
+                },
+            };
+            if remaining > 0 {
+                return Ok(None);
+            }
+
+            value = match stack.pop() {
+                Some(Frame::Array { vec, .. }) => Value::Vec(vec),
+                Some(Frame::Map { map, .. }) => Value::Object(map),
+                None => return Ok(None),
+            };
+        }
+    }
+
+    // 将读取的值转换为成员名称. 不是字符串的名称只有在`MapKeyPolicy::Stringify`时才会读到这里.
+    fn map_key(&self, mut value: Value) -> Result<String, String> {
+        if let Value::String(ref mut s) = value {
+            return Ok(mem::take(s));
+        }
+
+        match self.settings.map_keys {
+            MapKeyPolicy::Reject => Err(String::from("Unsupported MessagePack map key")),
+            MapKeyPolicy::Stringify => Ok(match value {
+                Value::I64(n) => n.to_string(),
+                _ => value.to_json(),
+            }),
+        }
+    }
+
+    fn check_string_length(&self, len: usize) -> Result<(), String> {
+        if len > self.settings.max_string_length {
+            Err(format!(
+                "The set string length limit is exceeded: {}",
+                self.settings.max_string_length
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn limit_error(is_map: bool, limit: usize) -> String {
+    if is_map {
+        format!("The set object member limit is exceeded: {}", limit)
+    } else {
+        format!("The set array length limit is exceeded: {}", limit)
+    }
+}
+
+impl<'a> Input<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < len {
+            return Err(String::from("Unexpected end of MessagePack input"));
+        }
+
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> Result<usize, String> {
+        Ok(u8::from_be_bytes(self.take_array()?) as usize)
+    }
+
+    fn read_u16(&mut self) -> Result<usize, String> {
+        Ok(u16::from_be_bytes(self.take_array()?) as usize)
+    }
+
+    fn read_u32(&mut self) -> Result<usize, String> {
+        Ok(u32::from_be_bytes(self.take_array()?) as usize)
+    }
+
+    fn read_head(&mut self) -> Result<Head, String> {
+        let [marker] = self.take_array::<1>()?;
+        let head = match marker {
+            0x00..=0x7f => Head::Value(Value::I64(marker as i64)),
+            0x80..=0x8f => Head::Map((marker & 0x0f) as usize),
+            0x90..=0x9f => Head::Array((marker & 0x0f) as usize),
+            0xa0..=0xbf => Head::Str((marker & 0x1f) as usize),
+            0xe0..=0xff => Head::Value(Value::I64(marker as i8 as i64)),
+            NIL => Head::Value(Value::Null),
+            FALSE => Head::Value(Value::Bool(false)),
+            TRUE => Head::Value(Value::Bool(true)),
+            0xc4 => Head::Bin(self.read_u8()?),
+            0xc5 => Head::Bin(self.read_u16()?),
+            0xc6 => Head::Bin(self.read_u32()?),
+            0xc7 => Head::Ext(self.read_u8()?),
+            0xc8 => Head::Ext(self.read_u16()?),
+            0xc9 => Head::Ext(self.read_u32()?),
+            FLOAT32 => Head::Value(Value::F64(f32::from_be_bytes(self.take_array()?) as f64)),
+            FLOAT64 => Head::Value(Value::F64(f64::from_be_bytes(self.take_array()?))),
+            UINT8 => Head::Value(Value::I64(self.read_u8()? as i64)),
+            UINT16 => Head::Value(Value::I64(self.read_u16()? as i64)),
+            UINT32 => Head::Value(Value::I64(self.read_u32()? as i64)),
+            UINT64 => {
+                let n = u64::from_be_bytes(self.take_array()?);
+                Head::Value(Value::I64(i64::try_from(n).map_err(|_| {
+                    format!("MessagePack integer out of range for I64: {}", n)
+                })?))
+            }
+            INT8 => Head::Value(Value::I64(i8::from_be_bytes(self.take_array()?) as i64)),
+            INT16 => Head::Value(Value::I64(i16::from_be_bytes(self.take_array()?) as i64)),
+            INT32 => Head::Value(Value::I64(i32::from_be_bytes(self.take_array()?) as i64)),
+            INT64 => Head::Value(Value::I64(i64::from_be_bytes(self.take_array()?))),
+            0xd4..=0xd8 => Head::Ext(1 << (marker - 0xd4)), // fixext 1/2/4/8/16
+            STR8 => Head::Str(self.read_u8()?),
+            STR16 => Head::Str(self.read_u16()?),
+            STR32 => Head::Str(self.read_u32()?),
+            0xdc => Head::Array(self.read_u16()?),
+            0xdd => Head::Array(self.read_u32()?),
+            0xde => Head::Map(self.read_u16()?),
+            0xdf => Head::Map(self.read_u32()?),
+            _ => return Err(format!("Invalid MessagePack marker: 0x{:02x}", marker)),
+        };
+
+        Ok(head)
+    }
+}
+
+const NIL: u8 = 0xc0;
+const FALSE: u8 = 0xc2;
+const TRUE: u8 = 0xc3;
+const FLOAT32: u8 = 0xca;
+const FLOAT64: u8 = 0xcb;
+const UINT8: u8 = 0xcc;
+const UINT16: u8 = 0xcd;
+const UINT32: u8 = 0xce;
+const UINT64: u8 = 0xcf;
+const INT8: u8 = 0xd0;
+const INT16: u8 = 0xd1;
+const INT32: u8 = 0xd2;
+const INT64: u8 = 0xd3;
+const STR8: u8 = 0xd9;
+const STR16: u8 = 0xda;
+const STR32: u8 = 0xdb;
+
+/// 读取MessagePack时使用的设置. 各项限制与`JsonReaderSettings`中的同名限制含义相同.
+pub struct MsgpackReaderSettings {
+    pub depth_limit: usize,          // 对象与数组的最大嵌套深度.
+    pub max_input_length: usize,     // 输入的最大长度(字节).
+    pub max_string_length: usize,    // 单个str, bin或ext(包括成员名称)的最大长度(字节).
+    pub max_array_length: usize,     // 单个数组(包括展开为数组的bin)的最大元素个数.
+    pub max_object_members: usize,   // 单个对象的最大成员个数.
+    pub max_nodes: usize,            // 整个文档中值(包括对象与数组本身)的最大个数.
+    pub binary: ByteStringPolicy,    // 遇到bin时的处理方式.
+    pub extensions: ExtensionPolicy, // 遇到ext时的处理方式.
+    pub map_keys: MapKeyPolicy,      // 遇到不是str的成员名称时的处理方式.
+    pub non_finite: NonFinitePolicy, // 遇到Json不能表示的无穷大和NaN时的处理方式.
+}
+
+impl Default for MsgpackReaderSettings {
+    fn default() -> Self {
+        MsgpackReaderSettings {
+            depth_limit: 128,
+            max_input_length: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_members: usize::MAX,
+            max_nodes: usize::MAX,
+            binary: ByteStringPolicy::Reject,
+            extensions: ExtensionPolicy::Reject,
+            map_keys: MapKeyPolicy::Reject,
+            non_finite: NonFinitePolicy::Reject,
+        }
+    }
+}
+
+/// 读取MessagePack扩展类型(ext, 包括时间戳)时的处理方式, `Value`中没有对应的类型.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtensionPolicy {
+    /// 返回错误.
+    Reject,
+    /// 转换为`{"type": 类型编号, "data": Base64编码的数据}`形式的对象.
+    Object,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::msgpack::write_length;
+    use crate::test_util::{Rng, hex, unhex};
+    use crate::{
+        ByteStringPolicy, ExtensionPolicy, JsonWriterSettings, Map, MapKeyPolicy,
+        MsgpackReaderSettings, NonFinitePolicy, Value,
+    };
+
+    #[test]
+    fn smallest_encodings() {
+        let cases: &[(Value, &str)] = &[
+            (Value::I64(0), "00"),
+            (Value::I64(127), "7f"),
+            (Value::I64(128), "cc80"),
+            (Value::I64(256), "cd0100"),
+            (Value::I64(65536), "ce00010000"),
+            (Value::I64(1 << 32), "cf0000000100000000"),
+            (Value::I64(-1), "ff"),
+            (Value::I64(-32), "e0"),
+            (Value::I64(-33), "d0df"),
+            (Value::I64(-129), "d1ff7f"),
+            (Value::I64(-32769), "d2ffff7fff"),
+            (Value::I64(i64::MIN), "d38000000000000000"),
+            (Value::F64(1.5), "ca3fc00000"),
+            (Value::F64(1.1), "cb3ff199999999999a"),
+            (Value::Null, "c0"),
+            (Value::Bool(true), "c3"),
+            (
+                Value::String("a".repeat(31)),
+                &format!("bf{}", "61".repeat(31)),
+            ),
+            (
+                Value::String("a".repeat(32)),
+                &format!("d920{}", "61".repeat(32)),
+            ),
+            (
+                Value::String("a".repeat(256)),
+                &format!("da0100{}", "61".repeat(256)),
+            ),
+            (
+                Value::Vec(vec![Value::Null; 15]),
+                &format!("9f{}", "c0".repeat(15)),
+            ),
+            (
+                Value::Vec(vec![Value::Null; 16]),
+                &format!("dc0010{}", "c0".repeat(16)),
+            ),
+        ];
+
+        for (value, expected) in cases {
+            let bytes = value.to_msgpack().unwrap();
+            assert_eq!(hex(&bytes), *expected);
+            assert!(
+                Value::from_msgpack(&bytes).unwrap() == *value,
+                "{}",
+                expected
+            );
+        }
+
+        let long = Value::String("a".repeat(0x1_0000)).to_msgpack().unwrap();
+        assert_eq!(hex(&long[..5]), "db00010000");
+        let mut map = Map::new();
+        for i in 0..16 {
+            map.insert(i.to_string(), Value::Null);
+        }
+        assert_eq!(hex(&map.to_msgpack().unwrap()[..3]), "de0010");
+
+        // 超过32位的长度无法表示. 只检查头部, 不实际分配这么长的值.
+        if let Ok(len) = usize::try_from(1u64 << 32) {
+            assert_eq!(
+                write_length(&mut Vec::new(), len, 0x90, 0xdc).unwrap_err(),
+                "MessagePack cannot represent an array of length 4294967296, the limit is 4294967295"
+            );
+            assert!(write_length(&mut Vec::new(), len - 1, 0x80, 0xde).is_ok());
+        }
+    }
+
+    #[test]
+    fn bin_ext_and_keys() {
+        // {"b": bin8 [1, 2], "e": fixext1 type 5 [0xff], 1: nil}
+        let bytes = unhex("83a162c4020102a165d405ff01c0");
+        assert_eq!(
+            parse_err(&bytes, MsgpackReaderSettings::default()),
+            "Unsupported MessagePack bin"
+        );
+
+        let settings = MsgpackReaderSettings {
+            binary: ByteStringPolicy::Array,
+            extensions: ExtensionPolicy::Object,
+            map_keys: MapKeyPolicy::Stringify,
+            ..Default::default()
+        };
+        let value = Value::from_msgpack_with_settings(&bytes, settings).unwrap();
+        let settings = JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            value.to_json_with_settings(settings),
+            r#"{"1":null,"b":[1,2],"e":{"data":"/w==","type":5}}"#
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        let cases = [
+            ("c1", "Invalid MessagePack marker: 0xc1"),
+            ("92c0", "Unexpected end of MessagePack input"),
+            ("ddffffffff", "Unexpected end of MessagePack input"),
+            ("a2c328", "Invalid UTF-8 in MessagePack str"),
+            (
+                "cfffffffffffffffff",
+                "MessagePack integer out of range for I64: 18446744073709551615",
+            ),
+            ("c0c0", "Unexpected data after the end of MessagePack value"),
+            (
+                "d7ff0000000000000000",
+                "Unsupported MessagePack ext type: -1",
+            ),
+            (
+                "ca7f800000",
+                "Unsupported non-finite MessagePack float: inf",
+            ),
+            (
+                "cbfff0000000000000",
+                "Unsupported non-finite MessagePack float: -inf",
+            ),
+            (
+                "81a161cb7ff8000000000000",
+                "Unsupported non-finite MessagePack float: NaN",
+            ),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(
+                parse_err(&unhex(bytes), MsgpackReaderSettings::default()),
+                expected
+            );
+        }
+
+        let settings = MsgpackReaderSettings {
+            depth_limit: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_err(&unhex("91919100"), settings),
+            "The set depth limit is exceeded: 2"
+        );
+        assert_eq!(
+            Map::from_msgpack(&unhex("90")).err().unwrap(),
+            "Expected a MessagePack map"
+        );
+
+        let settings = MsgpackReaderSettings {
+            binary: ByteStringPolicy::Array,
+            max_array_length: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_err(&unhex("c403010203"), settings),
+            "The set array length limit is exceeded: 2"
+        );
+
+        // [inf, NaN, 1.5]
+        let settings = MsgpackReaderSettings {
+            non_finite: NonFinitePolicy::Null,
+            ..Default::default()
+        };
+        let bytes = unhex("93ca7f800000cb7ff8000000000000ca3fc00000");
+        let value = Value::from_msgpack_with_settings(&bytes, settings).unwrap();
+        assert_eq!(value.to_json(), "[null,null,1.5]");
+    }
+
+    #[test]
+    fn json_round_trip_is_lossless() {
+        let mut rng = Rng(0x853c_49e6_748f_ea9b);
+        let settings = || JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        };
+
+        for _ in 0..500 {
+            let json = random_object(&mut rng, 0);
+            let mut map = Map::new();
+            map.merge(json.as_str()).unwrap();
+
+            let bytes = map.to_msgpack().unwrap();
+            let decoded = Map::from_msgpack(&bytes).unwrap();
+            assert!(decoded == map, "{}", json);
+            assert_eq!(
+                decoded.to_json_with_settings(settings()),
+                map.to_json_with_settings(settings())
+            );
+        }
+    }
+
+    // 生成随机的Json对象文本.
+    fn random_object(rng: &mut Rng, depth: usize) -> String {
+        let len = rng.below(if depth > 3 { 3 } else { 8 });
+        let members: Vec<String> = (0..len)
+            .map(|i| {
+                format!(
+                    "\"{}{}\":{}",
+                    random_text(rng),
+                    i,
+                    random_value(rng, depth + 1)
+                )
+            })
+            .collect();
+        format!("{{{}}}", members.join(","))
+    }
+
+    fn random_value(rng: &mut Rng, depth: usize) -> String {
+        match rng.below(if depth > 3 { 6 } else { 8 }) {
+            0 => "null".to_string(),
+            1 => (rng.below(2) == 0).to_string(),
+            2 => (rng.next_u64() as i64 >> rng.below(64)).to_string(),
+            3 => format!("{:e}", f64::from_bits(rng.next_u64() >> 2) * 1e-300),
+            4 => format!("{}.{}", rng.below(1000) as i64 - 500, rng.below(1000)),
+            5 => format!("\"{}\"", random_text(rng)),
+            6 => {
+                let len = rng.below(40);
+                let items: Vec<String> = (0..len).map(|_| random_value(rng, depth + 1)).collect();
+                format!("[{}]", items.join(","))
+            }
+            _ => random_object(rng, depth),
+        }
+    }
+
+    fn random_text(rng: &mut Rng) -> String {
+        const CHARS: [&str; 8] = ["a", "Z", "0", " ", "\\n", "\\\"", "é", "😀"];
+        let max = if rng.below(10) == 0 { 300 } else { 12 };
+        let len = rng.below(max);
+        (0..len)
+            .map(|_| CHARS[rng.below(CHARS.len() as u64) as usize])
+            .collect()
+    }
+
+    fn parse_err(bytes: &[u8], settings: MsgpackReaderSettings) -> String {
+        match Value::from_msgpack_with_settings(bytes, settings) {
+            Ok(_) => panic!("Expected an Err for {}", hex(bytes)),
+            Err(e) => e,
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::Value;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// `Value`中数字的只读视图, 不区分`I64`与`F64`的存储方式.
+///
+/// 比较时按数学值进行, 因此`Number::I64(3) == Number::F64(3.0)`, 且`i64`的大数不会因为
+/// 转换为`f64`而丢失精度. `NaN`与任何数字都不相等, 也无法比较大小.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{Number, Value};
+///
+/// assert_eq!(Value::I64(3).as_number(), Value::F64(3.0).as_number());
+/// assert!(Number::I64(i64::MAX) > Number::F64(9.2e18));
+/// assert!(Number::I64(i64::MAX) < Number::F64(9223372036854775808.0));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub enum Number {
+    I64(i64),
+    F64(f64),
+}
+
+/// 数值转换失败的原因.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NumberError {
+    /// 值不是数字.
+    NotANumber,
+    /// 数字超出了目标类型的范围.
+    OutOfRange { value: Number, target: &'static str },
+    /// 数字在目标类型的范围内, 但无法精确表示(例如带有小数部分).
+    Inexact { value: Number, target: &'static str },
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberError::NotANumber => f.write_str("Value is not a number"),
+            NumberError::OutOfRange { value, target } => {
+                write!(f, "Number {} is out of range for {}", value, target)
+            }
+            NumberError::Inexact { value, target } => {
+                write!(
+                    f,
+                    "Number {} cannot be represented exactly as {}",
+                    value, target
+                )
+            }
+        }
+    }
+}
+
+impl Error for NumberError {}
+
+// 2^127, 绝对值小于它的整数值f64可以无损转换为i128.
+const I128_BOUND: f64 = 170141183460469231731687303715884105728.0;
+
+// 2^63, 绝对值小于它(或等于-2^63)的整数值f64可以无损转换为i64.
+const I64_BOUND: f64 = 9223372036854775808.0;
+
+impl Number {
+    pub fn is_i64(&self) -> bool {
+        matches!(self, Number::I64(_))
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self, Number::F64(_))
+    }
+
+    pub fn to_i64(self) -> Result<i64, NumberError> {
+        self.to_integer("i64")
+    }
+
+    pub fn to_u64(self) -> Result<u64, NumberError> {
+        self.to_integer("u64")
+    }
+
+    pub fn to_i32(self) -> Result<i32, NumberError> {
+        self.to_integer("i32")
+    }
+
+    pub fn to_u32(self) -> Result<u32, NumberError> {
+        self.to_integer("u32")
+    }
+
+    pub fn to_usize(self) -> Result<usize, NumberError> {
+        self.to_integer("usize")
+    }
+
+    /// 转换为`f64`, `I64`无法精确表示时返回`NumberError::Inexact`.
+    pub fn to_f64(self) -> Result<f64, NumberError> {
+        match self {
+            Number::F64(f) => Ok(f),
+            Number::I64(n) => {
+                let f = n as f64;
+                if f as i128 == n as i128 {
+                    Ok(f)
+                } else {
+                    Err(NumberError::Inexact {
+                        value: self,
+                        target: "f64",
+                    })
+                }
+            }
+        }
+    }
+
+    /// 转换为`f32`, 无法精确表示时返回`NumberError::Inexact`, 超出`f32`范围时返回
+    /// `NumberError::OutOfRange`. `NaN`与无穷大原样保留.
+    pub fn to_f32(self) -> Result<f32, NumberError> {
+        let exact = match self {
+            Number::F64(f) => {
+                let g = f as f32;
+                if f.is_nan() || g as f64 == f {
+                    return Ok(g);
+                }
+                g.is_finite()
+            }
+            Number::I64(n) => {
+                let g = n as f32;
+                if g as i128 == n as i128 {
+                    return Ok(g);
+                }
+                true
+            }
+        };
+        Err(if exact {
+            NumberError::Inexact {
+                value: self,
+                target: "f32",
+            }
+        } else {
+            NumberError::OutOfRange {
+                value: self,
+                target: "f32",
+            }
+        })
+    }
+
+    fn to_integer<T: TryFrom<i128>>(self, target: &'static str) -> Result<T, NumberError> {
+        let n = match self {
+            Number::I64(n) => n as i128,
+            Number::F64(f) if f.is_nan() => {
+                return Err(NumberError::Inexact {
+                    value: self,
+                    target,
+                });
+            }
+            Number::F64(f) if f.abs() >= I128_BOUND => {
+                return Err(NumberError::OutOfRange {
+                    value: self,
+                    target,
+                });
+            }
+            Number::F64(f) if f.fract() != 0.0 => {
+                return Err(NumberError::Inexact {
+                    value: self,
+                    target,
+                });
+            }
+            Number::F64(f) => f as i128,
+        };
+        T::try_from(n).map_err(|_| NumberError::OutOfRange {
+            value: self,
+            target,
+        })
+    }
+}
+
+// 按数学值比较i64与f64, f64为NaN时返回None.
+fn compare_i64_f64(n: i64, f: f64) -> Option<Ordering> {
+    if f.is_nan() {
+        None
+    } else if f >= I64_BOUND {
+        Some(Ordering::Less)
+    } else if f < -I64_BOUND {
+        Some(Ordering::Greater)
+    } else {
+        let t = f.trunc();
+        match n.cmp(&(t as i64)) {
+            Ordering::Equal => 0.0.partial_cmp(&(f - t)),
+            other => Some(other),
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (*self, *other) {
+            (Number::I64(a), Number::I64(b)) => Some(a.cmp(&b)),
+            (Number::F64(a), Number::F64(b)) => a.partial_cmp(&b),
+            (Number::I64(a), Number::F64(b)) => compare_i64_f64(a, b),
+            (Number::F64(a), Number::I64(b)) => compare_i64_f64(b, a).map(Ordering::reverse),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::I64(n) => write!(f, "{}", n),
+            Number::F64(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Number {
+        Number::I64(n)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Number {
+        Number::F64(n)
+    }
+}
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Value {
+        match n {
+            Number::I64(n) => Value::I64(n),
+            Number::F64(n) => Value::F64(n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(Number::I64(3), Number::F64(3.0));
+        assert_ne!(Number::I64(3), Number::F64(3.5));
+        assert!(Number::I64(3) < Number::F64(3.5));
+        assert!(Number::I64(-3) > Number::F64(-3.5));
+        assert!(Number::F64(-3.5) < Number::I64(-3));
+        // 2^53 + 1无法用f64表示, 不能与2^53相等.
+        assert_ne!(
+            Number::I64(9007199254740993),
+            Number::F64(9007199254740992.0)
+        );
+        assert!(Number::I64(9007199254740993) > Number::F64(9007199254740992.0));
+        assert_eq!(Number::I64(i64::MIN), Number::F64(-9223372036854775808.0));
+        assert!(Number::I64(i64::MAX) < Number::F64(9223372036854775808.0));
+        assert!(Number::I64(i64::MIN) > Number::F64(f64::NEG_INFINITY));
+        assert_ne!(Number::F64(f64::NAN), Number::F64(f64::NAN));
+        assert_eq!(Number::I64(0).partial_cmp(&Number::F64(f64::NAN)), None);
+        assert_eq!(Number::F64(-0.0), Number::I64(0));
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(Number::F64(3.0).to_i64(), Ok(3));
+        assert_eq!(Number::I64(-1).to_i32(), Ok(-1));
+        assert_eq!(Number::F64(4294967295.0).to_u32(), Ok(u32::MAX));
+        assert_eq!(Number::I64(i64::MAX).to_u64(), Ok(i64::MAX as u64));
+        assert_eq!(Number::F64(1.8e19).to_u64(), Ok(18000000000000000000));
+        assert_eq!(
+            Number::I64(-1).to_u64(),
+            Err(NumberError::OutOfRange {
+                value: Number::I64(-1),
+                target: "u64"
+            })
+        );
+        assert_eq!(
+            Number::I64(1 << 40).to_i32(),
+            Err(NumberError::OutOfRange {
+                value: Number::I64(1 << 40),
+                target: "i32"
+            })
+        );
+        assert_eq!(
+            Number::F64(9223372036854775808.0).to_i64(),
+            Err(NumberError::OutOfRange {
+                value: Number::F64(9223372036854775808.0),
+                target: "i64"
+            })
+        );
+        assert_eq!(
+            Number::F64(1.5).to_i64(),
+            Err(NumberError::Inexact {
+                value: Number::F64(1.5),
+                target: "i64"
+            })
+        );
+        assert!(matches!(
+            Number::F64(f64::INFINITY).to_usize(),
+            Err(NumberError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            Number::F64(f64::NAN).to_i64(),
+            Err(NumberError::Inexact { .. })
+        ));
+    }
+
+    #[test]
+    fn test_float() {
+        assert_eq!(Number::I64(1 << 53).to_f64(), Ok(9007199254740992.0));
+        assert_eq!(Number::I64(i64::MIN).to_f64(), Ok(-9223372036854775808.0));
+        assert!(matches!(
+            Number::I64(i64::MAX).to_f64(),
+            Err(NumberError::Inexact { .. })
+        ));
+        assert!(matches!(
+            Number::I64((1 << 53) + 1).to_f64(),
+            Err(NumberError::Inexact { .. })
+        ));
+        assert_eq!(Number::F64(0.5).to_f32(), Ok(0.5));
+        assert_eq!(Number::I64(1 << 24).to_f32(), Ok(16777216.0));
+        assert!(Number::F64(f64::NAN).to_f32().unwrap().is_nan());
+        assert_eq!(Number::F64(f64::INFINITY).to_f32(), Ok(f32::INFINITY));
+        assert!(matches!(
+            Number::F64(0.1).to_f32(),
+            Err(NumberError::Inexact { .. })
+        ));
+        assert!(matches!(
+            Number::I64((1 << 24) + 1).to_f32(),
+            Err(NumberError::Inexact { .. })
+        ));
+        assert!(matches!(
+            Number::F64(1e300).to_f32(),
+            Err(NumberError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(NumberError::NotANumber.to_string(), "Value is not a number");
+        assert_eq!(
+            Number::I64(-300).to_u32().unwrap_err().to_string(),
+            "Number -300 is out of range for u32"
+        );
+        assert_eq!(
+            Number::F64(2.5).to_i32().unwrap_err().to_string(),
+            "Number 2.5 cannot be represented exactly as i32"
+        );
+    }
+}
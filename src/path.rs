@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::fmt;
+
+/// Json文档中路径的一段: 对象成员的名称或数组元素的下标.
+///
+/// 遍历或写入过程中, 一个节点的路径用`&[PathSegment]`表示, 从最外层对象的成员开始.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment<'a> {
+    Key(Cow<'a, str>),
+    Index(usize),
+}
+
+impl PathSegment<'_> {
+    /// 返回对象成员的名称, 数组下标返回None.
+    pub fn as_key(&self) -> Option<&str> {
+        match *self {
+            PathSegment::Key(ref key) => Some(key),
+            PathSegment::Index(_) => None,
+        }
+    }
+
+    /// 返回数组元素的下标, 对象成员返回None.
+    pub fn as_index(&self) -> Option<usize> {
+        match *self {
+            PathSegment::Key(_) => None,
+            PathSegment::Index(index) => Some(index),
+        }
+    }
+}
+
+impl fmt::Display for PathSegment<'_> {
+    // 按JSON Pointer (RFC 6901)的规则输出, `~`和`/`分别转义为`~0`和`~1`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PathSegment::Key(ref key) => {
+                for c in key.chars() {
+                    match c {
+                        '~' => f.write_str("~0")?,
+                        '/' => f.write_str("~1")?,
+                        _ => fmt::Write::write_char(f, c)?,
+                    }
+                }
+                Ok(())
+            }
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// 将路径转换为JSON Pointer (RFC 6901)字符串. 空路径表示整个文档, 转换为空字符串.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{to_json_pointer, PathSegment};
+///
+/// let path = [
+///     PathSegment::Key("a/b".into()),
+///     PathSegment::Index(0),
+///     PathSegment::Key("c".into()),
+/// ];
+/// assert_eq!(to_json_pointer(&path), "/a~1b/0/c");
+/// ```
+pub fn to_json_pointer(path: &[PathSegment]) -> String {
+    let mut pointer = String::new();
+    for segment in path {
+        pointer.push('/');
+        pointer.push_str(segment.to_string().as_str());
+    }
+
+    pointer
+}
+
+// 将JSON Pointer拆分为各段并还原转义. 非空的JSON Pointer必须以`/`开头, 否则返回None.
+pub(crate) fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let rest = match pointer {
+        "" => None,
+        _ => Some(pointer.strip_prefix('/')?),
+    };
+
+    Some(
+        rest.into_iter()
+            .flat_map(|rest| rest.split('/'))
+            .map(|token| {
+                if token.contains('~') {
+                    Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+                } else {
+                    Cow::Borrowed(token)
+                }
+            }),
+    )
+}
+
+// 将JSON Pointer中的一段解析为数组下标. 只接受没有前导0的十进制数.
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if !token.bytes().all(|b| b.is_ascii_digit()) || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+
+    token.parse().ok()
+}
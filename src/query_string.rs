@@ -0,0 +1,489 @@
+use crate::{Map, Value};
+use std::borrow::Cow;
+use std::mem;
+
+// 参数名称在根名称之后的一段.
+enum Segment {
+    Key(String),  // `[name]`或`.name`: 对象成员.
+    Index(usize), // `[0]`: 数组元素.
+    Append,       // `[]`: 追加一个数组元素.
+}
+
+/// 将URL查询字符串(或`application/x-www-form-urlencoded`表单)转换成`Map`的转换器.
+pub struct QueryStringReader {
+    settings: QueryStringSettings,
+}
+
+impl QueryStringReader {
+    pub fn new(settings: QueryStringSettings) -> Self {
+        QueryStringReader { settings }
+    }
+
+    pub fn parse(&self, query: &str) -> Result<Map, String> {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        let mut map = Map::new();
+        for (i, param) in query.split('&').filter(|p| !p.is_empty()).enumerate() {
+            if i >= self.settings.max_parameters {
+                return Err(format!(
+                    "The set parameter limit is exceeded: {}",
+                    self.settings.max_parameters
+                ));
+            }
+
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let key = decode(key)?;
+            let value = decode(value)?;
+            let (root, segments) = self.parse_key(&key)?;
+            let value = match self.settings.infer_types {
+                true => infer(value),
+                false => Value::String(value.into_owned()),
+            };
+            insert(&mut map, root, &segments, value, &key)?;
+        }
+
+        Ok(map)
+    }
+
+    // 将参数名称拆分为根名称和之后的各段. 不符合嵌套语法的名称整体作为一个成员名称.
+    fn parse_key<'k>(&self, key: &'k str) -> Result<(&'k str, Vec<Segment>), String> {
+        let flat = Ok((key, Vec::new()));
+        if self.settings.nesting == KeyNesting::None {
+            return flat;
+        }
+
+        let dots = self.settings.nesting == KeyNesting::Dots;
+        let name_end = |s: &str| match dots {
+            true => s.find(['[', '.']).unwrap_or(s.len()),
+            false => s.find('[').unwrap_or(s.len()),
+        };
+        let (root, mut rest) = key.split_at(name_end(key));
+        if root.is_empty() {
+            return flat;
+        }
+
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(inner) = rest.strip_prefix('[') {
+                let Some((name, after)) = inner.split_once(']') else {
+                    return flat;
+                };
+                segments.push(match name {
+                    "" => Segment::Append,
+                    _ => segment(name),
+                });
+                rest = after;
+            } else if let Some(inner) = rest.strip_prefix('.').filter(|_| dots) {
+                let (name, after) = inner.split_at(name_end(inner));
+                if name.is_empty() {
+                    return flat;
+                }
+                segments.push(segment(name));
+                rest = after;
+            } else {
+                return flat;
+            }
+        }
+
+        if segments.len() > self.settings.depth_limit {
+            return Err(format!(
+                "The set depth limit is exceeded: {}",
+                self.settings.depth_limit
+            ));
+        }
+        Ok((root, segments))
+    }
+}
+
+// 由十进制数字组成的段表示数组下标, 其余的段表示对象成员. 空的段由调用者处理.
+fn segment(name: &str) -> Segment {
+    let canonical =
+        name.bytes().all(|b| b.is_ascii_digit()) && (name == "0" || !name.starts_with('0'));
+    match name.parse() {
+        Ok(index) if canonical => Segment::Index(index),
+        _ => Segment::Key(name.to_string()),
+    }
+}
+
+// 将一个参数写入`map`. 重复的参数合并为数组; 同一个名称既作为值又作为容器时返回错误.
+fn insert(
+    map: &mut Map,
+    root: &str,
+    segments: &[Segment],
+    value: Value,
+    key: &str,
+) -> Result<(), String> {
+    let conflict = || format!("Conflicting query string parameter: {}", key);
+    // 新建的位置先以`Null`占位, 解析出的值不会是`Null`.
+    let mut slot = map.entry(root.to_string()).or_insert(Value::Null);
+    for segment in segments {
+        if slot.is_null() {
+            *slot = match segment {
+                Segment::Key(_) => Value::Object(Map::new()),
+                _ => Value::Vec(Vec::new()),
+            };
+        }
+
+        slot = match (segment, slot) {
+            (Segment::Key(name), Value::Object(obj)) => {
+                obj.entry(name.clone()).or_insert(Value::Null)
+            }
+            (Segment::Append, Value::Vec(vec)) => {
+                vec.push(Value::Null);
+                vec.last_mut().expect("an element was just pushed")
+            }
+            (&Segment::Index(index), Value::Vec(vec)) if index <= vec.len() => {
+                if index == vec.len() {
+                    vec.push(Value::Null);
+                }
+                &mut vec[index]
+            }
+            (Segment::Index(_), Value::Vec(_)) => {
+                return Err(format!(
+                    "Array index out of order in query string parameter: {}",
+                    key
+                ));
+            }
+            _ => return Err(conflict()),
+        };
+    }
+
+    match slot {
+        Value::Null => *slot = value,
+        Value::Vec(vec) => vec.push(value),
+        Value::Object(_) => return Err(conflict()),
+        _ => {
+            let first = mem::replace(slot, Value::Null);
+            *slot = Value::Vec(vec![first, value]);
+        }
+    }
+    Ok(())
+}
+
+// 推断值的类型: `true`和`false`为`Bool`, 规范写法的十进制整数为`I64`, 其余为`String`.
+fn infer(text: Cow<str>) -> Value {
+    let digits = text.strip_prefix('-').unwrap_or(&text);
+    let canonical = !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+        && text != "-0";
+
+    match &*text {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if canonical => match text.parse() {
+            Ok(n) => Value::I64(n),
+            Err(_) => Value::String(text.into_owned()),
+        },
+        _ => Value::String(text.into_owned()),
+    }
+}
+
+// 百分号解码, `+`解码为空格.
+fn decode(text: &str) -> Result<Cow<'_, str>, String> {
+    if !text.contains(['%', '+']) {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = text
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Invalid percent-encoding in query string: {}", text))?;
+                decoded.push(byte);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8(decoded)
+        .map(Cow::Owned)
+        .map_err(|_| format!("Invalid UTF-8 in query string: {}", text))
+}
+
+/// 将`Map`转换成URL查询字符串的转换器. 成员按名称的字典序排列.
+pub struct QueryStringWriter {
+    settings: QueryStringSettings,
+}
+
+impl QueryStringWriter {
+    pub fn new(settings: QueryStringSettings) -> Self {
+        QueryStringWriter { settings }
+    }
+
+    pub fn format(&self, map: &Map) -> Result<String, String> {
+        let mut out = String::new();
+        // 待写入的参数: 已编码的参数名称和值. 后写入的先压栈, 使参数按顺序输出.
+        let mut stack = sorted_entries(map, |key| encode(key).into_owned());
+        while let Some((name, value)) = stack.pop() {
+            match *value {
+                Value::Object(ref obj) => {
+                    let child = |key: &str| match self.settings.nesting {
+                        KeyNesting::Dots => format!("{}.{}", name, encode(key)),
+                        _ => format!("{}[{}]", name, encode(key)),
+                    };
+                    self.check_nesting(&name, "an object")?;
+                    stack.extend(sorted_entries(obj, child));
+                }
+                Value::Vec(ref vec) => {
+                    let nested = vec
+                        .iter()
+                        .any(|v| matches!(v, Value::Vec(_) | Value::Object(_)));
+                    if nested {
+                        self.check_nesting(&name, "a nested array")?;
+                    }
+                    let indexed = self.settings.array_indices || nested;
+                    let elements = vec.iter().enumerate().rev().map(|(i, value)| {
+                        let name = match self.settings.nesting {
+                            KeyNesting::None => name.clone(),
+                            _ if indexed => format!("{}[{}]", name, i),
+                            _ => format!("{}[]", name),
+                        };
+                        (name, value)
+                    });
+                    stack.extend(elements);
+                }
+                _ => {
+                    if !out.is_empty() {
+                        out.push('&');
+                    }
+                    out.push_str(&name);
+                    out.push('=');
+                    match *value {
+                        Value::Null => {}
+                        Value::String(ref s) => out.push_str(&encode(s)),
+                        _ => out.push_str(&value.to_json()),
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn check_nesting(&self, name: &str, what: &str) -> Result<(), String> {
+        match self.settings.nesting {
+            KeyNesting::None => Err(format!(
+                "Query string without key nesting cannot represent {} at {}",
+                what, name
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// 按名称排序的成员, 逆序排列以便压栈.
+fn sorted_entries(map: &Map, name: impl Fn(&str) -> String) -> Vec<(String, &Value)> {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort_by(|a, b| b.cmp(a));
+    keys.into_iter().map(|key| (name(key), &map[key])).collect()
+}
+
+// 百分号编码. 非保留字符(`A-Z a-z 0-9 - . _ ~`)保持原样, 空格编码为`+`.
+fn encode(text: &str) -> Cow<'_, str> {
+    let unreserved = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+    if text.bytes().all(unreserved) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len() + 8);
+    for b in text.bytes() {
+        match b {
+            b' ' => out.push('+'),
+            _ if unreserved(b) => out.push(b as char),
+            _ => {
+                out.push('%');
+                out.push(
+                    char::from_digit((b >> 4) as u32, 16)
+                        .unwrap()
+                        .to_ascii_uppercase(),
+                );
+                out.push(
+                    char::from_digit((b & 0xf) as u32, 16)
+                        .unwrap()
+                        .to_ascii_uppercase(),
+                );
+            }
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// 查询字符串的读写设置.
+pub struct QueryStringSettings {
+    pub nesting: KeyNesting,   // 参数名称的嵌套语法.
+    pub infer_types: bool, // 读取时是否将`true`/`false`和整数转换为`Bool`和`I64`, 否则所有值都是`String`.
+    pub array_indices: bool, // 写入时数组元素是否带下标(`a[0]=x`), 否则写为`a[]=x`. 包含容器的数组总是带下标.
+    pub depth_limit: usize,  // 读取时参数名称中根名称之后的最大段数.
+    pub max_parameters: usize, // 读取时参数的最大个数.
+}
+
+impl Default for QueryStringSettings {
+    fn default() -> Self {
+        QueryStringSettings {
+            nesting: KeyNesting::Brackets,
+            infer_types: false,
+            array_indices: false,
+            depth_limit: 16,
+            max_parameters: 1000,
+        }
+    }
+}
+
+/// 查询字符串中参数名称的嵌套语法.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyNesting {
+    /// 不嵌套, `a[b]`就是成员名称. 重复的参数仍然合并为数组.
+    None,
+    /// `a[b][c]=1`表示对象成员, `list[]=x`追加数组元素, `list[0]=x`指定数组下标.
+    Brackets,
+    /// 在`Brackets`的基础上, 也将`a.b.c=1`解析为对象成员. 写入时对象成员使用点分隔.
+    Dots,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{JsonWriterSettings, KeyNesting, Map, QueryStringSettings};
+
+    fn parse(query: &str, settings: QueryStringSettings) -> String {
+        match Map::from_query_string_with_settings(query, settings) {
+            Ok(map) => map.to_json_with_settings(JsonWriterSettings {
+                sort_keys: true,
+                ..Default::default()
+            }),
+            Err(e) => panic!("{}: {}", e, query),
+        }
+    }
+
+    fn parse_err(query: &str, settings: QueryStringSettings) -> String {
+        match Map::from_query_string_with_settings(query, settings) {
+            Ok(_) => panic!("Expected an Err for {}", query),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn nesting() {
+        let query = "?a[b][c]=1&list[]=x&list[]=y&rows[0][id]=7&rows[0][on]=true&rows[1][id]=8&flag&q=a+b%26c%20%E2%9C%93";
+        assert_eq!(
+            parse(query, QueryStringSettings::default()),
+            r#"{"a":{"b":{"c":"1"}},"flag":"","list":["x","y"],"q":"a b&c ✓","rows":[{"id":"7","on":"true"},{"id":"8"}]}"#
+        );
+
+        let settings = QueryStringSettings {
+            infer_types: true,
+            nesting: KeyNesting::Dots,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse(
+                "a.b=1&a.c[]=-2&a.d=true&e=007&f=9223372036854775808&g=1.5&t=x&t=y",
+                settings
+            ),
+            r#"{"a":{"b":1,"c":[-2],"d":true},"e":"007","f":"9223372036854775808","g":"1.5","t":["x","y"]}"#
+        );
+
+        // 不符合嵌套语法的名称整体作为成员名称, 编码的括号也是括号
+        assert_eq!(
+            parse(
+                "a[b=1&[c]=2&d[e]f=3&g%5Bh%5D=4&i.j=5",
+                QueryStringSettings::default()
+            ),
+            r#"{"[c]":"2","a[b":"1","d[e]f":"3","g":{"h":"4"},"i.j":"5"}"#
+        );
+
+        let settings = QueryStringSettings {
+            nesting: KeyNesting::None,
+            ..Default::default()
+        };
+        assert_eq!(parse("a[b]=1&a[b]=2", settings), r#"{"a[b]":["1","2"]}"#);
+    }
+
+    #[test]
+    fn errors_and_limits() {
+        let cases = [
+            ("a=1&a[b]=2", "Conflicting query string parameter: a[b]"),
+            ("a[b]=1&a=2", "Conflicting query string parameter: a"),
+            (
+                "a[1]=x",
+                "Array index out of order in query string parameter: a[1]",
+            ),
+            ("a=%2", "Invalid percent-encoding in query string: %2"),
+            ("a=%FF", "Invalid UTF-8 in query string: %FF"),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(parse_err(query, QueryStringSettings::default()), expected);
+        }
+
+        let settings = QueryStringSettings {
+            max_parameters: 2,
+            ..Default::default()
+        };
+        assert_eq!(parse("a=1&&b=2&", settings), r#"{"a":"1","b":"2"}"#);
+        let settings = QueryStringSettings {
+            max_parameters: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_err("a=1&b=2&c=3", settings),
+            "The set parameter limit is exceeded: 2"
+        );
+
+        let settings = QueryStringSettings {
+            depth_limit: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_err("a[b][c][d]=1", settings),
+            "The set depth limit is exceeded: 2"
+        );
+    }
+
+    #[test]
+    fn write() {
+        let mut map = Map::new();
+        map.merge(r#"{"q":"a b&c/✓","n":1,"ok":true,"none":null,"list":["x","y"],"a":{"b":{"c":1.5}},"rows":[{"id":7},{"id":8}],"empty":[]}"#)
+            .unwrap();
+
+        let query = map.to_query_string().unwrap();
+        assert_eq!(
+            query,
+            "a[b][c]=1.5&list[]=x&list[]=y&n=1&none=&ok=true&q=a+b%26c%2F%E2%9C%93&rows[0][id]=7&rows[1][id]=8"
+        );
+
+        let settings = QueryStringSettings {
+            nesting: KeyNesting::Dots,
+            array_indices: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            map.to_query_string_with_settings(settings).unwrap(),
+            "a.b.c=1.5&list[0]=x&list[1]=y&n=1&none=&ok=true&q=a+b%26c%2F%E2%9C%93&rows[0].id=7&rows[1].id=8"
+        );
+
+        let settings = QueryStringSettings {
+            infer_types: true,
+            ..Default::default()
+        };
+        let parsed = Map::from_query_string_with_settings(&query, settings).unwrap();
+        assert_eq!(parsed["rows"].to_json(), r#"[{"id":7},{"id":8}]"#);
+        assert_eq!(parsed["q"].as_string(), Some("a b&c/✓"));
+
+        let settings = QueryStringSettings {
+            nesting: KeyNesting::None,
+            ..Default::default()
+        };
+        assert_eq!(
+            map.to_query_string_with_settings(settings).err().unwrap(),
+            "Query string without key nesting cannot represent an object at a"
+        );
+    }
+}
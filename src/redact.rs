@@ -0,0 +1,246 @@
+use crate::json_reader::JsonReader;
+use crate::json_writer::JsonWriter;
+use crate::path::{self, PathSegment};
+use crate::{JsonReaderSettings, JsonWriterSettings, Value};
+use std::borrow::Cow;
+
+/// 写入Json时替换敏感值的规则, 设置在`JsonWriterSettings::redactions`中.
+///
+/// 规则作用于对象成员和数组元素, 不作用于最外层的值. 一个值匹配多条规则时使用第一条.
+/// 对象和数组被整体替换, 其中的成员不再写入.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{JsonWriterSettings, Map, Redaction, RedactionAction, RedactionTarget};
+///
+/// let mut map = Map::new();
+/// map.merge(r#"{"user":{"password":"hunter2","card":"4111111111111111"}}"#).unwrap();
+/// let settings = JsonWriterSettings {
+///     sort_keys: true,
+///     redactions: vec![
+///         Redaction {
+///             target: RedactionTarget::KeyPattern("pass*".to_string()),
+///             action: RedactionAction::Mask("***".to_string()),
+///         },
+///         Redaction {
+///             target: RedactionTarget::Path("/*/card".to_string()),
+///             action: RedactionAction::Preview(4),
+///         },
+///     ],
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     map.to_json_with_settings(settings),
+///     r#"{"user":{"card":"4111…","password":"***"}}"#
+/// );
+/// // `Map`本身不受影响.
+/// assert_eq!(map.pointer("/user/password").unwrap().as_string(), Some("hunter2"));
+/// ```
+pub struct Redaction {
+    pub target: RedactionTarget, // 需要替换的值.
+    pub action: RedactionAction, // 替换成什么.
+}
+
+/// 脱敏规则匹配的值.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RedactionTarget {
+    /// 名称与之完全相同的对象成员, 不论在哪一层.
+    Key(String),
+    /// 名称匹配这个模式的对象成员, 不区分大小写. `*`匹配任意个字符, `?`匹配一个字符.
+    KeyPattern(String),
+    /// 位于这个JSON Pointer的成员或元素, 其中的`*`段匹配任意一个成员名称或数组下标.
+    Path(String),
+}
+
+/// 脱敏后写入的值, 总是一个字符串.
+///
+/// 哈希和预览使用原值的文本: 字符串使用其内容, 其他值使用紧凑格式的Json文本, 其中对象成员按名称排序,
+/// 数字按解析后的值写出. 因此写入`Map`与`reformat`对相同的值得到相同的结果.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RedactionAction {
+    /// 替换为固定的字符串.
+    Mask(String),
+    /// 替换为`fnv1a:`加上16位十六进制的FNV-1a哈希值, 相同的值得到相同的结果, 便于在日志中关联.
+    /// 这不是加密哈希, 取值范围小的值(例如短密码)可以被穷举还原.
+    Hash,
+    /// 只保留前n个字符, 后面的部分替换为`…`.
+    Preview(usize),
+}
+
+impl RedactionAction {
+    // 返回替换`text`的字符串.
+    pub(crate) fn apply(&self, text: &str) -> String {
+        match *self {
+            RedactionAction::Mask(ref mask) => mask.clone(),
+            RedactionAction::Hash => format!("fnv1a:{:016x}", fnv1a(text.as_bytes())),
+            RedactionAction::Preview(n) => match text.char_indices().nth(n) {
+                Some((end, _)) => format!("{}…", &text[..end]),
+                None => text.to_string(),
+            },
+        }
+    }
+
+    // 是否需要原值的文本. 不需要时流式写入可以不保存被替换的对象或数组.
+    pub(crate) fn needs_text(&self) -> bool {
+        !matches!(*self, RedactionAction::Mask(_))
+    }
+}
+
+// 返回哈希和预览使用的原值文本. 不使用写入时的转义策略, 使结果与写入设置无关.
+pub(crate) fn value_text(val: &Value) -> Cow<'_, str> {
+    match *val {
+        Value::String(ref val) => Cow::Borrowed(val),
+        _ => {
+            let settings = JsonWriterSettings {
+                sort_keys: true,
+                ..Default::default()
+            };
+            Cow::Owned(JsonWriter::new(&settings).format_value(val))
+        }
+    }
+}
+
+// 解析流式写入时保存的Json文本, 返回与`value_text`相同的文本. 重复的名称与`Map`一样保留最后一个.
+pub(crate) fn json_text(json: &str) -> Result<String, String> {
+    let settings = JsonReaderSettings {
+        recursion_limit: usize::MAX,
+        depth_limit: usize::MAX,
+        ..Default::default()
+    };
+    let value = JsonReader::new(settings).parse_value(json)?;
+    Ok(value_text(&value).into_owned())
+}
+
+// 返回第一条匹配`path`的规则的替换方式. `path`的最后一段为值在其容器中的名称或下标.
+pub(crate) fn find<'r>(
+    rules: &'r [Redaction],
+    path: &[PathSegment],
+) -> Option<&'r RedactionAction> {
+    let last = path.last()?;
+    rules
+        .iter()
+        .find(|rule| match rule.target {
+            RedactionTarget::Key(ref key) => last.as_key() == Some(key.as_str()),
+            RedactionTarget::KeyPattern(ref pattern) => {
+                last.as_key().is_some_and(|key| glob_match(pattern, key))
+            }
+            RedactionTarget::Path(ref pointer) => path_match(pointer, path),
+        })
+        .map(|rule| &rule.action)
+}
+
+fn path_match(pointer: &str, path: &[PathSegment]) -> bool {
+    let Some(tokens) = path::pointer_tokens(pointer) else {
+        return false;
+    };
+
+    let mut segments = path.iter();
+    for token in tokens {
+        let matched = match segments.next() {
+            Some(_) if token == "*" => true,
+            Some(PathSegment::Key(key)) => token == *key,
+            Some(PathSegment::Index(index)) => path::parse_index(&token) == Some(*index),
+            None => false,
+        };
+        if !matched {
+            return false;
+        }
+    }
+    segments.next().is_none()
+}
+
+// 不区分大小写的通配符匹配. 遇到`*`时记录位置, 之后失配时回到该位置多匹配一个字符.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let (mut p, mut t) = (0, 0);
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || same(c, text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*token*", "X-Auth-TOKEN"));
+        assert!(glob_match("pass?ord", "PassWord"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b", "aXbY"));
+        assert!(!glob_match("pass", "password"));
+    }
+
+    #[test]
+    fn find_rule() {
+        let rules = vec![
+            Redaction {
+                target: RedactionTarget::Path("/users/*/name".to_string()),
+                action: RedactionAction::Preview(1),
+            },
+            Redaction {
+                target: RedactionTarget::Key("name".to_string()),
+                action: RedactionAction::Hash,
+            },
+        ];
+        fn path<'a>(segments: &[&'a str]) -> Vec<PathSegment<'a>> {
+            segments
+                .iter()
+                .map(|s| match s.parse() {
+                    Ok(i) => PathSegment::Index(i),
+                    Err(_) => PathSegment::Key(Cow::Borrowed(*s)),
+                })
+                .collect()
+        }
+        assert_eq!(
+            find(&rules, &path(&["users", "0", "name"])),
+            Some(&RedactionAction::Preview(1))
+        );
+        assert_eq!(
+            find(&rules, &path(&["users", "name"])),
+            Some(&RedactionAction::Hash)
+        );
+        assert_eq!(find(&rules, &path(&["users", "0"])), None);
+        assert_eq!(find(&rules, &[]), None);
+    }
+
+    #[test]
+    fn actions() {
+        assert_eq!(RedactionAction::Mask("-".to_string()).apply("abc"), "-");
+        assert_eq!(RedactionAction::Preview(2).apply("中文字"), "中文…");
+        assert_eq!(RedactionAction::Preview(3).apply("abc"), "abc");
+        assert_eq!(RedactionAction::Hash.apply(""), "fnv1a:cbf29ce484222325");
+        assert_eq!(RedactionAction::Hash.apply("a"), "fnv1a:af63dc4c8601ec8c");
+    }
+}
@@ -0,0 +1,603 @@
+use crate::json_reader::JsonReader;
+use crate::{JsonReaderSettings, Map, Number, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
+
+/// 从多个样本中推断出的某个位置上的值的结构, 每种类型分别统计.
+///
+/// 依次调用`add`加入样本后, 可以查看各类型出现的次数, 数值范围, 字符串格式, 数组元素和对象成员的结构,
+/// 或者用`to_json_schema`输出为JSON Schema.
+///
+/// # 例子
+///
+/// ```
+/// use mapjson::{Shape, StringFormat};
+/// use std::io::Cursor;
+///
+/// let ndjson = r#"
+/// {"id":1,"created":"2024-01-02T03:04:05Z","tags":["a"]}
+/// {"id":2,"created":"2024-02-03T04:05:06+08:00","tags":[],"note":null}
+/// {"id":3,"created":"2024-03-04T05:06:07Z","tags":["b","c"],"note":"x"}
+/// "#;
+/// let mut shape = Shape::new();
+/// assert_eq!(shape.add_json_lines(Cursor::new(ndjson)).unwrap(), 3);
+///
+/// let object = shape.objects.as_ref().unwrap();
+/// assert_eq!(object.required().collect::<Vec<_>>(), ["created", "id", "tags"]);
+/// assert_eq!(object.fields["note"].to_string(), "String|Null");
+/// assert_eq!(object.fields["created"].strings.as_ref().unwrap().format, Some(StringFormat::DateTime));
+///
+/// let id = object.fields["id"].integers.as_ref().unwrap();
+/// assert_eq!((id.min, id.max), (1, 3));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Shape {
+    pub count: usize,                       // 样本总数, 包括`Null`.
+    pub nulls: usize,                       // `Null`的个数.
+    pub bools: usize,                       // `Bool`的个数.
+    pub integers: Option<NumberRange<i64>>, // `I64`的统计.
+    pub floats: Option<NumberRange<f64>>,   // `F64`的统计, 范围只包括有限的值.
+    pub strings: Option<StringShape>,       // `String`的统计.
+    pub arrays: Option<ArrayShape>,         // `Vec`的统计.
+    pub objects: Option<ObjectShape>,       // `Object`的统计.
+}
+
+/// 数字的个数和范围.
+#[derive(Clone, Debug)]
+pub struct NumberRange<T> {
+    pub count: usize,
+    pub min: T,
+    pub max: T,
+}
+
+/// 字符串的个数, 长度(字符数)范围和格式. 只有所有字符串都符合同一种格式时`format`才不为None.
+#[derive(Clone, Debug)]
+pub struct StringShape {
+    pub count: usize,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub format: Option<StringFormat>,
+}
+
+/// 数组的个数, 长度范围和所有元素合并后的结构.
+#[derive(Clone, Debug)]
+pub struct ArrayShape {
+    pub count: usize,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub items: Box<Shape>,
+}
+
+/// 对象的个数和每个成员的结构. 成员的`count`等于对象的`count`时, 该成员在每个对象中都存在.
+#[derive(Clone, Debug)]
+pub struct ObjectShape {
+    pub count: usize,
+    pub fields: BTreeMap<String, Shape>,
+}
+
+/// 可以识别的字符串格式, 与JSON Schema的`format`对应.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringFormat {
+    DateTime, // RFC 3339的日期时间, 例如`2024-01-02T03:04:05.6Z`.
+    Date,     // RFC 3339的日期, 例如`2024-01-02`.
+    Time,     // RFC 3339的时间, 必须带有时区, 例如`03:04:05+08:00`.
+    Uuid,     // 例如`123e4567-e89b-12d3-a456-426614174000`.
+    Email,    // 例如`a@example.com`.
+    Uri,      // 带有`://`的URI, 例如`https://example.com/a`.
+}
+
+impl StringFormat {
+    const ALL: [StringFormat; 6] = [
+        StringFormat::DateTime,
+        StringFormat::Date,
+        StringFormat::Time,
+        StringFormat::Uuid,
+        StringFormat::Email,
+        StringFormat::Uri,
+    ];
+
+    /// 返回`s`符合的格式, 都不符合时返回None.
+    pub fn detect(s: &str) -> Option<StringFormat> {
+        StringFormat::ALL.into_iter().find(|f| f.matches(s))
+    }
+
+    pub fn matches(self, s: &str) -> bool {
+        match self {
+            StringFormat::DateTime => match s.find(['T', 't']) {
+                Some(i) => is_date(&s[..i]) && is_time(&s[i + 1..]),
+                None => false,
+            },
+            StringFormat::Date => is_date(s),
+            StringFormat::Time => is_time(s),
+            StringFormat::Uuid => is_uuid(s),
+            StringFormat::Email => is_email(s),
+            StringFormat::Uri => is_uri(s),
+        }
+    }
+
+    /// JSON Schema中`format`的值.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StringFormat::DateTime => "date-time",
+            StringFormat::Date => "date",
+            StringFormat::Time => "time",
+            StringFormat::Uuid => "uuid",
+            StringFormat::Email => "email",
+            StringFormat::Uri => "uri",
+        }
+    }
+}
+
+// 检查`s`是否为`pattern`的形式, `pattern`中的`9`表示一个十进制数字, 其他字符必须相同.
+fn is_pattern(s: &str, pattern: &str) -> bool {
+    s.len() == pattern.len()
+        && s.bytes().zip(pattern.bytes()).all(|(c, p)| match p {
+            b'9' => c.is_ascii_digit(),
+            _ => c == p,
+        })
+}
+
+fn number(s: &str) -> u32 {
+    s.parse().unwrap_or(0)
+}
+
+fn is_date(s: &str) -> bool {
+    is_pattern(s, "9999-99-99")
+        && (1..=12).contains(&number(&s[5..7]))
+        && (1..=31).contains(&number(&s[8..10]))
+}
+
+// RFC 3339的full-time: `HH:MM:SS`, 可选的小数秒, 以及`Z`或`+HH:MM`形式的时区.
+fn is_time(s: &str) -> bool {
+    if !s.get(..8).is_some_and(|head| is_pattern(head, "99:99:99")) {
+        return false;
+    }
+    if number(&s[..2]) > 23 || number(&s[3..5]) > 59 || number(&s[6..8]) > 60 {
+        return false;
+    }
+
+    let mut rest = &s[8..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return false;
+        }
+        rest = &frac[digits..];
+    }
+    match rest {
+        "Z" | "z" => true,
+        _ => {
+            (rest.starts_with('+') || rest.starts_with('-'))
+                && is_pattern(&rest[1..], "99:99")
+                && number(&rest[1..3]) <= 23
+                && number(&rest[4..6]) <= 59
+        }
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.bytes().enumerate().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == b'-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !s.contains(char::is_whitespace)
+}
+
+fn is_uri(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+        && !s.contains(char::is_whitespace)
+}
+
+impl Shape {
+    pub fn new() -> Self {
+        Shape::default()
+    }
+
+    /// 加入一个样本.
+    pub fn add(&mut self, value: &Value) {
+        self.count += 1;
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.bools += 1,
+            Value::I64(n) => {
+                let range = self.integers.get_or_insert(NumberRange {
+                    count: 0,
+                    min: *n,
+                    max: *n,
+                });
+                range.count += 1;
+                range.min = range.min.min(*n);
+                range.max = range.max.max(*n);
+            }
+            Value::F64(f) => {
+                let range = self.floats.get_or_insert(NumberRange {
+                    count: 0,
+                    min: f64::INFINITY,
+                    max: f64::NEG_INFINITY,
+                });
+                range.count += 1;
+                if f.is_finite() {
+                    range.min = range.min.min(*f);
+                    range.max = range.max.max(*f);
+                }
+            }
+            Value::String(s) => {
+                let length = s.chars().count();
+                match self.strings {
+                    None => {
+                        self.strings = Some(StringShape {
+                            count: 1,
+                            min_length: length,
+                            max_length: length,
+                            format: StringFormat::detect(s),
+                        })
+                    }
+                    Some(ref mut shape) => {
+                        shape.count += 1;
+                        shape.min_length = shape.min_length.min(length);
+                        shape.max_length = shape.max_length.max(length);
+                        if shape.format.is_some_and(|f| !f.matches(s)) {
+                            shape.format = None;
+                        }
+                    }
+                }
+            }
+            Value::Vec(vec) => {
+                let shape = self.arrays.get_or_insert(ArrayShape {
+                    count: 0,
+                    min_length: vec.len(),
+                    max_length: vec.len(),
+                    items: Box::default(),
+                });
+                shape.count += 1;
+                shape.min_length = shape.min_length.min(vec.len());
+                shape.max_length = shape.max_length.max(vec.len());
+                for item in vec {
+                    shape.items.add(item);
+                }
+            }
+            Value::Object(map) => self.add_fields(map),
+        }
+    }
+
+    /// 加入一个对象样本, 与`add(&Value::Object(map))`相同, 但不需要复制`map`.
+    pub fn add_map(&mut self, map: &Map) {
+        self.count += 1;
+        self.add_fields(map);
+    }
+
+    fn add_fields(&mut self, map: &Map) {
+        let shape = self.objects.get_or_insert(ObjectShape {
+            count: 0,
+            fields: BTreeMap::new(),
+        });
+        shape.count += 1;
+        for (key, value) in map.iter() {
+            shape.fields.entry(key.clone()).or_default().add(value);
+        }
+    }
+
+    /// 加入每行一个Json值(NDJSON)的样本, 跳过空行, 返回加入的样本数.
+    /// 某一行不是合法的Json时返回带有行号的错误, 之前的行已经加入.
+    pub fn add_json_lines<R: BufRead>(&mut self, input: R) -> Result<usize, String> {
+        let reader = JsonReader::new(JsonReaderSettings::default());
+        let mut added = 0;
+        for (i, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value = reader
+                .parse_value(&line)
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            self.add(&value);
+            added += 1;
+        }
+
+        Ok(added)
+    }
+
+    /// 出现过的类型名称, 与`Value`的变体同名, `Null`排在最后.
+    pub fn type_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        let counts = [
+            ("Bool", self.bools),
+            ("I64", self.integers.as_ref().map_or(0, |s| s.count)),
+            ("F64", self.floats.as_ref().map_or(0, |s| s.count)),
+            ("String", self.strings.as_ref().map_or(0, |s| s.count)),
+            ("Vec", self.arrays.as_ref().map_or(0, |s| s.count)),
+            ("Object", self.objects.as_ref().map_or(0, |s| s.count)),
+            ("Null", self.nulls),
+        ];
+        for (name, count) in counts {
+            if count > 0 {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// 输出为JSON Schema (2020-12). 只出现过`I64`的数字输出为`integer`, 否则为`number`;
+    /// 数值范围输出为`minimum`与`maximum`, 每个对象中都存在的成员列入`required`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, Shape};
+    ///
+    /// let mut shape = Shape::new();
+    /// for json in [r#"{"a":1,"b":"x"}"#, r#"{"a":2.5}"#] {
+    ///     let mut map = Map::new();
+    ///     map.merge(json).unwrap();
+    ///     shape.add_map(&map);
+    /// }
+    /// let schema = shape.to_json_schema();
+    /// assert_eq!(schema.pointer("/properties/a/type").unwrap().as_string(), Some("number"));
+    /// assert_eq!(schema.pointer("/properties/a/maximum").unwrap().as_f64(), Some(2.5));
+    /// assert_eq!(schema.pointer("/required").unwrap().to_json(), r#"["a"]"#);
+    /// ```
+    pub fn to_json_schema(&self) -> Map {
+        let mut schema = Map::new();
+        schema.insert(
+            "$schema".to_string(),
+            Value::String("https://json-schema.org/draft/2020-12/schema".to_string()),
+        );
+        schema.merge_from(self.schema());
+        schema
+    }
+
+    fn schema(&self) -> Map {
+        let mut schema = Map::new();
+        let mut types = Vec::new();
+        if self.bools > 0 {
+            types.push("boolean");
+        }
+        match (&self.integers, &self.floats) {
+            (Some(_), None) => types.push("integer"),
+            (_, Some(_)) => types.push("number"),
+            (None, None) => {}
+        }
+        if self.strings.is_some() {
+            types.push("string");
+        }
+        if self.arrays.is_some() {
+            types.push("array");
+        }
+        if self.objects.is_some() {
+            types.push("object");
+        }
+        if self.nulls > 0 {
+            types.push("null");
+        }
+        let string = |s: &str| Value::String(s.to_string());
+        match types[..] {
+            [] => return schema,
+            [name] => {
+                schema.insert("type".to_string(), string(name));
+            }
+            _ => {
+                schema.insert(
+                    "type".to_string(),
+                    Value::Vec(types.into_iter().map(string).collect()),
+                );
+            }
+        }
+
+        let mut min: Option<Number> = None;
+        let mut max: Option<Number> = None;
+        if let Some(ref range) = self.integers {
+            min = Some(Number::I64(range.min));
+            max = Some(Number::I64(range.max));
+        }
+        if let Some(ref range) = self.floats
+            && range.min <= range.max
+        {
+            let (lo, hi) = (Number::F64(range.min), Number::F64(range.max));
+            min = Some(min.map_or(lo, |m| if lo < m { lo } else { m }));
+            max = Some(max.map_or(hi, |m| if hi > m { hi } else { m }));
+        }
+        if let Some(min) = min {
+            schema.insert("minimum".to_string(), Value::from(min));
+        }
+        if let Some(max) = max {
+            schema.insert("maximum".to_string(), Value::from(max));
+        }
+
+        if let Some(format) = self.strings.as_ref().and_then(|s| s.format) {
+            schema.insert("format".to_string(), string(format.as_str()));
+        }
+
+        if let Some(ref array) = self.arrays
+            && array.items.count > 0
+        {
+            schema.insert("items".to_string(), Value::Object(array.items.schema()));
+        }
+
+        if let Some(ref object) = self.objects {
+            let mut properties = Map::new();
+            for (key, field) in &object.fields {
+                properties.insert(key.clone(), Value::Object(field.schema()));
+            }
+            schema.insert("properties".to_string(), Value::Object(properties));
+            let required: Vec<Value> = object.required().map(string).collect();
+            if !required.is_empty() {
+                schema.insert("required".to_string(), Value::Vec(required));
+            }
+        }
+
+        schema
+    }
+}
+
+impl ObjectShape {
+    /// 在每个对象中都存在的成员名称, 按名称排序.
+    pub fn required(&self) -> impl Iterator<Item = &str> {
+        self.fields
+            .iter()
+            .filter(|(_, field)| field.count == self.count)
+            .map(|(key, _)| key.as_str())
+    }
+}
+
+impl fmt::Display for Shape {
+    // 以`|`连接出现过的类型名称, 例如`I64|Null`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.type_names().join("|"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::JsonWriterSettings;
+    use std::io::Cursor;
+
+    #[test]
+    fn formats() {
+        let cases = [
+            ("2024-01-02T03:04:05Z", Some(StringFormat::DateTime)),
+            (
+                "2024-01-02t03:04:05.123-07:30",
+                Some(StringFormat::DateTime),
+            ),
+            ("2024-01-02T03:04:05", None),
+            ("2024-01-02", Some(StringFormat::Date)),
+            ("2024-13-02", None),
+            ("2024-1-02", None),
+            ("23:59:60.5+00:00", Some(StringFormat::Time)),
+            ("24:00:00Z", None),
+            ("12:30:00", None),
+            (
+                "123e4567-e89b-12d3-a456-426614174000",
+                Some(StringFormat::Uuid),
+            ),
+            ("123e4567-e89b-12d3-a456-42661417400g", None),
+            ("a.b@example.com", Some(StringFormat::Email)),
+            ("a@localhost", None),
+            ("a b@example.com", None),
+            ("https://example.com/a?b=1", Some(StringFormat::Uri)),
+            ("s3+x://bucket", Some(StringFormat::Uri)),
+            ("1http://a", None),
+            ("http://", None),
+            ("hello", None),
+            ("", None),
+        ];
+        for (s, format) in cases {
+            assert_eq!(StringFormat::detect(s), format, "{}", s);
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        let ndjson = concat!(
+            r#"{"id":1,"price":9.5,"name":"a","tags":["x"],"owner":{"email":"a@b.cc"}}"#,
+            "\n\n",
+            r#"{"id":-4,"price":10,"name":null,"tags":[],"owner":{"email":"c@d.ee","age":3}}"#,
+            "\n",
+            r#"{"id":7,"price":2.0,"name":"bc","tags":[1,"y",null]}"#,
+            "\n",
+        );
+        let mut shape = Shape::new();
+        assert_eq!(shape.add_json_lines(Cursor::new(ndjson)).unwrap(), 3);
+        assert_eq!(shape.to_string(), "Object");
+
+        let object = shape.objects.as_ref().unwrap();
+        assert_eq!(object.count, 3);
+        assert_eq!(
+            object.required().collect::<Vec<_>>(),
+            ["id", "name", "price", "tags"]
+        );
+        let fields = &object.fields;
+        assert_eq!(fields["name"].to_string(), "String|Null");
+        assert_eq!(fields["price"].to_string(), "I64|F64");
+        assert_eq!(fields["owner"].count, 2);
+
+        let id = fields["id"].integers.as_ref().unwrap();
+        assert_eq!((id.count, id.min, id.max), (3, -4, 7));
+        let price = fields["price"].floats.as_ref().unwrap();
+        assert_eq!((price.count, price.min, price.max), (2, 2.0, 9.5));
+        let name = fields["name"].strings.as_ref().unwrap();
+        assert_eq!((name.count, name.min_length, name.max_length), (2, 1, 2));
+        let tags = fields["tags"].arrays.as_ref().unwrap();
+        assert_eq!((tags.count, tags.min_length, tags.max_length), (3, 0, 3));
+        assert_eq!(tags.items.to_string(), "I64|String|Null");
+        assert_eq!(tags.items.strings.as_ref().unwrap().format, None);
+
+        let owner = fields["owner"].objects.as_ref().unwrap();
+        assert_eq!(owner.required().collect::<Vec<_>>(), ["email"]);
+        assert_eq!(
+            owner.fields["email"].strings.as_ref().unwrap().format,
+            Some(StringFormat::Email)
+        );
+
+        let error = shape.add_json_lines(Cursor::new("1\n[\n")).unwrap_err();
+        assert!(error.starts_with("line 2: "), "{}", error);
+        assert_eq!(shape.count, 4);
+    }
+
+    #[test]
+    fn json_schema() {
+        let mut shape = Shape::new();
+        shape
+            .add_json_lines(Cursor::new(concat!(
+                r#"{"a":1,"b":[{"c":"2024-01-02"}],"d":null,"e":true}"#,
+                "\n",
+                r#"{"a":-2.5,"b":[],"d":"x","e":false}"#,
+                "\n",
+            )))
+            .unwrap();
+        let schema = shape.to_json_schema();
+        let json = schema.to_json_with_settings(JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","#,
+                r#""properties":{"a":{"maximum":1,"minimum":-2.5,"type":"number"},"#,
+                r#""b":{"items":{"properties":{"c":{"format":"date","type":"string"}},"#,
+                r#""required":["c"],"type":"object"},"type":"array"},"#,
+                r#""d":{"type":["string","null"]},"e":{"type":"boolean"}},"#,
+                r#""required":["a","b","d","e"],"type":"object"}"#
+            )
+        );
+
+        // 没有样本时不限制类型.
+        let empty = Shape::new().to_json_schema();
+        assert_eq!(empty.len(), 1);
+        let mut shape = Shape::new();
+        shape.add(&Value::Vec(Vec::new()));
+        assert!(shape.to_json_schema().get("items").is_none());
+
+        // 范围只包括有限的值.
+        let mut shape = Shape::new();
+        shape.add(&Value::F64(f64::INFINITY));
+        assert!(shape.to_json_schema().get("maximum").is_none());
+        shape.add(&Value::I64(3));
+        shape.add(&Value::F64(f64::NAN));
+        let schema = shape.to_json_schema();
+        assert_eq!(schema["minimum"].as_i64(), Some(3));
+        assert_eq!(schema["maximum"].as_i64(), Some(3));
+    }
+}
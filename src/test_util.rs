@@ -0,0 +1,36 @@
+// 单元测试与基准测试共用的工具. 基准测试通过`#[path]`引入这个文件, 因此这里只能依赖标准库.
+
+// 确定性的线性同余随机数生成器, 使失败可以重现, 生成的语料每次相同.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    // 返回31位的随机数.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    // 返回64位的随机数.
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.next() << 33 ^ self.next() << 2 ^ self.next()
+    }
+
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+// 将字节转换为十六进制文本, 便于与规范中的例子比较.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,1131 @@
+use crate::path::{PathSegment, to_json_pointer};
+use crate::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::fmt::Write;
+
+// 表在文档中的路径. 表数组中的表以下标区分.
+type TablePath = Vec<PathSegment<'static>>;
+
+// 数组与内联表的最大嵌套深度.
+const DEPTH_LIMIT: usize = 128;
+
+/// 将TOML (v1.0.0)转换成`Map`的转换器.
+///
+/// 日期和时间在`Value`中没有对应的类型, 按原文转换为字符串. Json不能表示的`inf`和`nan`会返回错误.
+pub struct TomlReader<'a> {
+    text: &'a str,
+    pos: usize,
+    root: Map,
+    current: TablePath, // 最近的表头所指的表, 之后的键值对都写入这个表.
+    tables: Tables,
+}
+
+// 记录各个表的定义方式, 用于检查重复定义. 内联表使用各自独立的记录, 路径相对于内联表本身.
+#[derive(Default)]
+struct Tables {
+    defined: HashSet<TablePath>, // 通过`[...]`定义的表.
+    dotted: HashSet<TablePath>,  // 通过点分隔的键创建的表.
+    frozen: HashSet<TablePath>,  // 通过`=`赋值的内联表和数组, 之后不能再扩展.
+    arrays: HashSet<TablePath>,  // 通过`[[...]]`创建的表数组.
+}
+
+impl<'a> TomlReader<'a> {
+    pub fn new(text: &'a str) -> Self {
+        TomlReader {
+            text,
+            pos: 0,
+            root: Map::new(),
+            current: TablePath::new(),
+            tables: Tables::default(),
+        }
+    }
+
+    // 出错时, 错误信息以出错位置的`行:列`结尾, 行和列都从1开始, 列按字符计算.
+    pub fn parse(mut self) -> Result<Map, String> {
+        if self.text.starts_with('\u{feff}') {
+            self.pos = '\u{feff}'.len_utf8();
+        }
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Ok(self.root),
+                Some(b'\n' | b'\r' | b'#') => {}
+                Some(b'[') => self.parse_header()?,
+                Some(_) => self.parse_key_value_line()?,
+            }
+            self.expect_line_end()?;
+        }
+    }
+
+    fn error(&self, message: impl AsRef<str>) -> String {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        format!("{} at {}:{}", message.as_ref(), line, column)
+    }
+
+    fn error_at(&mut self, pos: usize, message: String) -> String {
+        self.pos = pos;
+        self.error(message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.text.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        let matched = self.peek() == Some(b);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn eat_newline(&mut self) -> bool {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(b'\n'), _) => self.pos += 1,
+            (Some(b'\r'), Some(b'\n')) => self.pos += 2,
+            _ => return false,
+        }
+        true
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    // 跳过空白, 换行和注释, 用于数组内部.
+    fn skip_whitespace_and_newlines(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(b'#') {
+                self.skip_comment()?;
+            } else if !self.eat_newline() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) -> Result<(), String> {
+        while let Some(b) = self.peek() {
+            if b == b'\n' || (b == b'\r' && self.peek_at(1) == Some(b'\n')) {
+                break;
+            }
+            if is_control(b) {
+                return Err(
+                    self.error(format!("Invalid control character in comment: U+{:04X}", b))
+                );
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn expect_line_end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(b'#') {
+            self.skip_comment()?;
+        }
+        if self.peek().is_none() || self.eat_newline() {
+            Ok(())
+        } else {
+            Err(self.error("Expected a newline"))
+        }
+    }
+
+    fn parse_header(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        self.pos += 1;
+        let array = self.eat(b'[');
+        let keys = self.parse_key()?;
+        if !self.eat(b']') || (array && !self.eat(b']')) {
+            let close = if array { "]]" } else { "]" };
+            return Err(self.error(format!("Expected `{}` after a table name", close)));
+        }
+
+        let result = if array {
+            self.open_table_array(&keys)
+        } else {
+            self.open_table(&keys)
+        };
+        result.map_err(|e| self.error_at(start, e))
+    }
+
+    // `[a.b.c]`: 定义表, 并使之后的键值对写入这个表.
+    fn open_table(&mut self, keys: &[String]) -> Result<(), String> {
+        let (last, prefix) = keys.split_last().expect("a key has at least one part");
+        let mut path = self.enter_prefix(keys, prefix)?;
+        let table = table_mut(&mut self.root, &path);
+        path.push(PathSegment::Key(Cow::Owned(last.clone())));
+        match table.entry(last.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(Value::Object(Map::new()));
+            }
+            Entry::Occupied(entry) => {
+                let redefined = !matches!(entry.get(), Value::Object(_))
+                    || self.tables.defined.contains(&path)
+                    || self.tables.dotted.contains(&path)
+                    || self.tables.frozen.contains(&path);
+                if redefined {
+                    return Err(format!("Table `{}` is already defined", display_keys(keys)));
+                }
+            }
+        }
+
+        self.tables.defined.insert(path.clone());
+        self.current = path;
+        Ok(())
+    }
+
+    // `[[a.b.c]]`: 向表数组添加一个表, 并使之后的键值对写入这个表.
+    fn open_table_array(&mut self, keys: &[String]) -> Result<(), String> {
+        let (last, prefix) = keys.split_last().expect("a key has at least one part");
+        let mut path = self.enter_prefix(keys, prefix)?;
+        let table = table_mut(&mut self.root, &path);
+        path.push(PathSegment::Key(Cow::Owned(last.clone())));
+        let entry = table
+            .entry(last.clone())
+            .or_insert_with(|| Value::Vec(Vec::new()));
+        match *entry {
+            Value::Vec(ref mut vec) if !self.tables.frozen.contains(&path) => {
+                vec.push(Value::Object(Map::new()));
+                self.tables.arrays.insert(path.clone());
+                path.push(PathSegment::Index(vec.len() - 1));
+            }
+            _ => {
+                return Err(format!(
+                    "Key `{}` is already defined and is not an array of tables",
+                    display_keys(keys)
+                ));
+            }
+        }
+
+        self.current = path;
+        Ok(())
+    }
+
+    // 进入表头中最后一个键之前的各个表, 不存在时隐式创建. 表数组进入其中最后一个表.
+    fn enter_prefix(&mut self, keys: &[String], prefix: &[String]) -> Result<TablePath, String> {
+        let mut path = TablePath::new();
+        for (i, key) in prefix.iter().enumerate() {
+            let table = table_mut(&mut self.root, &path);
+            let entry = table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            path.push(PathSegment::Key(Cow::Owned(key.clone())));
+            match *entry {
+                Value::Object(_) if !self.tables.frozen.contains(&path) => {}
+                Value::Vec(ref vec) if self.tables.arrays.contains(&path) => {
+                    path.push(PathSegment::Index(vec.len() - 1));
+                }
+                _ => {
+                    return Err(format!(
+                        "Cannot define table `{}` because `{}` is already defined as a value",
+                        display_keys(keys),
+                        display_keys(&keys[..=i])
+                    ));
+                }
+            }
+        }
+        Ok(path)
+    }
+
+    fn parse_key_value_line(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        let (keys, value) = self.parse_key_value(0)?;
+        let table = table_mut(&mut self.root, &self.current);
+        insert_value(table, &self.current, &keys, value, &mut self.tables)
+            .map_err(|e| self.error_at(start, e))
+    }
+
+    fn parse_key_value(&mut self, depth: usize) -> Result<(Vec<String>, Value), String> {
+        let keys = self.parse_key()?;
+        if !self.eat(b'=') {
+            return Err(self.error("Expected `=` after a key"));
+        }
+        self.skip_whitespace();
+        let value = self.parse_value(depth)?;
+        Ok((keys, value))
+    }
+
+    // 读取可能以点分隔的键, 包括前后的空白.
+    fn parse_key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    self.parse_basic_string()?
+                }
+                Some(b'\'') => {
+                    self.pos += 1;
+                    self.parse_literal_string()?
+                }
+                _ => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(is_bare_key_byte) {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("Expected a key"));
+                    }
+                    self.text[start..self.pos].to_string()
+                }
+            };
+            keys.push(key);
+
+            self.skip_whitespace();
+            if !self.eat(b'.') {
+                return Ok(keys);
+            }
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value, String> {
+        let rest = &self.text[self.pos..];
+        let value = match self.peek() {
+            Some(b'"') if rest.starts_with("\"\"\"") => {
+                self.pos += 3;
+                Value::String(self.parse_multiline_string(true)?)
+            }
+            Some(b'"') => {
+                self.pos += 1;
+                Value::String(self.parse_basic_string()?)
+            }
+            Some(b'\'') if rest.starts_with("'''") => {
+                self.pos += 3;
+                Value::String(self.parse_multiline_string(false)?)
+            }
+            Some(b'\'') => {
+                self.pos += 1;
+                Value::String(self.parse_literal_string()?)
+            }
+            Some(b'[' | b'{') if depth >= DEPTH_LIMIT => {
+                return Err(self.error(format!("The set depth limit is exceeded: {}", DEPTH_LIMIT)));
+            }
+            Some(b'[') => self.parse_array(depth)?,
+            Some(b'{') => self.parse_inline_table(depth)?,
+            _ => self.parse_scalar()?,
+        };
+        Ok(value)
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Value, String> {
+        self.pos += 1;
+        let mut vec = Vec::new();
+        loop {
+            self.skip_whitespace_and_newlines()?;
+            if self.eat(b']') {
+                break;
+            }
+            vec.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace_and_newlines()?;
+            if self.eat(b']') {
+                break;
+            }
+            if !self.eat(b',') {
+                return Err(self.error("Expected `,` or `]` in an array"));
+            }
+        }
+        Ok(Value::Vec(vec))
+    }
+
+    // 内联表必须写在一行之内, 最后一个键值对之后不能有逗号.
+    fn parse_inline_table(&mut self, depth: usize) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        let mut tables = Tables::default();
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return Ok(Value::Object(map));
+        }
+
+        loop {
+            let start = self.pos;
+            let (keys, value) = self.parse_key_value(depth + 1)?;
+            insert_value(&mut map, &TablePath::new(), &keys, value, &mut tables)
+                .map_err(|e| self.error_at(start, e))?;
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return Ok(Value::Object(map));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("Expected `,` or `}` in an inline table"));
+            }
+        }
+    }
+
+    // 读取单行的基本字符串, 起始的引号已经读取.
+    fn parse_basic_string(&mut self) -> Result<String, String> {
+        let mut s = String::new();
+        loop {
+            let start = self.pos;
+            while self
+                .peek()
+                .is_some_and(|b| b != b'"' && b != b'\\' && !is_control(b))
+            {
+                self.pos += 1;
+            }
+            s.push_str(&self.text[start..self.pos]);
+
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    self.parse_escape(&mut s)?;
+                }
+                None | Some(b'\n') => return Err(self.error("Unterminated string")),
+                Some(b) => return Err(self.error(control_error(b))),
+            }
+        }
+    }
+
+    // 读取单行的字面量字符串, 起始的引号已经读取.
+    fn parse_literal_string(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b != b'\'' && !is_control(b)) {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            Some(b'\'') => {
+                self.pos += 1;
+                Ok(self.text[start..self.pos - 1].to_string())
+            }
+            None | Some(b'\n') => Err(self.error("Unterminated string")),
+            Some(b) => Err(self.error(control_error(b))),
+        }
+    }
+
+    // 读取多行字符串, 起始的三个引号已经读取. 紧跟起始引号的换行不属于字符串.
+    fn parse_multiline_string(&mut self, basic: bool) -> Result<String, String> {
+        let quote = if basic { b'"' } else { b'\'' };
+        let mut s = String::new();
+        self.eat_newline();
+        loop {
+            let start = self.pos;
+            while self.peek().is_some_and(|b| {
+                b != quote && !(basic && b == b'\\') && (b == b'\n' || !is_control(b))
+            }) {
+                self.pos += 1;
+            }
+            s.push_str(&self.text[start..self.pos]);
+
+            match self.peek() {
+                Some(b) if b == quote => {
+                    let count = self.text.as_bytes()[self.pos..]
+                        .iter()
+                        .take_while(|&&c| c == quote)
+                        .count();
+                    self.pos += count;
+                    // 结束的引号之前最多可以紧跟两个引号
+                    if count >= 3 {
+                        if count > 5 {
+                            return Err(self.error("Too many quotes at the end of a string"));
+                        }
+                        s.extend(std::iter::repeat_n(quote as char, count - 3));
+                        return Ok(s);
+                    }
+                    s.extend(std::iter::repeat_n(quote as char, count));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = self.pos;
+                    self.skip_whitespace();
+                    if self.eat_newline() {
+                        // 行尾的反斜杠: 去掉之后所有的空白和换行
+                        while self.eat_newline() || matches!(self.peek(), Some(b' ' | b'\t')) {
+                            self.skip_whitespace();
+                        }
+                    } else {
+                        self.pos = escape;
+                        self.parse_escape(&mut s)?;
+                    }
+                }
+                Some(b'\r') if self.peek_at(1) == Some(b'\n') => {
+                    self.pos += 2;
+                    s.push_str("\r\n");
+                }
+                None => return Err(self.error("Unterminated string")),
+                Some(b) => return Err(self.error(control_error(b))),
+            }
+        }
+    }
+
+    // 读取转义序列, 反斜杠已经读取.
+    fn parse_escape(&mut self, s: &mut String) -> Result<(), String> {
+        let escaped = match self.peek() {
+            Some(b'b') => '\u{8}',
+            Some(b't') => '\t',
+            Some(b'n') => '\n',
+            Some(b'f') => '\u{c}',
+            Some(b'r') => '\r',
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b @ (b'u' | b'U')) => {
+                let len = if b == b'u' { 4 } else { 8 };
+                let hex = self
+                    .text
+                    .get(self.pos + 1..self.pos + 1 + len)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or_else(|| self.error("Invalid Unicode escape sequence"))?;
+                let c = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error(format!("Invalid Unicode scalar value: {}", hex)))?;
+                self.pos += len;
+                c
+            }
+            _ => return Err(self.error("Invalid escape sequence")),
+        };
+
+        self.pos += 1;
+        s.push(escaped);
+        Ok(())
+    }
+
+    // 读取布尔值, 数字, 日期和时间.
+    fn parse_scalar(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        self.skip_scalar();
+        // 日期与时间之间可以用空格分隔, 例如`1979-05-27 07:32:00`
+        let bytes = self.text.as_bytes();
+        if is_date(&bytes[start..self.pos])
+            && self.peek() == Some(b' ')
+            && bytes
+                .get(self.pos + 1..self.pos + 3)
+                .is_some_and(|b| b.iter().all(u8::is_ascii_digit))
+            && self.peek_at(3) == Some(b':')
+        {
+            self.pos += 1;
+            self.skip_scalar();
+        }
+
+        let token = &self.text[start..self.pos];
+        let value = match token {
+            "" => return Err(self.error("Expected a value")),
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            // Json不能表示无穷大和NaN, 读取后无法写成合法的Json, 所以直接报错.
+            "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => {
+                return Err(self.error_at(
+                    start,
+                    format!("Non-finite float is not supported in JSON: `{}`", token),
+                ));
+            }
+            _ if is_datetime(token.as_bytes()) => Value::String(token.to_string()),
+            _ => parse_number(token).map_err(|e| self.error_at(start, e))?,
+        };
+        Ok(value)
+    }
+
+    fn skip_scalar(&mut self) {
+        while self.peek().is_some_and(|b| {
+            b.is_ascii_alphanumeric() || matches!(b, b'_' | b'+' | b'-' | b'.' | b':')
+        }) {
+            self.pos += 1;
+        }
+    }
+}
+
+// 返回路径所指的表. 路径总是指向已经创建的表.
+fn table_mut<'m>(mut table: &'m mut Map, path: &[PathSegment]) -> &'m mut Map {
+    let mut segments = path.iter().peekable();
+    while let Some(segment) = segments.next() {
+        let key = segment.as_key().expect("a table path starts with a key");
+        let mut value = table.get_mut(key).expect("the table exists");
+        if let Some(index) = segments.peek().and_then(|s| s.as_index()) {
+            segments.next();
+            value = match *value {
+                Value::Vec(ref mut vec) => &mut vec[index],
+                _ => unreachable!("an index follows an array of tables"),
+            };
+        }
+        table = match *value {
+            Value::Object(ref mut map) => map,
+            _ => unreachable!("the path refers to a table"),
+        };
+    }
+    table
+}
+
+// 将键值对写入路径为`base`的表`table`. 点分隔的键隐式创建中间的表, 但不能扩展通过其它方式定义的表.
+fn insert_value(
+    mut table: &mut Map,
+    base: &TablePath,
+    keys: &[String],
+    value: Value,
+    tables: &mut Tables,
+) -> Result<(), String> {
+    let (last, prefix) = keys.split_last().expect("a key has at least one part");
+    let mut path = base.clone();
+    for (i, key) in prefix.iter().enumerate() {
+        path.push(PathSegment::Key(Cow::Owned(key.clone())));
+        let entry = table.entry(key.clone()).or_insert_with(|| {
+            tables.dotted.insert(path.clone());
+            Value::Object(Map::new())
+        });
+        table = match *entry {
+            Value::Object(ref mut map) if tables.dotted.contains(&path) => map,
+            _ => {
+                return Err(format!(
+                    "Cannot add key `{}` because `{}` is already defined",
+                    display_keys(keys),
+                    display_keys(&keys[..=i])
+                ));
+            }
+        };
+    }
+
+    match table.entry(last.clone()) {
+        Entry::Occupied(_) => Err(format!("Duplicate key `{}`", display_keys(keys))),
+        Entry::Vacant(entry) => {
+            if matches!(value, Value::Object(_) | Value::Vec(_)) {
+                path.push(PathSegment::Key(Cow::Owned(last.clone())));
+                tables.frozen.insert(path);
+            }
+            entry.insert(value);
+            Ok(())
+        }
+    }
+}
+
+fn parse_number(token: &str) -> Result<Value, String> {
+    let invalid = || format!("Invalid value: `{}`", token);
+    let radix = match token.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+
+    if radix != 10 {
+        let digits = &token[2..];
+        if !is_digits(digits, |b| (b as char).is_digit(radix)) {
+            return Err(invalid());
+        }
+        return i64::from_str_radix(&digits.replace('_', ""), radix)
+            .map(Value::I64)
+            .map_err(|_| format!("Integer out of range for I64: `{}`", token));
+    }
+
+    let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let decimal = |s: &str| is_digits(s, |b| b.is_ascii_digit());
+    let valid = decimal(integer)
+        && !(integer.len() > 1 && integer.starts_with('0'))
+        && fraction.is_none_or(decimal)
+        && exponent.is_none_or(|e| decimal(e.strip_prefix(['+', '-']).unwrap_or(e)));
+    if !valid {
+        return Err(invalid());
+    }
+
+    let number = token.replace('_', "");
+    if fraction.is_none() && exponent.is_none() {
+        number
+            .parse()
+            .map(Value::I64)
+            .map_err(|_| format!("Integer out of range for I64: `{}`", token))
+    } else {
+        number.parse().map(Value::F64).map_err(|_| invalid())
+    }
+}
+
+// 检查数字: 不能为空, 下划线只能出现在两个数字之间.
+fn is_digits(s: &str, is_digit: impl Fn(u8) -> bool) -> bool {
+    let bytes = s.as_bytes();
+    bytes.first().is_some_and(|&b| is_digit(b))
+        && bytes.last().is_some_and(|&b| is_digit(b))
+        && !s.contains("__")
+        && bytes.iter().all(|&b| b == b'_' || is_digit(b))
+}
+
+// 日期, 时间或日期时间: `1979-05-27`, `07:32:00.999`, `1979-05-27T07:32:00-08:00`.
+fn is_datetime(token: &[u8]) -> bool {
+    if is_time(token) {
+        return true;
+    }
+    if token.len() < 10 || !is_date(&token[..10]) {
+        return false;
+    }
+    let rest = &token[10..];
+    let Some((b'T' | b't' | b' ', rest)) = rest.split_first() else {
+        return rest.is_empty();
+    };
+
+    let end = rest
+        .iter()
+        .position(|b| matches!(b, b'Z' | b'z' | b'+' | b'-'))
+        .unwrap_or(rest.len());
+    let (time, offset) = rest.split_at(end);
+    is_time(time)
+        && match offset {
+            [] | [b'Z' | b'z'] => true,
+            [b'+' | b'-', hour @ .., b':', m1, m2] => {
+                is_two_digits(hour, 0, 23) && is_two_digits(&[*m1, *m2], 0, 59)
+            }
+            _ => false,
+        }
+}
+
+fn is_date(s: &[u8]) -> bool {
+    s.len() == 10
+        && s[..4].iter().all(u8::is_ascii_digit)
+        && s[4] == b'-'
+        && is_two_digits(&s[5..7], 1, 12)
+        && s[7] == b'-'
+        && is_two_digits(&s[8..10], 1, 31)
+}
+
+fn is_time(s: &[u8]) -> bool {
+    s.len() >= 8
+        && is_two_digits(&s[..2], 0, 23)
+        && s[2] == b':'
+        && is_two_digits(&s[3..5], 0, 59)
+        && s[5] == b':'
+        && is_two_digits(&s[6..8], 0, 60)
+        && match s[8..].split_first() {
+            None => true,
+            Some((b'.', fraction)) => {
+                !fraction.is_empty() && fraction.iter().all(u8::is_ascii_digit)
+            }
+            Some(_) => false,
+        }
+}
+
+fn is_two_digits(s: &[u8], min: u8, max: u8) -> bool {
+    match *s {
+        [a @ b'0'..=b'9', b @ b'0'..=b'9'] => (min..=max).contains(&((a - b'0') * 10 + b - b'0')),
+        _ => false,
+    }
+}
+
+fn is_bare_key_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'-'
+}
+
+// 除制表符之外的控制字符不能直接出现在字符串和注释中.
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && b != b'\t') || b == 0x7f
+}
+
+fn control_error(b: u8) -> String {
+    format!("Invalid control character in string: U+{:04X}", b)
+}
+
+// 以TOML的形式显示点分隔的键, 用于错误信息和表头.
+fn display_keys<S: AsRef<str>>(keys: &[S]) -> String {
+    let mut out = String::new();
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            out.push('.');
+        }
+        write_key(&mut out, key.as_ref());
+    }
+    out
+}
+
+/// 将`Map`转换成TOML的转换器.
+///
+/// 键按字典序排列. 对象写为表, 元素全部是对象的非空数组写为表数组, 其余的值写为键值对;
+/// 数组中的对象写为内联表. TOML无法表示的`Null`, 以及同时包含对象和其它值的数组会返回错误.
+pub struct TomlWriter;
+
+impl TomlWriter {
+    pub fn format(&self, map: &Map) -> Result<String, String> {
+        let mut out = String::new();
+        // 待写入的表: 路径, 表, 是否为表数组中的元素. 按深度优先的顺序写入, 子表紧跟在所属的表之后.
+        let mut stack = vec![(Vec::new(), map, false)];
+        while let Some((path, table, in_array)) = stack.pop() {
+            let mut keys: Vec<&String> = table.keys().collect();
+            keys.sort();
+
+            let mut values = Vec::new();
+            let mut children = Vec::new();
+            for key in keys {
+                let mut child_path = path.clone();
+                child_path.push(PathSegment::Key(Cow::Borrowed(key.as_str())));
+                match table[key] {
+                    Value::Object(ref map) => children.push((child_path, map, false)),
+                    Value::Vec(ref vec) if is_table_array(vec) => {
+                        for (i, value) in vec.iter().enumerate() {
+                            if let Value::Object(ref map) = *value {
+                                let mut element_path = child_path.clone();
+                                element_path.push(PathSegment::Index(i));
+                                children.push((element_path, map, true));
+                            }
+                        }
+                    }
+                    ref value => values.push((key, child_path, value)),
+                }
+            }
+
+            // 只包含子表的表不需要表头
+            if in_array || (!path.is_empty() && (!values.is_empty() || children.is_empty())) {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let keys: Vec<&str> = path.iter().filter_map(PathSegment::as_key).collect();
+                let (open, close) = if in_array { ("[[", "]]") } else { ("[", "]") };
+                let _ = writeln!(out, "{}{}{}", open, display_keys(&keys), close);
+            }
+
+            for (key, path, value) in values {
+                write_key(&mut out, key);
+                out.push_str(" = ");
+                write_inline(&mut out, value, &path)?;
+                out.push('\n');
+            }
+
+            stack.extend(children.into_iter().rev());
+        }
+
+        Ok(out)
+    }
+}
+
+fn is_table_array(vec: &[Value]) -> bool {
+    !vec.is_empty() && vec.iter().all(|v| matches!(v, Value::Object(_)))
+}
+
+// 以行内的形式写入值: 数组写为`[1, 2]`, 对象写为内联表`{ a = 1 }`.
+fn write_inline(out: &mut String, value: &Value, path: &[PathSegment]) -> Result<(), String> {
+    match *value {
+        Value::Null => {
+            return Err(format!(
+                "TOML cannot represent null at {}",
+                to_json_pointer(path)
+            ));
+        }
+        Value::Bool(b) => {
+            let _ = write!(out, "{}", b);
+        }
+        Value::I64(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        Value::F64(n) if n.is_nan() => out.push_str("nan"),
+        Value::F64(n) if n.is_infinite() => out.push_str(if n > 0.0 { "inf" } else { "-inf" }),
+        // `Debug`的输出总是包含小数点或指数, 不会与整数混淆
+        Value::F64(n) => {
+            let _ = write!(out, "{:?}", n);
+        }
+        Value::String(ref s) => write_string(out, s),
+        Value::Vec(ref vec) => {
+            let objects = vec.iter().filter(|v| matches!(v, Value::Object(_))).count();
+            if objects > 0 && objects < vec.len() {
+                return Err(format!(
+                    "TOML cannot mix tables and other values in one array at {}",
+                    to_json_pointer(path)
+                ));
+            }
+
+            out.push('[');
+            for (i, value) in vec.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let mut path = path.to_vec();
+                path.push(PathSegment::Index(i));
+                write_inline(out, value, &path)?;
+            }
+            out.push(']');
+        }
+        Value::Object(ref map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                out.push_str(if i > 0 { ", " } else { " " });
+                write_key(out, key);
+                out.push_str(" = ");
+                let mut path = path.to_vec();
+                path.push(PathSegment::Key(Cow::Borrowed(key.as_str())));
+                write_inline(out, &map[key], &path)?;
+            }
+            out.push_str(if map.is_empty() { "}" } else { " }" });
+        }
+    }
+
+    Ok(())
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if !key.is_empty() && key.bytes().all(is_bare_key_byte) {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' || c == '\u{7f}' => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{JsonWriterSettings, Map, Value};
+
+    fn to_json(map: &Map) -> String {
+        map.to_json_with_settings(JsonWriterSettings {
+            sort_keys: true,
+            ..Default::default()
+        })
+    }
+
+    fn parse(toml: &str) -> String {
+        match Map::from_toml(toml) {
+            Ok(map) => to_json(&map),
+            Err(e) => panic!("{}: {:?}", e, toml),
+        }
+    }
+
+    #[test]
+    fn tables() {
+        let toml = r#"
+# 注释
+title = "TOML" # 行尾注释
+
+[owner]
+name = "Tom"
+site."google.com" = true
+
+[database]
+ports = [ 8000, 8001,
+  8002, # 注释
+]
+temp_targets = { cpu = 79.5, case.max = 72.0 }
+
+[servers.alpha]
+ip = "10.0.0.1"
+
+[[products]]
+name = "Hammer"
+
+[products.size]
+unit = "cm"
+
+[[products]]
+
+[[products]]
+name = "Nail"
+"#;
+        assert_eq!(
+            parse(toml),
+            r#"{"database":{"ports":[8000,8001,8002],"temp_targets":{"case":{"max":72},"cpu":79.5}},"owner":{"name":"Tom","site":{"google.com":true}},"products":[{"name":"Hammer","size":{"unit":"cm"}},{},{"name":"Nail"}],"servers":{"alpha":{"ip":"10.0.0.1"}},"title":"TOML"}"#
+        );
+
+        // 可以通过表头在点分隔的键创建的表中定义子表, 也可以在定义子表之后定义上层的表
+        assert_eq!(
+            parse("[fruit]\napple.color = 'red'\n[fruit.apple.texture]\nsmooth = true\n"),
+            r#"{"fruit":{"apple":{"color":"red","texture":{"smooth":true}}}}"#
+        );
+        assert_eq!(
+            parse("[x.y.z]\n[x]\na = 1"),
+            r#"{"x":{"a":1,"y":{"z":{}}}}"#
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let toml = concat!(
+            "basic = \"tab\\t quote\\\" \\u00e9 \\U0001F600\"\n",
+            "literal = 'C:\\Users\\nodejs'\n",
+            "multi = \"\"\"\nRoses\nare \\\n    red\"\"\"\n",
+            "quotes = \"\"\"\"\"x\"\"\"\"\"\n",
+            "raw = '''\nfirst\n\\n'''\n",
+            "crlf = \"\"\"a\r\nb\"\"\"\n",
+            "'key with spaces' = \"\"\n",
+        );
+        assert_eq!(
+            parse(toml),
+            r#"{"basic":"tab\t quote\" é 😀","crlf":"a\r\nb","key with spaces":"","literal":"C:\\Users\\nodejs","multi":"Roses\nare red","quotes":"\"\"x\"\"","raw":"first\n\\n"}"#
+        );
+    }
+
+    #[test]
+    fn numbers_and_datetimes() {
+        let toml = "ints = [+99, -17, 0, 1_000, 0xDEAD_beef, 0o755, 0b1101]
+floats = [+1.0, 2.5, -0.01, 5e+22, 1e06, -2E-2, 6.626e-34, 224_617.445_991]
+dates = [1979-05-27T07:32:00Z, 1979-05-27 00:32:00.999999-07:00, 1979-05-27T07:32:00, 1979-05-27, 00:32:00.5]
+";
+        let map = Map::from_toml(toml).unwrap();
+        let json = |key: &str| map[key].to_json();
+        assert_eq!(json("ints"), "[99,-17,0,1000,3735928559,493,13]");
+        let floats: Vec<f64> = map["floats"]
+            .as_vec()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_f64().unwrap())
+            .collect();
+        assert_eq!(
+            floats,
+            [1.0, 2.5, -0.01, 5e22, 1e6, -0.02, 6.626e-34, 224617.445991]
+        );
+        assert_eq!(
+            json("dates"),
+            r#"["1979-05-27T07:32:00Z","1979-05-27 00:32:00.999999-07:00","1979-05-27T07:32:00","1979-05-27","00:32:00.5"]"#
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let cases = [
+            ("a = 1\na = 2", "Duplicate key `a` at 2:1"),
+            ("a = 01", "Invalid value: `01` at 1:5"),
+            ("a = 1__0", "Invalid value: `1__0` at 1:5"),
+            ("a = .5", "Invalid value: `.5` at 1:5"),
+            (
+                "a = 9223372036854775808",
+                "Integer out of range for I64: `9223372036854775808` at 1:5",
+            ),
+            ("a = 1979-13-01", "Invalid value: `1979-13-01` at 1:5"),
+            (
+                "a = [1.5, -inf]",
+                "Non-finite float is not supported in JSON: `-inf` at 1:11",
+            ),
+            (
+                "a = nan",
+                "Non-finite float is not supported in JSON: `nan` at 1:5",
+            ),
+            ("a = \"b\nc\"", "Unterminated string at 1:7"),
+            ("a = \"\\x\"", "Invalid escape sequence at 1:7"),
+            ("a = 1 b = 2", "Expected a newline at 1:7"),
+            ("a = ", "Expected a value at 1:5"),
+            ("= 1", "Expected a key at 1:1"),
+            ("a = [1 2]", "Expected `,` or `]` in an array at 1:8"),
+            ("a = { b = 1, }", "Expected a key at 1:14"),
+            (
+                "a = { b = 1\n}",
+                "Expected `,` or `}` in an inline table at 1:12",
+            ),
+            ("[a]\n[a]", "Table `a` is already defined at 2:1"),
+            ("a.b = 1\n[a]", "Table `a` is already defined at 2:1"),
+            (
+                "[a.b.c]\n[a]\nb.c.d = 1",
+                "Cannot add key `b.c.d` because `b` is already defined at 3:1",
+            ),
+            (
+                "a = { b = 1 }\n[a.c]",
+                "Cannot define table `a.c` because `a` is already defined as a value at 2:1",
+            ),
+            (
+                "a = [1]\n[[a]]",
+                "Key `a` is already defined and is not an array of tables at 2:1",
+            ),
+            (
+                "a = []\n[[a]]",
+                "Key `a` is already defined and is not an array of tables at 2:1",
+            ),
+            ("[[a]]\n[a]", "Table `a` is already defined at 2:1"),
+            (
+                "\"é\" = 'x\u{1}'",
+                "Invalid control character in string: U+0001 at 1:9",
+            ),
+        ];
+        for (toml, expected) in cases {
+            match Map::from_toml(toml) {
+                Ok(_) => panic!("Expected an Err for {:?}", toml),
+                Err(e) => assert_eq!(e, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn write() {
+        let mut map = Map::new();
+        map.merge(
+            r#"{"title":"a \"b\"\n","n":1,"x":1.0,"list":[1,[2.5,"c"],[{"k":true}]],"empty":[],
+            "owner":{"name":"Tom","dob":"1979-05-27"},"a":{"b":{"c d":{}}},
+            "products":[{"name":"Hammer","size":{"unit":"cm"}},{}]}"#,
+        )
+        .unwrap();
+
+        let toml = map.to_toml().unwrap();
+        assert_eq!(
+            toml,
+            r#"empty = []
+list = [1, [2.5, "c"], [{ k = true }]]
+n = 1
+title = "a \"b\"\n"
+x = 1.0
+
+[a.b."c d"]
+
+[owner]
+dob = "1979-05-27"
+name = "Tom"
+
+[[products]]
+name = "Hammer"
+
+[products.size]
+unit = "cm"
+
+[[products]]
+"#
+        );
+        assert!(Map::from_toml(&toml).unwrap() == map);
+    }
+
+    #[test]
+    fn write_errors() {
+        let mut map = Map::new();
+        map.merge(r#"{"a":{"b":[1,null]}}"#).unwrap();
+        assert_eq!(
+            map.to_toml().err().unwrap(),
+            "TOML cannot represent null at /a/b/1"
+        );
+
+        let mut map = Map::new();
+        map.merge(r#"{"a":[{"b":1},2]}"#).unwrap();
+        assert_eq!(
+            map.to_toml().err().unwrap(),
+            "TOML cannot mix tables and other values in one array at /a"
+        );
+
+        let mut map = Map::new();
+        map.insert("f".to_string(), Value::F64(f64::NEG_INFINITY));
+        assert_eq!(map.to_toml().unwrap(), "f = -inf\n");
+    }
+}
@@ -1,8 +1,30 @@
-use crate::Map;
+use crate::canonical;
+use crate::cbor::{CborReader, CborWriter};
+use crate::compare::{self, Mismatch};
+use crate::csv;
+use crate::json_reader::JsonReader;
+use crate::json_writer::JsonWriter;
+use crate::msgpack::{MsgpackReader, MsgpackWriter};
+use crate::path;
+use crate::walk::{self, Children, ChildrenMut};
+use crate::{
+    CborReaderSettings, CborWriterSettings, CompareSettings, CsvWriterSettings, JsonReaderSettings,
+    JsonWriterSettings, Map, MsgpackReaderSettings, Number, NumberError, PathSegment, Visitor,
+    Walk,
+};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::hash::Hasher;
+use std::io::Write;
+use std::mem;
 
 /// `Map`的指定值类型.
-#[derive(Clone, PartialEq)]
+///
+/// `Value`实现了`Drop`, 以显式的栈释放嵌套的对象和数组, 因此不能通过模式匹配移出其中的字段,
+/// 需要时请使用`std::mem::replace`等方式取出.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum Value {
+    #[default]
     Null,
     Bool(bool),
     F64(f64),
@@ -24,18 +46,107 @@ impl Value {
         }
     }
 
-    pub fn as_f64(&self) -> Option<f64> {
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::F64(_) | Value::I64(_))
+    }
+
+    /// 返回数字的只读视图, 不是数字时返回None.
+    pub fn as_number(&self) -> Option<Number> {
         match *self {
-            Value::F64(n) => Some(n),
+            Value::F64(n) => Some(Number::F64(n)),
+            Value::I64(n) => Some(Number::I64(n)),
             _ => None,
         }
     }
 
+    /// 返回`f64`, `I64`能够精确表示为`f64`时也会返回.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// assert_eq!(Value::I64(3).as_f64(), Some(3.0));
+    /// assert_eq!(Value::I64(i64::MAX).as_f64(), None);
+    /// assert_eq!(Value::F64(3.0).as_i64(), Some(3));
+    /// assert_eq!(Value::F64(3.5).as_i64(), None);
+    /// assert_eq!(Value::I64(-1).as_u64(), None);
+    /// ```
+    pub fn as_f64(&self) -> Option<f64> {
+        self.to_f64().ok()
+    }
+
+    /// 返回`i64`, 值为整数且在范围内的`F64`也会返回.
     pub fn as_i64(&self) -> Option<i64> {
-        match *self {
-            Value::I64(n) => Some(n),
-            _ => None,
-        }
+        self.to_i64().ok()
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.to_u64().ok()
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.to_i32().ok()
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.to_u32().ok()
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.to_usize().ok()
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.to_f32().ok()
+    }
+
+    /// 无损转换为`f64`, 失败时返回具体原因, 参见`Number::to_f64`.
+    pub fn to_f64(&self) -> Result<f64, NumberError> {
+        self.to_number()?.to_f64()
+    }
+
+    /// 无损转换为`i64`, 失败时返回具体原因.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Number, NumberError, Value};
+    ///
+    /// assert_eq!(Value::F64(8080.0).to_i64(), Ok(8080));
+    /// assert_eq!(Value::Null.to_i64(), Err(NumberError::NotANumber));
+    /// assert_eq!(Value::I64(70000).to_i32(), Ok(70000));
+    /// assert_eq!(
+    ///     Value::I64(-1).to_u32(),
+    ///     Err(NumberError::OutOfRange { value: Number::I64(-1), target: "u32" })
+    /// );
+    /// ```
+    pub fn to_i64(&self) -> Result<i64, NumberError> {
+        self.to_number()?.to_i64()
+    }
+
+    pub fn to_u64(&self) -> Result<u64, NumberError> {
+        self.to_number()?.to_u64()
+    }
+
+    pub fn to_i32(&self) -> Result<i32, NumberError> {
+        self.to_number()?.to_i32()
+    }
+
+    pub fn to_u32(&self) -> Result<u32, NumberError> {
+        self.to_number()?.to_u32()
+    }
+
+    pub fn to_usize(&self) -> Result<usize, NumberError> {
+        self.to_number()?.to_usize()
+    }
+
+    pub fn to_f32(&self) -> Result<f32, NumberError> {
+        self.to_number()?.to_f32()
+    }
+
+    fn to_number(&self) -> Result<Number, NumberError> {
+        self.as_number().ok_or(NumberError::NotANumber)
     }
 
     pub fn as_string(&self) -> Option<&str> {
@@ -58,4 +169,460 @@ impl Value {
             _ => None,
         }
     }
+
+    pub fn as_string_mut(&mut self) -> Option<&mut String> {
+        match *self {
+            Value::String(ref mut s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// 返回数组的可变引用, 可以增删其中的元素.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let mut value = Value::Vec(vec![Value::I64(1)]);
+    /// value.as_vec_mut().unwrap().push(Value::Null);
+    /// assert_eq!(value.to_json(), "[1,null]");
+    /// ```
+    pub fn as_vec_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match *self {
+            Value::Vec(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match *self {
+            Value::Object(ref mut o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// 与`expected`进行语义比较, 带有默认设置: `I64`与`F64`按数学值比较, 其他保持严格.
+    /// 返回所有差异, 相等时返回空的`Vec`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, Value};
+    ///
+    /// let mut actual = Map::new();
+    /// actual.merge(r#"{"a":1,"b":[true,"x"]}"#).unwrap();
+    /// let mut expected = Map::new();
+    /// expected.merge(r#"{"a":1.0,"b":[true,"y"],"c":null}"#).unwrap();
+    ///
+    /// let (actual, expected) = (Value::Object(actual), Value::Object(expected));
+    /// let mismatches = actual.compare(&expected);
+    /// assert_eq!(mismatches.len(), 2);
+    /// assert_eq!(mismatches[0].pointer, "/b/1");
+    /// assert_eq!(mismatches[1].to_string(), "Missing null at /c");
+    /// ```
+    pub fn compare<'a>(&'a self, expected: &'a Value) -> Vec<Mismatch<'a>> {
+        self.compare_with_settings(expected, CompareSettings::default())
+    }
+
+    /// 与`expected`进行语义比较, 自定义比较设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{CompareSettings, Tolerance, Value};
+    ///
+    /// let actual = Value::Vec(vec![Value::F64(0.1 + 0.2), Value::I64(1)]);
+    /// let expected = Value::Vec(vec![Value::I64(1), Value::F64(0.3)]);
+    /// let settings = CompareSettings {
+    ///     tolerance: Tolerance::Absolute(1e-9),
+    ///     ignore_array_order: true,
+    ///     ..Default::default()
+    /// };
+    /// assert!(actual.compare_with_settings(&expected, settings).is_empty());
+    /// ```
+    pub fn compare_with_settings<'a>(
+        &'a self,
+        expected: &'a Value,
+        settings: CompareSettings,
+    ) -> Vec<Mismatch<'a>> {
+        compare::compare_values(self, expected, &settings)
+    }
+
+    /// 按`CanonicalValue`的规则比较两个值, 可以直接用于`sort_by`.
+    pub fn canonical_cmp(&self, other: &Value) -> Ordering {
+        canonical::compare(self, other)
+    }
+
+    /// 按`CanonicalValue`的规则计算哈希值, 与`canonical_cmp`相等的值哈希值也相等.
+    pub fn canonical_hash<H: Hasher>(&self, state: &mut H) {
+        canonical::hash(self, state)
+    }
+
+    /// 取出值并在原处留下`Null`.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let mut value = Value::Vec(vec![Value::String("a".to_string())]);
+    /// let s = value.as_vec_mut().unwrap()[0].take();
+    /// assert_eq!(s.as_string(), Some("a"));
+    /// assert_eq!(value.to_json(), "[null]");
+    ///
+    /// let old = value.replace(Value::Bool(true));
+    /// assert_eq!(old.to_json(), "[null]");
+    /// assert_eq!(value.as_bool(), Some(true));
+    /// ```
+    pub fn take(&mut self) -> Value {
+        mem::replace(self, Value::Null)
+    }
+
+    /// 用`value`替换自身, 返回原来的值.
+    pub fn replace(&mut self, value: Value) -> Value {
+        mem::replace(self, value)
+    }
+
+    /// 深度优先遍历自身及所有子节点, 自身的路径为空. 返回是否被`Walk::Stop`提前结束.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, PathSegment, Value, Walk, to_json_pointer};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"a":{"b":["x",1,"y"]}}"#).unwrap();
+    /// let value = Value::Object(map);
+    ///
+    /// let mut strings = Vec::new();
+    /// value.walk(&mut |path: &[PathSegment], value: &Value| {
+    ///     if let Some(s) = value.as_string() {
+    ///         strings.push(format!("{}={}", to_json_pointer(path), s));
+    ///     }
+    ///     Walk::Continue
+    /// });
+    /// strings.sort();
+    /// assert_eq!(strings, ["/a/b/0=x", "/a/b/2=y"]);
+    /// ```
+    pub fn walk<V: Visitor + ?Sized>(&self, visitor: &mut V) -> bool {
+        match visitor.visit(&[], self) {
+            Walk::Continue => match Children::of_value(self) {
+                Some(children) => walk::walk(children, visitor),
+                None => false,
+            },
+            Walk::SkipChildren => false,
+            Walk::Stop => true,
+        }
+    }
+
+    /// 对自身及所有子节点中每个不是数组或对象的值调用`f`, 参数为值的路径和值的可变引用.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let mut value = Value::Vec(vec![Value::F64(1.26), Value::Vec(vec![Value::F64(2.5)])]);
+    /// value.map_values(|_, value| {
+    ///     if let Value::F64(f) = *value {
+    ///         *value = Value::F64((f * 10.0).round() / 10.0);
+    ///     }
+    /// });
+    /// assert_eq!(value.to_json(), "[1.3,[2.5]]");
+    /// ```
+    pub fn map_values<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[PathSegment], &mut Value),
+    {
+        match ChildrenMut::of_value(self) {
+            Some(children) => walk::map_values(children, f),
+            None => f(&[], self),
+        }
+    }
+
+    /// 删除所有`f`返回false的对象成员和数组元素, 被删除的节点的子节点不会被访问.
+    /// 参数中的数组下标为元素在过滤后的数组中的位置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let mut value = Value::Vec(vec![Value::Null, Value::Vec(vec![Value::I64(1), Value::Null])]);
+    /// value.retain_deep(|_, value| !value.is_null());
+    /// assert_eq!(value.to_json(), "[[1]]");
+    /// ```
+    pub fn retain_deep<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[PathSegment], &Value) -> bool,
+    {
+        walk::retain_children(self, &mut Vec::new(), &mut f);
+        if let Some(children) = ChildrenMut::of_value(self) {
+            walk::retain(children, f);
+        }
+    }
+
+    /// 重命名所有对象的成员, `f`的参数为成员的路径和名称, 返回None时保持不变.
+    /// 子节点的路径使用新的名称. 新名称与同一对象中的其他成员冲突时返回错误,
+    /// 此时之前的修改不会撤销.
+    pub fn rename_keys<F>(&mut self, mut f: F) -> Result<(), String>
+    where
+        F: FnMut(&[PathSegment], &str) -> Option<String>,
+    {
+        if let Value::Object(ref mut obj) = *self {
+            walk::rename_members(obj, &mut Vec::new(), &mut f)?;
+        }
+        match ChildrenMut::of_value(self) {
+            Some(children) => walk::rename_keys(children, f),
+            None => Ok(()),
+        }
+    }
+
+    // 按照JSON Pointer已经拆分的各段查找值, 供`Map::pointer`使用.
+    pub(crate) fn pointer_tokens<'a>(
+        &self,
+        tokens: impl Iterator<Item = Cow<'a, str>>,
+    ) -> Option<&Value> {
+        let mut target = self;
+        for token in tokens {
+            target = match *target {
+                Value::Object(ref obj) => obj.get(token.as_ref())?,
+                Value::Vec(ref vec) => vec.get(path::parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(target)
+    }
+
+    /// 将值转换为Json, 带有默认设置. 与`Map::to_json`不同, 最外层可以是任意值.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let value = Value::Vec(vec![Value::I64(1), Value::String("a".to_string())]);
+    /// assert_eq!(value.to_json(), r#"[1,"a"]"#);
+    /// ```
+    pub fn to_json(&self) -> String {
+        self.to_json_with_settings(JsonWriterSettings::default())
+    }
+
+    /// 将值转换为Json, 自定义格式化设置.
+    pub fn to_json_with_settings(&self, settings: JsonWriterSettings) -> String {
+        JsonWriter::new(&settings).format_value(self)
+    }
+
+    /// 解析Json, 带有默认设置. 与`Map::merge`使用相同的读取器和限制, 但最外层可以是任意值.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let value = Value::from_json("[1, \"a\"]").unwrap();
+    /// assert_eq!(value.to_json(), r#"[1,"a"]"#);
+    /// assert!(Value::from_json("[1,]").is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Value, String> {
+        Value::from_json_with_settings(json, JsonReaderSettings::default())
+    }
+
+    /// 解析Json, 自定义设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{JsonReaderSettings, Value};
+    ///
+    /// let settings = JsonReaderSettings {
+    ///     max_array_length: 2,
+    ///     ..Default::default()
+    /// };
+    /// assert!(Value::from_json_with_settings("[1, 2, 3]", settings).is_err());
+    /// ```
+    pub fn from_json_with_settings(
+        json: &str,
+        settings: JsonReaderSettings,
+    ) -> Result<Value, String> {
+        JsonReader::new(settings).parse_value(json)
+    }
+
+    /// 将值编码为CBOR (RFC 8949), 带有默认设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let value = Value::Vec(vec![Value::I64(1), Value::String("a".to_string())]);
+    /// let bytes = value.to_cbor();
+    /// assert_eq!(bytes, [0x82, 0x01, 0x61, b'a']);
+    /// assert!(Value::from_cbor(&bytes).unwrap() == value);
+    /// ```
+    pub fn to_cbor(&self) -> Vec<u8> {
+        self.to_cbor_with_settings(CborWriterSettings::default())
+    }
+
+    /// 将值编码为CBOR, 自定义编码设置.
+    pub fn to_cbor_with_settings(&self, settings: CborWriterSettings) -> Vec<u8> {
+        CborWriter::new(settings).format(self)
+    }
+
+    /// 解码CBOR, 带有默认设置. 标签, 字节串和不是文本串的成员名称在`Value`中没有对应的类型, 默认返回错误.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Value, String> {
+        Value::from_cbor_with_settings(bytes, CborReaderSettings::default())
+    }
+
+    /// 解码CBOR, 自定义解码设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{ByteStringPolicy, CborReaderSettings, Value};
+    ///
+    /// let bytes = [0x42, 0x01, 0x02];
+    /// assert!(Value::from_cbor(&bytes).is_err());
+    ///
+    /// let settings = CborReaderSettings {
+    ///     byte_strings: ByteStringPolicy::Base64,
+    ///     ..Default::default()
+    /// };
+    /// let value = Value::from_cbor_with_settings(&bytes, settings).unwrap();
+    /// assert_eq!(value.as_string(), Some("AQI="));
+    /// ```
+    pub fn from_cbor_with_settings(
+        bytes: &[u8],
+        settings: CborReaderSettings,
+    ) -> Result<Value, String> {
+        CborReader::new(settings).parse(bytes)
+    }
+
+    /// 将值编码为MessagePack, 整数, 浮点数和长度总是使用最短的编码. 单精度能精确表示的浮点数编码为float32.
+    ///
+    /// MessagePack的长度最多为32位, 字符串, 数组或映射超过这个长度时返回错误.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::Value;
+    ///
+    /// let value = Value::Vec(vec![Value::I64(300), Value::F64(0.5)]);
+    /// let bytes = value.to_msgpack().unwrap();
+    /// assert_eq!(bytes, [0x92, 0xcd, 0x01, 0x2c, 0xca, 0x3f, 0x00, 0x00, 0x00]);
+    /// assert!(Value::from_msgpack(&bytes).unwrap() == value);
+    /// ```
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, String> {
+        MsgpackWriter.format(self)
+    }
+
+    /// 解码MessagePack, 带有默认设置. bin, ext和不是str的成员名称在`Value`中没有对应的类型, 默认返回错误.
+    /// Json不能表示的无穷大和NaN默认也返回错误.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Value, String> {
+        Value::from_msgpack_with_settings(bytes, MsgpackReaderSettings::default())
+    }
+
+    /// 解码MessagePack, 自定义解码设置.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{ExtensionPolicy, MsgpackReaderSettings, Value};
+    ///
+    /// // fixext1, 类型编号为1, 数据为0x2a
+    /// let bytes = [0xd4, 0x01, 0x2a];
+    /// assert!(Value::from_msgpack(&bytes).is_err());
+    ///
+    /// let settings = MsgpackReaderSettings {
+    ///     extensions: ExtensionPolicy::Object,
+    ///     ..Default::default()
+    /// };
+    /// let value = Value::from_msgpack_with_settings(&bytes, settings).unwrap();
+    /// let object = value.as_object().unwrap();
+    /// assert_eq!(object["type"].as_i64(), Some(1));
+    /// assert_eq!(object["data"].as_string(), Some("Kg=="));
+    /// ```
+    pub fn from_msgpack_with_settings(
+        bytes: &[u8],
+        settings: MsgpackReaderSettings,
+    ) -> Result<Value, String> {
+        MsgpackReader::new(settings).parse(bytes)
+    }
+
+    /// 将对象的数组转换为CSV (RFC 4180), 带有默认设置.
+    ///
+    /// 列名为所有对象中成员名称的并集, 按字典序排列. 嵌套的对象展开为以`.`连接的列名,
+    /// 数组以Json写入一个单元格. 自身不是数组或者其中有不是对象的元素时返回错误.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::{Map, Value};
+    ///
+    /// let mut map = Map::new();
+    /// map.merge(r#"{"rows":[{"id":1,"user":{"name":"Tom"}},{"id":2,"tags":["a"]}]}"#).unwrap();
+    /// assert_eq!(
+    ///     map["rows"].to_csv().unwrap(),
+    ///     "id,tags,user.name\r\n1,,Tom\r\n2,\"[\"\"a\"\"]\",\r\n"
+    /// );
+    /// ```
+    pub fn to_csv(&self) -> Result<String, String> {
+        let mut output = Vec::new();
+        csv::write_csv(self, &mut output, &CsvWriterSettings::default())?;
+        Ok(String::from_utf8(output).expect("CSV output is valid UTF-8"))
+    }
+
+    /// 将对象的数组以CSV写入`output`, 自定义设置.
+    pub fn write_csv<W: Write>(
+        &self,
+        output: W,
+        settings: CsvWriterSettings,
+    ) -> Result<(), String> {
+        csv::write_csv(self, output, &settings)
+    }
+}
+
+impl Value {
+    pub(crate) fn is_container(&self) -> bool {
+        matches!(self, Value::Vec(_) | Value::Object(_))
+    }
+
+    // 将嵌套的对象和数组从`self`中取出并压入栈中, 使`self`被释放时不会再递归.
+    pub(crate) fn take_nested(&mut self, stack: &mut Vec<Value>) {
+        match self {
+            Value::Vec(vec) => stack.extend(vec.drain(..).filter(Value::is_container)),
+            Value::Object(obj) => {
+                stack.extend(obj.drain().map(|(_, v)| v).filter(Value::is_container))
+            }
+            _ => {}
+        }
+    }
+}
+
+impl From<Map> for Value {
+    fn from(map: Map) -> Value {
+        Value::Object(map)
+    }
+}
+
+// 默认的释放过程会对每一层嵌套递归一次, 对于很深的文档会导致栈溢出, 所以这里改为使用显式的栈逐层释放.
+impl Drop for Value {
+    fn drop(&mut self) {
+        let has_nested = match self {
+            Value::Vec(vec) => vec.iter().any(Value::is_container),
+            Value::Object(obj) => obj.values().any(Value::is_container),
+            _ => false,
+        };
+        if !has_nested {
+            return;
+        }
+
+        let mut stack = Vec::new();
+        self.take_nested(&mut stack);
+        while let Some(mut value) = stack.pop() {
+            value.take_nested(&mut stack);
+        }
+    }
 }
@@ -0,0 +1,203 @@
+use crate::{Map, MapRef, Number, Value};
+use std::borrow::Cow;
+use std::collections::hash_map;
+use std::mem;
+use std::vec;
+
+/// `MapRef`的指定值类型, 与`Value`一一对应, 但字符串借用自被解析的Json文本.
+///
+/// 只有包含转义字符的字符串才会分配新的内存(`Cow::Owned`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    Null,
+    Bool(bool),
+    F64(f64),
+    I64(i64),
+    String(Cow<'a, str>),
+    Vec(Vec<ValueRef<'a>>),
+    Object(MapRef<'a>),
+}
+
+impl<'a> ValueRef<'a> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, ValueRef::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            ValueRef::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, ValueRef::F64(_) | ValueRef::I64(_))
+    }
+
+    /// 返回数字的只读视图, 不是数字时返回None.
+    pub fn as_number(&self) -> Option<Number> {
+        match *self {
+            ValueRef::F64(n) => Some(Number::F64(n)),
+            ValueRef::I64(n) => Some(Number::I64(n)),
+            _ => None,
+        }
+    }
+
+    /// 与`Value::as_f64`相同, `I64`能够精确表示为`f64`时也会返回.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number()?.to_f64().ok()
+    }
+
+    /// 与`Value::as_i64`相同, 值为整数且在范围内的`F64`也会返回.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.to_i64().ok()
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match *self {
+            ValueRef::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_vec(&self) -> Option<&[ValueRef<'a>]> {
+        match *self {
+            ValueRef::Vec(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&MapRef<'a>> {
+        match *self {
+            ValueRef::Object(ref o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// 转换为拥有所有权的`Value`, 复制所有借用的字符串.
+    ///
+    /// # 例子
+    ///
+    /// ```
+    /// use mapjson::MapRef;
+    ///
+    /// let json = r#"{"a":["hi","china"]}"#;
+    /// let map = MapRef::parse(json).unwrap();
+    ///
+    /// let value = map["a"].clone().into_owned();
+    /// assert_eq!(value.as_vec().unwrap()[1].as_string().unwrap(), "china");
+    /// ```
+    pub fn into_owned(mut self) -> Value {
+        // 与`Drop`相同, 使用显式的栈转换嵌套的对象和数组, 避免递归.
+        let mut stack: Vec<Frame<'a>> = Vec::new();
+        let mut value = self.take_scalar(&mut stack);
+
+        loop {
+            let Some(frame) = stack.last_mut() else {
+                return value.unwrap_or(Value::Null);
+            };
+
+            let next = match frame {
+                Frame::Vec { iter, vec } => {
+                    if let Some(val) = value.take() {
+                        vec.push(val);
+                    }
+                    iter.next()
+                }
+                Frame::Object { iter, map, name } => {
+                    if let Some(val) = value.take() {
+                        map.insert(mem::take(name), val);
+                    }
+                    iter.next().map(|(key, val)| {
+                        *name = key.into_owned();
+                        val
+                    })
+                }
+            };
+
+            value = match next {
+                Some(mut val) => val.take_scalar(&mut stack),
+                None => match stack.pop() {
+                    Some(Frame::Vec { vec, .. }) => Some(Value::Vec(vec)),
+                    Some(Frame::Object { map, .. }) => Some(Value::Object(map)),
+                    None => None,
+                },
+            };
+        }
+    }
+
+    // 将标量转换为`Value`; 对于对象和数组, 取出其成员压入栈中, 并返回None.
+    fn take_scalar(&mut self, stack: &mut Vec<Frame<'a>>) -> Option<Value> {
+        match self {
+            ValueRef::Null => Some(Value::Null),
+            ValueRef::Bool(b) => Some(Value::Bool(*b)),
+            ValueRef::F64(n) => Some(Value::F64(*n)),
+            ValueRef::I64(n) => Some(Value::I64(*n)),
+            ValueRef::String(s) => Some(Value::String(mem::take(s).into_owned())),
+            ValueRef::Vec(v) => {
+                let v = mem::take(v);
+                stack.push(Frame::Vec {
+                    vec: Vec::with_capacity(v.len()),
+                    iter: v.into_iter(),
+                });
+                None
+            }
+            ValueRef::Object(o) => {
+                stack.push(Frame::Object {
+                    iter: mem::take(&mut **o).into_iter(),
+                    map: Map::new(),
+                    name: String::new(),
+                });
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_container(&self) -> bool {
+        matches!(self, ValueRef::Vec(_) | ValueRef::Object(_))
+    }
+
+    // 将嵌套的对象和数组从`self`中取出并压入栈中, 使`self`被释放时不会再递归.
+    pub(crate) fn take_nested(&mut self, stack: &mut Vec<ValueRef<'a>>) {
+        match self {
+            ValueRef::Vec(vec) => stack.extend(vec.drain(..).filter(ValueRef::is_container)),
+            ValueRef::Object(obj) => {
+                stack.extend(obj.drain().map(|(_, v)| v).filter(ValueRef::is_container))
+            }
+            _ => {}
+        }
+    }
+}
+
+// `into_owned`转换栈中尚未完成的对象或数组.
+enum Frame<'a> {
+    Vec {
+        iter: vec::IntoIter<ValueRef<'a>>,
+        vec: Vec<Value>,
+    },
+    Object {
+        iter: hash_map::IntoIter<Cow<'a, str>, ValueRef<'a>>,
+        map: Map,
+        name: String,
+    },
+}
+
+// 与`Value`相同, 使用显式的栈逐层释放, 避免很深的文档导致栈溢出.
+impl Drop for ValueRef<'_> {
+    fn drop(&mut self) {
+        let has_nested = match self {
+            ValueRef::Vec(vec) => vec.iter().any(ValueRef::is_container),
+            ValueRef::Object(obj) => obj.values().any(ValueRef::is_container),
+            _ => false,
+        };
+        if !has_nested {
+            return;
+        }
+
+        let mut stack = Vec::new();
+        self.take_nested(&mut stack);
+        while let Some(mut value) = stack.pop() {
+            value.take_nested(&mut stack);
+        }
+    }
+}
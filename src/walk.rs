@@ -0,0 +1,457 @@
+use crate::{Map, PathSegment, Value, to_json_pointer};
+use std::borrow::Cow;
+use std::collections::hash_map;
+use std::{iter, slice};
+
+/// `Visitor`的返回值, 决定遍历如何继续.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Walk {
+    Continue,     // 继续遍历, 包括当前节点的子节点.
+    SkipChildren, // 跳过当前节点的子节点, 继续遍历其后的节点.
+    Stop,         // 立即结束遍历.
+}
+
+/// 深度优先遍历`Map`或`Value`时对每个节点调用的访问者.
+///
+/// 闭包`FnMut(&[PathSegment], &Value) -> Walk`也实现了`Visitor`.
+pub trait Visitor {
+    /// 参数为节点的路径(最后一段为成员的名称或下标)和节点的值. 父节点总是先于子节点被访问,
+    /// 同一对象中成员的顺序不确定.
+    fn visit(&mut self, path: &[PathSegment], value: &Value) -> Walk;
+}
+
+impl<F> Visitor for F
+where
+    F: FnMut(&[PathSegment], &Value) -> Walk,
+{
+    fn visit(&mut self, path: &[PathSegment], value: &Value) -> Walk {
+        self(path, value)
+    }
+}
+
+// 对象成员或数组元素的迭代器, 附带每个子节点的路径段.
+pub(crate) enum Children<'a> {
+    Object(hash_map::Iter<'a, String, Value>),
+    Vec(iter::Enumerate<slice::Iter<'a, Value>>),
+}
+
+impl<'a> Children<'a> {
+    pub(crate) fn of_map(map: &'a Map) -> Self {
+        Children::Object(map.iter())
+    }
+
+    pub(crate) fn of_value(value: &'a Value) -> Option<Self> {
+        match value {
+            Value::Object(obj) => Some(Children::of_map(obj)),
+            Value::Vec(vec) => Some(Children::Vec(vec.iter().enumerate())),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = (PathSegment<'a>, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Children::Object(iter) => iter
+                .next()
+                .map(|(k, v)| (PathSegment::Key(Cow::Borrowed(k)), v)),
+            Children::Vec(iter) => iter.next().map(|(i, v)| (PathSegment::Index(i), v)),
+        }
+    }
+}
+
+// `Children`的可变版本.
+pub(crate) enum ChildrenMut<'a> {
+    Object(hash_map::IterMut<'a, String, Value>),
+    Vec(iter::Enumerate<slice::IterMut<'a, Value>>),
+}
+
+impl<'a> ChildrenMut<'a> {
+    pub(crate) fn of_map(map: &'a mut Map) -> Self {
+        ChildrenMut::Object(map.iter_mut())
+    }
+
+    pub(crate) fn of_value(value: &'a mut Value) -> Option<Self> {
+        match value {
+            Value::Object(obj) => Some(ChildrenMut::of_map(obj)),
+            Value::Vec(vec) => Some(ChildrenMut::Vec(vec.iter_mut().enumerate())),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for ChildrenMut<'a> {
+    type Item = (PathSegment<'a>, &'a mut Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChildrenMut::Object(iter) => iter
+                .next()
+                .map(|(k, v)| (PathSegment::Key(Cow::Borrowed(k)), v)),
+            ChildrenMut::Vec(iter) => iter.next().map(|(i, v)| (PathSegment::Index(i), v)),
+        }
+    }
+}
+
+// 以显式的栈先序遍历`root`下的所有节点, 返回是否被`Walk::Stop`提前结束.
+pub(crate) fn walk<V: Visitor + ?Sized>(root: Children, visitor: &mut V) -> bool {
+    let mut path = Vec::new();
+    let mut stack = vec![root];
+    while let Some(top) = stack.last_mut() {
+        let Some((segment, value)) = top.next() else {
+            stack.pop();
+            path.pop();
+            continue;
+        };
+
+        path.push(segment);
+        match visitor.visit(&path, value) {
+            Walk::Stop => return true,
+            Walk::Continue => {
+                if let Some(children) = Children::of_value(value) {
+                    stack.push(children);
+                    continue;
+                }
+            }
+            Walk::SkipChildren => {}
+        }
+        path.pop();
+    }
+
+    false
+}
+
+// 以显式的栈先序遍历`root`下的所有节点, 先对节点调用`f`, 再进入它(可能已被修改)的子节点.
+fn for_each_mut<'a, F>(root: ChildrenMut<'a>, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&mut Vec<PathSegment<'a>>, &mut Value) -> Result<(), String>,
+{
+    let mut path = Vec::new();
+    let mut stack = vec![root];
+    while let Some(top) = stack.last_mut() {
+        let Some((segment, value)) = top.next() else {
+            stack.pop();
+            path.pop();
+            continue;
+        };
+
+        path.push(segment);
+        f(&mut path, value)?;
+        match ChildrenMut::of_value(value) {
+            Some(children) => stack.push(children),
+            None => {
+                path.pop();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 对`root`下每个不是数组或对象的值调用`f`.
+pub(crate) fn map_values<F>(root: ChildrenMut, mut f: F)
+where
+    F: FnMut(&[PathSegment], &mut Value),
+{
+    let _ = for_each_mut(root, |path, value| {
+        if !value.is_container() {
+            f(path, value);
+        }
+        Ok(())
+    });
+}
+
+// 删除`root`下`f`返回false的成员和元素, 被删除的节点的子节点不会被访问.
+pub(crate) fn retain<F>(root: ChildrenMut, mut f: F)
+where
+    F: FnMut(&[PathSegment], &Value) -> bool,
+{
+    let _ = for_each_mut(root, |path, value| {
+        retain_children(value, path, &mut f);
+        Ok(())
+    });
+}
+
+// 只处理`map`的直接成员, 更深的节点由`retain`处理.
+pub(crate) fn retain_members<F>(map: &mut Map, path: &mut Vec<PathSegment>, f: &mut F)
+where
+    F: FnMut(&[PathSegment], &Value) -> bool,
+{
+    map.retain(|key, value| {
+        path.push(PathSegment::Key(Cow::Owned(key.clone())));
+        let keep = f(path, value);
+        path.pop();
+        keep
+    });
+}
+
+pub(crate) fn retain_children<F>(value: &mut Value, path: &mut Vec<PathSegment>, f: &mut F)
+where
+    F: FnMut(&[PathSegment], &Value) -> bool,
+{
+    match value {
+        Value::Object(obj) => retain_members(obj, path, f),
+        Value::Vec(vec) => {
+            // 下标为元素在过滤后的数组中的位置, 与随后访问子节点时的路径一致.
+            let mut index = 0;
+            vec.retain(|value| {
+                path.push(PathSegment::Index(index));
+                let keep = f(path, value);
+                path.pop();
+                if keep {
+                    index += 1;
+                }
+                keep
+            });
+        }
+        _ => {}
+    }
+}
+
+// 重命名`root`下所有对象的成员, 子节点的路径使用新的名称.
+pub(crate) fn rename_keys<F>(root: ChildrenMut, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&[PathSegment], &str) -> Option<String>,
+{
+    for_each_mut(root, |path, value| match value {
+        Value::Object(obj) => rename_members(obj, path, &mut f),
+        _ => Ok(()),
+    })
+}
+
+// 只处理`map`的直接成员. 新名称与同一对象中的其他成员冲突时返回错误.
+pub(crate) fn rename_members<F>(
+    map: &mut Map,
+    path: &mut Vec<PathSegment>,
+    f: &mut F,
+) -> Result<(), String>
+where
+    F: FnMut(&[PathSegment], &str) -> Option<String>,
+{
+    let mut renamed = Vec::new();
+    for key in map.keys() {
+        path.push(PathSegment::Key(Cow::Owned(key.clone())));
+        let name = f(path, key);
+        path.pop();
+        match name {
+            Some(name) if name != *key => renamed.push((key.clone(), name)),
+            _ => {}
+        }
+    }
+
+    let moved: Vec<(String, Value)> = renamed
+        .into_iter()
+        .map(|(key, name)| (name, map.remove(&key).unwrap()))
+        .collect();
+    for (name, value) in moved {
+        if map.contains_key(&name) {
+            path.push(PathSegment::Key(Cow::Owned(name)));
+            let pointer = to_json_pointer(path);
+            path.pop();
+            return Err(format!("Duplicate key after renaming: {}", pointer));
+        }
+        map.insert(name, value);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Map, PathSegment, Value, Walk, to_json_pointer};
+
+    fn parse(json: &str) -> Map {
+        let mut map = Map::new();
+        map.merge(json).unwrap();
+        map
+    }
+
+    #[test]
+    fn walk_paths() {
+        let map = parse(r#"{"a":{"b":[1,{"c":"x"}]},"d":null}"#);
+        let mut paths = Vec::new();
+        map.walk(&mut |path: &[PathSegment], _: &Value| {
+            paths.push(to_json_pointer(path));
+            Walk::Continue
+        });
+        paths.sort();
+        assert_eq!(paths, ["/a", "/a/b", "/a/b/0", "/a/b/1", "/a/b/1/c", "/d"]);
+
+        // 父节点先于子节点.
+        let mut order = Vec::new();
+        Value::Object(map).walk(&mut |path: &[PathSegment], _: &Value| {
+            order.push(to_json_pointer(path));
+            Walk::Continue
+        });
+        assert_eq!(order[0], "");
+        let pos = |p: &str| order.iter().position(|o| o == p).unwrap();
+        assert!(pos("/a") < pos("/a/b") && pos("/a/b") < pos("/a/b/1/c"));
+    }
+
+    #[test]
+    fn walk_control() {
+        let map = parse(r#"{"a":{"b":{"c":1}},"d":[1,2,3]}"#);
+        let mut paths = Vec::new();
+        map.walk(&mut |path: &[PathSegment], _: &Value| {
+            paths.push(to_json_pointer(path));
+            if path.len() == 1 {
+                Walk::SkipChildren
+            } else {
+                Walk::Continue
+            }
+        });
+        paths.sort();
+        assert_eq!(paths, ["/a", "/d"]);
+
+        let mut found = None;
+        let mut visited = 0;
+        let stopped = map.walk(&mut |path: &[PathSegment], value: &Value| {
+            visited += 1;
+            if value.as_i64() == Some(2) {
+                found = Some(to_json_pointer(path));
+                return Walk::Stop;
+            }
+            Walk::Continue
+        });
+        assert_eq!(found.as_deref(), Some("/d/1"));
+        assert!(stopped);
+        // 共7个节点, `/d/2`在`/d/1`之后, 不会被访问.
+        assert!(visited < 7);
+
+        let mut count = 0;
+        Value::I64(1).walk(&mut |path: &[PathSegment], _: &Value| {
+            assert!(path.is_empty());
+            count += 1;
+            Walk::Continue
+        });
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn walk_deep() {
+        let depth = 100_000;
+        let mut value = Value::Vec(Vec::new());
+        for _ in 1..depth {
+            value = Value::Vec(vec![value]);
+        }
+        let mut map = Map::new();
+        map.insert("a".to_string(), value);
+        let mut max = 0;
+        map.walk(&mut |path: &[PathSegment], _: &Value| {
+            max = max.max(path.len());
+            Walk::Continue
+        });
+        assert_eq!(max, depth);
+        map.map_values(|_, _| {});
+    }
+
+    #[test]
+    fn map_values() {
+        let mut map = parse(r#"{"a":[1.25,{"b":2.5}],"s":"x","n":3}"#);
+        map.map_values(|_, value| {
+            if let Value::F64(f) = *value {
+                *value = Value::F64(f.round());
+            }
+        });
+        assert_eq!(map.pointer("/a/0").unwrap().as_f64(), Some(1.0));
+        assert_eq!(map.pointer("/a/1/b").unwrap().as_f64(), Some(3.0));
+
+        let mut value = Value::Object(map);
+        value.map_values(|path, value| {
+            if path.last().and_then(PathSegment::as_key) == Some("s") {
+                value.as_string_mut().unwrap().push('!');
+            }
+        });
+        assert_eq!(value.as_object().unwrap()["s"].as_string(), Some("x!"));
+
+        let mut scalar = Value::I64(1);
+        scalar.map_values(|path, value| {
+            assert!(path.is_empty());
+            *value = Value::Null;
+        });
+        assert!(scalar.is_null());
+    }
+
+    #[test]
+    fn retain_deep() {
+        let mut map = parse(r#"{"a":[null,1,null,{"b":null,"c":2}],"d":null,"e":{"f":[3]}}"#);
+        map.retain_deep(|_, value| !value.is_null());
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.pointer("/a").unwrap().to_json(), r#"[1,{"c":2}]"#);
+
+        let mut paths = Vec::new();
+        map.retain_deep(|path, _| {
+            paths.push(to_json_pointer(path));
+            path.first().and_then(PathSegment::as_key) != Some("e")
+        });
+        paths.sort();
+        assert_eq!(paths, ["/a", "/a/0", "/a/1", "/a/1/c", "/e"]);
+        assert!(map.get("e").is_none());
+
+        // 下标为过滤后的位置.
+        let mut value = Value::Vec(vec![Value::I64(1), Value::I64(2), Value::I64(3)]);
+        let mut indexes = Vec::new();
+        value.retain_deep(|path, value| {
+            indexes.push(path[0].as_index().unwrap());
+            value.as_i64() != Some(2)
+        });
+        assert_eq!(indexes, [0, 1, 1]);
+        assert_eq!(value.to_json(), "[1,3]");
+
+        // `HashMap::retain`仍然只过滤直接成员.
+        let mut map = parse(r#"{"a":[null],"b":null}"#);
+        map.retain(|_, value| !value.is_null());
+        assert_eq!(map.to_json(), r#"{"a":[null]}"#);
+    }
+
+    #[test]
+    fn rename_keys() {
+        let mut map =
+            parse(r#"{"user_name":"a","user_info":{"home_city":"b","tags":[{"tag_id":1}]}}"#);
+        map.rename_keys(|_, key| {
+            key.contains('_').then(|| {
+                let mut parts = key.split('_');
+                let mut name = parts.next().unwrap().to_string();
+                for part in parts {
+                    name.push_str(&part[..1].to_uppercase());
+                    name.push_str(&part[1..]);
+                }
+                name
+            })
+        })
+        .unwrap();
+        assert_eq!(map.pointer("/userName").unwrap().as_string(), Some("a"));
+        assert_eq!(
+            map.pointer("/userInfo/homeCity").unwrap().as_string(),
+            Some("b")
+        );
+        assert_eq!(
+            map.pointer("/userInfo/tags/0/tagId").unwrap().as_i64(),
+            Some(1)
+        );
+
+        // 子节点的路径使用新的名称.
+        let mut paths = Vec::new();
+        map.rename_keys(|path, _| {
+            paths.push(to_json_pointer(path));
+            None
+        })
+        .unwrap();
+        assert!(paths.contains(&"/userInfo/tags/0/tagId".to_string()));
+
+        // 交换名称不算冲突.
+        let mut value = Value::Object(parse(r#"{"a":1,"b":2}"#));
+        value
+            .rename_keys(|_, key| Some(if key == "a" { "b" } else { "a" }.to_string()))
+            .unwrap();
+        assert_eq!(value.as_object().unwrap()["a"].as_i64(), Some(2));
+
+        let mut map = parse(r#"{"x":{"a/b":1,"c":2}}"#);
+        assert_eq!(
+            map.rename_keys(|_, key| (key == "c").then(|| "a/b".to_string())),
+            Err("Duplicate key after renaming: /x/a~1b".to_string())
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn mapjson(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mapjson"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn fmt_and_minify() {
+    let json = r#"{"b":1.50,"a":[true]}"#;
+    let output = mapjson(&["fmt", "--indent", "4"], json);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "{\n    \"b\": 1.50,\n    \"a\": [\n        true\n    ]\n}\n"
+    );
+
+    let output = mapjson(&["fmt", "--sort-keys", "--indent", "0", "-"], json);
+    assert_eq!(stdout(&output), "{\"a\":[true],\"b\":1.5}\n");
+
+    let output = mapjson(&["minify"], "{ \"a\" : [ 1 ,\n 2 ] }");
+    assert_eq!(stdout(&output), "{\"a\":[1,2]}\n");
+}
+
+#[test]
+fn validate() {
+    let output = mapjson(&["validate"], "{\"a\": [1, 2]}");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let output = mapjson(&["validate"], "{\n  \"a\": [1, 2,]\n}");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "<stdin>: Invalid state to read a close square bracket: \"ARRAY_AFTER_COMMA\" at 2:14\n"
+    );
+
+    // 与`Map::merge`接受的输入相同: 最外层必须是对象, 并且有嵌套深度等限制.
+    let deep = format!("{{\"a\":{}{}}}", "[".repeat(200), "]".repeat(200));
+    for json in ["[1,2]", "\"x\"", deep.as_str()] {
+        let output = mapjson(&["validate"], json);
+        assert_eq!(output.status.code(), Some(1), "{}", json);
+        let output = mapjson(&["minify"], json);
+        assert_eq!(output.status.code(), Some(1), "{}", json);
+    }
+    let output = mapjson(&["validate"], &deep);
+    assert!(stderr(&output).contains("The set depth limit is exceeded: 128"));
+
+    let output = mapjson(&["validate", "does-not-exist.json"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("does-not-exist.json: "));
+    assert!(!stderr(&output).contains("does-not-exist.json: does-not-exist.json"));
+}
+
+#[test]
+fn get() {
+    let json = r#"{"a":{"b":[10,{"c/d":"x"}]}}"#;
+    let output = mapjson(&["get", "/a/b/1/c~1d"], json);
+    assert_eq!(stdout(&output), "\"x\"\n");
+
+    let output = mapjson(&["get", "/a/b"], json);
+    assert_eq!(
+        stdout(&output),
+        "[\n  10,\n  {\n    \"c/d\": \"x\"\n  }\n]\n"
+    );
+
+    let output = mapjson(&["get", "/a/x"], json);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "No value at /a/x\n");
+}
+
+#[test]
+fn ndjson() {
+    let output = mapjson(&["ndjson"], "{\"a\": 1}\n\n[ 1, 2 ]\n");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\"a\":1}\n[1,2]\n");
+
+    let output = mapjson(&["ndjson"], "{\"a\": 1}\n{\"a\": }\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("<stdin>: line 2: "));
+
+    // 与`validate`相同的嵌套深度限制
+    let deep = format!("1\n{}{}\n", "[".repeat(200), "]".repeat(200));
+    let output = mapjson(&["ndjson"], &deep);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("<stdin>: line 2: "));
+}
+
+#[test]
+fn schema() {
+    let output = mapjson(&["schema"], "{\"id\": 1, \"tag\": null}\n{\"id\": 2}\n");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            "{\n",
+            "  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",\n",
+            "  \"properties\": {\n",
+            "    \"id\": {\n",
+            "      \"maximum\": 2,\n",
+            "      \"minimum\": 1,\n",
+            "      \"type\": \"integer\"\n",
+            "    },\n",
+            "    \"tag\": {\n",
+            "      \"type\": \"null\"\n",
+            "    }\n",
+            "  },\n",
+            "  \"required\": [\n",
+            "    \"id\"\n",
+            "  ],\n",
+            "  \"type\": \"object\"\n",
+            "}\n",
+        )
+    );
+
+    let output = mapjson(&["schema"], "1\n{\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("<stdin>: line 2: "));
+}
+
+#[test]
+fn codegen() {
+    let output = mapjson(&["codegen", "--name", "Event"], "{\"id\": 1}\n");
+    assert!(output.status.success());
+    let code = stdout(&output);
+    assert!(code.starts_with("// Generated by mapjson codegen.\n"));
+    assert!(code.contains("pub struct Event {\n    pub id: i64,\n}\n"));
+
+    let output = mapjson(&["codegen"], "{\"id\": 1}\n[1]\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "<stdin>: Expected only objects, found Vec|Object\n"
+    );
+    assert_eq!(mapjson(&["codegen", "--name"], "").status.code(), Some(2));
+}
+
+#[test]
+fn usage_errors() {
+    assert_eq!(mapjson(&[], "").status.code(), Some(2));
+    assert_eq!(mapjson(&["frobnicate"], "").status.code(), Some(2));
+    assert_eq!(
+        mapjson(&["minify", "--indent", "2"], "").status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        mapjson(&["fmt", "--indent", "x"], "").status.code(),
+        Some(2)
+    );
+    assert!(mapjson(&["--help"], "").status.success());
+}
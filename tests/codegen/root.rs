@@ -0,0 +1,164 @@
+// Generated by mapjson codegen.
+
+use mapjson::{FromValue, Map, ToValue, Value};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Root {
+    pub extra: Option<Value>,
+    pub id: i64,
+    pub name: String,
+    pub nickname: Option<String>,
+    pub owner: Owner,
+    pub score: f64,
+    pub tags: Vec<String>,
+    pub r#type: String,
+    pub values: Vec<ValuesItem>,
+}
+
+impl Root {
+    pub fn from_map(map: &Map) -> Result<Self, String> {
+        Ok(Root {
+            extra: FromValue::from_field(map, "extra")?,
+            id: FromValue::from_field(map, "id")?,
+            name: FromValue::from_field(map, "name")?,
+            nickname: FromValue::from_field(map, "nickname")?,
+            owner: FromValue::from_field(map, "owner")?,
+            score: FromValue::from_field(map, "score")?,
+            tags: FromValue::from_field(map, "tags")?,
+            r#type: FromValue::from_field(map, "type")?,
+            values: FromValue::from_field(map, "values")?,
+        })
+    }
+
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        if let Some(value) = &self.extra {
+            map.insert("extra".to_string(), value.to_value());
+        }
+        map.insert("id".to_string(), self.id.to_value());
+        map.insert("name".to_string(), self.name.to_value());
+        if let Some(value) = &self.nickname {
+            map.insert("nickname".to_string(), value.to_value());
+        }
+        map.insert("owner".to_string(), self.owner.to_value());
+        map.insert("score".to_string(), self.score.to_value());
+        map.insert("tags".to_string(), self.tags.to_value());
+        map.insert("type".to_string(), self.r#type.to_value());
+        map.insert("values".to_string(), self.values.to_value());
+        map
+    }
+}
+
+impl ToValue for Root {
+    fn to_value(&self) -> Value {
+        Value::Object(self.to_map())
+    }
+}
+
+impl FromValue for Root {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_object() {
+            Some(map) => Self::from_map(map),
+            None => Err(mapjson::type_error("Object", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Owner {
+    pub id: i64,
+    pub login: String,
+}
+
+impl Owner {
+    pub fn from_map(map: &Map) -> Result<Self, String> {
+        Ok(Owner {
+            id: FromValue::from_field(map, "id")?,
+            login: FromValue::from_field(map, "login")?,
+        })
+    }
+
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("id".to_string(), self.id.to_value());
+        map.insert("login".to_string(), self.login.to_value());
+        map
+    }
+}
+
+impl ToValue for Owner {
+    fn to_value(&self) -> Value {
+        Value::Object(self.to_map())
+    }
+}
+
+impl FromValue for Owner {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_object() {
+            Some(map) => Self::from_map(map),
+            None => Err(mapjson::type_error("Object", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValuesItem {
+    I64(i64),
+    String(String),
+    Object(ValuesItemObject),
+}
+
+impl ToValue for ValuesItem {
+    fn to_value(&self) -> Value {
+        match self {
+            ValuesItem::I64(v) => v.to_value(),
+            ValuesItem::String(v) => v.to_value(),
+            ValuesItem::Object(v) => v.to_value(),
+        }
+    }
+}
+
+impl FromValue for ValuesItem {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::I64(_) => Ok(ValuesItem::I64(FromValue::from_value(value)?)),
+            Value::String(_) => Ok(ValuesItem::String(FromValue::from_value(value)?)),
+            Value::Object(_) => Ok(ValuesItem::Object(FromValue::from_value(value)?)),
+            _ => Err(mapjson::type_error("I64|String|Object", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValuesItemObject {
+    pub k: bool,
+}
+
+impl ValuesItemObject {
+    pub fn from_map(map: &Map) -> Result<Self, String> {
+        Ok(ValuesItemObject {
+            k: FromValue::from_field(map, "k")?,
+        })
+    }
+
+    pub fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("k".to_string(), self.k.to_value());
+        map
+    }
+}
+
+impl ToValue for ValuesItemObject {
+    fn to_value(&self) -> Value {
+        Value::Object(self.to_map())
+    }
+}
+
+impl FromValue for ValuesItemObject {
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_object() {
+            Some(map) => Self::from_map(map),
+            None => Err(mapjson::type_error("Object", value)),
+        }
+    }
+}
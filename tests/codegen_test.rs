@@ -0,0 +1,82 @@
+use mapjson::{CodegenSettings, FromValue, Map, ToValue, Value, assert_json_eq, generate_rust};
+
+mod generated {
+    include!("codegen/root.rs");
+}
+
+use generated::{Owner, Root, ValuesItem, ValuesItemObject};
+
+const SAMPLES: [&str; 2] = [
+    r#"{"id":1,"name":"a","type":"user","score":2.5,"tags":["x","y"],"owner":{"login":"o","id":7},"extra":null,"values":[1,"two",{"k":true}]}"#,
+    r#"{"id":2,"name":"b","type":"admin","score":3,"tags":[],"owner":{"login":"p","id":8},"nickname":"bee","values":[]}"#,
+];
+
+fn samples() -> Vec<Map> {
+    SAMPLES
+        .iter()
+        .map(|json| {
+            let mut map = Map::new();
+            map.merge(json).unwrap();
+            map
+        })
+        .collect()
+}
+
+#[test]
+fn generated_code() {
+    // 生成的代码与编译过的tests/codegen/root.rs一致.
+    let code = generate_rust(&samples(), CodegenSettings::default());
+    assert_eq!(code, include_str!("codegen/root.rs"));
+}
+
+#[test]
+fn round_trip() {
+    let samples = samples();
+    let root = Root::from_map(&samples[0]).unwrap();
+    assert_eq!(root.r#type, "user");
+    assert_eq!(root.extra, None);
+    assert_eq!(root.nickname, None);
+    assert_eq!(
+        root.owner,
+        Owner {
+            id: 7,
+            login: "o".to_string()
+        }
+    );
+    assert_eq!(
+        root.values,
+        [
+            ValuesItem::I64(1),
+            ValuesItem::String("two".to_string()),
+            ValuesItem::Object(ValuesItemObject { k: true }),
+        ]
+    );
+    // 可能不存在的成员为None时不写回, 因此`"extra": null`被省略.
+    let mut expected = samples[0].clone();
+    expected.remove("extra");
+    assert_json_eq!(root.to_map(), expected);
+
+    let root = Root::from_value(&Value::Object(samples[1].clone())).unwrap();
+    assert_eq!(root.score, 3.0);
+    assert_eq!(root.extra, None);
+    assert_eq!(root.nickname.as_deref(), Some("bee"));
+    // 3被推断为f64, 写回时是3.0, 按数值比较相等.
+    assert_json_eq!(root.to_value(), samples[1].clone());
+}
+
+#[test]
+fn conversion_errors() {
+    let mut map = samples().remove(0);
+    map.insert("values".to_string(), Value::Vec(vec![Value::Bool(false)]));
+    assert_eq!(
+        Root::from_map(&map).unwrap_err(),
+        "/values/0: Expected I64|String|Object, found Bool"
+    );
+
+    map.remove("owner");
+    assert_eq!(Root::from_map(&map).unwrap_err(), "Missing field `owner`");
+    assert_eq!(
+        Root::from_value(&Value::Null).unwrap_err(),
+        "Expected Object, found Null"
+    );
+}
@@ -1,4 +1,10 @@
-use mapjson::{JsonReaderSettings, JsonWriterSettings, Map, Value};
+#![allow(
+    clippy::approx_constant,
+    clippy::bool_assert_comparison,
+    clippy::vec_init_then_push
+)]
+
+use mapjson::{JsonReaderSettings, JsonWriterSettings, Map, MapRef, Newline, Value};
 
 #[test]
 fn standard_format() {
@@ -24,10 +30,18 @@ fn standard_format() {
     assert_eq!(json.len(), 81);
     let settings = JsonWriterSettings {
         indentation: "  ".to_string(),
+        ..Default::default()
     };
     let json = map.to_json_with_settings(settings);
     assert_ne!(json, "");
     assert_ne!(json, "{}");
+    assert_eq!(json.len(), 134);
+    let settings = JsonWriterSettings {
+        indentation: "  ".to_string(),
+        newline: Newline::CrLf,
+        ..Default::default()
+    };
+    let json = map.to_json_with_settings(settings);
     assert_eq!(json.len(), 147);
 }
 
@@ -37,6 +51,7 @@ fn default_values_when_omitted() {
     assert_eq!(map.to_json(), "{}");
     let settings = JsonWriterSettings {
         indentation: "  ".to_string(),
+        ..Default::default()
     };
     assert_eq!(map.to_json_with_settings(settings), "{}");
 }
@@ -61,6 +76,14 @@ fn nested_format() {
 
     let settings = JsonWriterSettings {
         indentation: "  ".to_string(),
+        ..Default::default()
+    };
+    let json = map.to_json_with_settings(settings);
+    assert_eq!(json.len(), 124);
+    let settings = JsonWriterSettings {
+        indentation: "  ".to_string(),
+        newline: Newline::CrLf,
+        ..Default::default()
     };
     let json = map.to_json_with_settings(settings);
     assert_eq!(json.len(), 136);
@@ -116,9 +139,74 @@ fn nested_parse() {
 fn recursion_limit() {
     let json = r#"{"a1":{"a2":{"a3":true}}}"#;
     let mut map = Map::new();
-    let settings = JsonReaderSettings { recursion_limit: 2 };
+    let settings = JsonReaderSettings {
+        recursion_limit: 2,
+        ..Default::default()
+    };
 
     if let Err(e) = map.merge_with_settings(json, settings) {
         assert_eq!("The set recursion depth is exceeded: 2", e);
     }
 }
+
+#[test]
+fn deep_nesting_on_small_stack() {
+    let handle = std::thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(|| {
+            let depth = 200_000;
+            let arrays = format!("{{\"a\":{}{}}}", "[".repeat(depth), "]".repeat(depth));
+            let objects = format!("{}{}{}", "{\"a\":".repeat(depth), "{}", "}".repeat(depth));
+
+            for json in [arrays, objects] {
+                let settings = JsonReaderSettings {
+                    recursion_limit: usize::MAX,
+                    depth_limit: usize::MAX,
+                    ..Default::default()
+                };
+                let borrowed = MapRef::parse_with_settings(json.as_str(), settings).unwrap();
+                let map = borrowed.into_owned();
+                assert_eq!(map.to_json(), json);
+            }
+
+            // 不在任何`Map`中的值也使用显式的栈释放
+            let mut value = Value::Null;
+            for _ in 0..depth {
+                value = Value::Vec(vec![value]);
+            }
+            drop(value);
+        })
+        .unwrap();
+
+    handle.join().unwrap();
+}
+
+#[test]
+fn assert_json_eq_reports_diff() {
+    let mut actual = Map::new();
+    actual.merge(r#"{"id":1,"tags":["a","b"]}"#).unwrap();
+    let mut expected = Map::new();
+    expected.merge(r#"{"id":1.0,"tags":["a","c"]}"#).unwrap();
+
+    let result = std::panic::catch_unwind(|| {
+        mapjson::assert_json_eq!(actual, expected, "case {}", 7);
+    });
+    let report = *result.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        report,
+        concat!(
+            "assertion failed: JSON values are not equal: case 7\n",
+            "  Expected \"c\", found \"b\" at /tags/1\n",
+            "--- expected\n",
+            "+++ actual\n",
+            "  {\n",
+            "    \"id\": 1,\n",
+            "    \"tags\": [\n",
+            "      \"a\",\n",
+            "-     \"c\"\n",
+            "+     \"b\"\n",
+            "    ]\n",
+            "  }\n",
+        )
+    );
+}